@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+/// Completed requests the error rate is measured over when --abort-window
+/// isn't given
+pub const DEFAULT_WINDOW: usize = 100;
+
+/// Parses an error rate given as a percentage ("10%") or a fraction ("0.1")
+pub fn parse_error_rate(s: &str) -> Result<f64, String> {
+    let s: &str = s.trim();
+    let rate: f64 = match s.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|percent| percent / 100.0),
+        None => s.parse::<f64>(),
+    }
+    .map_err(|_| format!("invalid error rate \"{}\"", s))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("error rate \"{}\" must be between 0% and 100%", s));
+    }
+    Ok(rate)
+}
+
+/// Error rate over the most recent completed requests, for stopping a run
+/// against a service that is clearly broken
+pub struct ErrorWindow {
+    limit: f64,
+    size: usize,
+    /// Whether each request in the window failed, oldest first
+    outcomes: VecDeque<bool>,
+    failures: usize,
+    /// The window's error rate when it first went over the limit
+    tripped: Option<f64>,
+}
+
+impl ErrorWindow {
+    pub fn new(limit: f64, size: usize) -> ErrorWindow {
+        ErrorWindow {
+            limit,
+            size: size.max(1),
+            outcomes: VecDeque::with_capacity(size),
+            failures: 0,
+            tripped: None,
+        }
+    }
+
+    /// Adds a completed request; the window is only judged once it is full
+    pub fn record(&mut self, failed: bool) {
+        if self.outcomes.len() == self.size && self.outcomes.pop_front() == Some(true) {
+            self.failures -= 1;
+        }
+        self.outcomes.push_back(failed);
+        if failed {
+            self.failures += 1;
+        }
+
+        if self.tripped.is_none() && self.outcomes.len() == self.size {
+            let rate: f64 = self.failures as f64 / self.size as f64;
+            if rate > self.limit {
+                self.tripped = Some(rate);
+            }
+        }
+    }
+
+    /// The window's error rate when the limit was exceeded, if it was
+    pub fn tripped(&self) -> Option<f64> {
+        self.tripped
+    }
+
+    pub fn limit(&self) -> f64 {
+        self.limit
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
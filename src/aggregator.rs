@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use crate::dashboard::Feed;
+use crate::metrics::Metrics;
+use crate::statsd::Statsd;
+use crate::{Results, Sample};
+
+/// Samples queued for the aggregator before workers wait for it to catch
+/// up, unless --aggregator-capacity says otherwise
+pub const CAPACITY: usize = 8192;
+
+/// A finished request, how long it waited behind the --rate schedule, and
+/// the worker that sent it
+type Record = (Sample, Option<Duration>, usize);
+
+/// Sends finished requests to the aggregator; cheap to clone for every worker
+#[derive(Clone)]
+pub struct Collector {
+    records: mpsc::Sender<Record>,
+}
+
+impl Collector {
+    /// Queues the requests of a job `worker` ran, unless it ran during the
+    /// warm-up
+    pub async fn send(
+        &self,
+        worker: usize,
+        warmup: bool,
+        behind: Option<Duration>,
+        samples: impl IntoIterator<Item = Sample>,
+    ) {
+        if warmup {
+            return;
+        }
+        for (index, sample) in samples.into_iter().enumerate() {
+            // Later steps of an iteration start when the one before ends, so
+            // only the first waited for its turn
+            let waited: Option<Duration> =
+                behind.map(|behind| if index == 0 { behind } else { Duration::ZERO });
+            let _ = self.records.send((sample, waited, worker)).await;
+        }
+    }
+}
+
+/// Task that owns the results and records every request as it arrives, so
+/// tallying never holds up sending
+pub struct Aggregator {
+    task: JoinHandle<Results>,
+    /// When measuring started, moved once the warm-up is over
+    start: watch::Sender<Instant>,
+    /// Set once the results' --abort-on-error-rate window goes over its limit
+    aborted: watch::Receiver<bool>,
+}
+
+impl Aggregator {
+    /// Starts recording into `results`, printing progress out of `total`
+    /// or showing it on the dashboard's `feed`, and counting it in the
+    /// served `metrics` and sending it to `statsd`; up to `capacity`
+    /// requests queue before collectors wait
+    pub fn start(
+        mut results: Results,
+        start: Instant,
+        total: Option<usize>,
+        capacity: usize,
+        feed: Option<Arc<Mutex<Feed>>>,
+        metrics: Option<Arc<Mutex<Metrics>>>,
+        mut statsd: Option<Statsd>,
+    ) -> (Collector, Aggregator) {
+        let (records, mut queue) = mpsc::channel::<Record>(capacity.max(1));
+        let (start, mut restarted) = watch::channel(start);
+        let (abort, aborted) = watch::channel(false);
+        let task: JoinHandle<Results> = tokio::spawn(async move {
+            while let Some((sample, waited, worker)) = queue.recv().await {
+                // Requests measured after the warm-up are only sent once it
+                // has moved the start
+                if restarted.has_changed().unwrap_or(false) {
+                    let start: Instant = *restarted.borrow_and_update();
+                    if let Some(buckets) = &mut results.buckets {
+                        buckets.start = start;
+                    }
+                    if let Some(timeseries) = &mut results.timeseries {
+                        timeseries.start = start;
+                    }
+                    if let Some(feed) = &feed {
+                        feed.lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .restart(start);
+                    }
+                }
+                if let Some(feed) = &feed {
+                    feed.lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .record(&sample);
+                }
+                if let Some(metrics) = &metrics {
+                    metrics
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .record(&sample);
+                }
+                if let Some(statsd) = &mut statsd {
+                    statsd.record(&sample);
+                }
+                results.record_sample(sample, waited, worker, total);
+                if !*abort.borrow()
+                    && results
+                        .abort
+                        .as_ref()
+                        .is_some_and(|window| window.tripped().is_some())
+                {
+                    abort.send_replace(true);
+                }
+            }
+            if let Some(statsd) = &mut statsd {
+                statsd.flush();
+            }
+            results
+        });
+        (
+            Collector { records },
+            Aggregator {
+                task,
+                start,
+                aborted,
+            },
+        )
+    }
+
+    /// Measures the windows of the run from `start` on
+    pub fn restart(&self, start: Instant) {
+        self.start.send_replace(start);
+    }
+
+    /// Whether the error rate went over --abort-on-error-rate, judged as
+    /// requests reach the aggregator
+    pub fn aborted(&self) -> bool {
+        *self.aborted.borrow()
+    }
+
+    /// Returns the results once every collector is dropped and the queue
+    /// has drained
+    pub async fn finish(self) -> Results {
+        match self.task.await {
+            Ok(results) => results,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use crate::format_bytes;
+
+/// A check every response must pass to count as successful
+#[derive(Clone, Debug)]
+pub enum Assertion {
+    /// The body contains this text
+    BodyContains(String),
+    /// The body matches this regular expression
+    BodyRegex(Regex),
+    /// The header is present, with exactly this value if one is given
+    Header(HeaderName, Option<HeaderValue>),
+    /// The header is present with a value matching this regular expression
+    HeaderRegex(HeaderName, Regex),
+    /// The decoded body is at least this many bytes
+    MinSize(u64),
+    /// The decoded body is at most this many bytes
+    MaxSize(u64),
+}
+
+impl Assertion {
+    /// Short description for the results, e.g. `body contains "ok"`
+    pub fn describe(&self) -> String {
+        match self {
+            Assertion::BodyContains(text) => format!("body contains {:?}", text),
+            Assertion::BodyRegex(regex) => format!("body matches /{}/", regex.as_str()),
+            Assertion::Header(name, None) => format!("header {} present", name),
+            Assertion::Header(name, Some(value)) => {
+                format!(
+                    "header {} is {:?}",
+                    name,
+                    String::from_utf8_lossy(value.as_bytes())
+                )
+            }
+            Assertion::HeaderRegex(name, regex) => {
+                format!("header {} matches /{}/", name, regex.as_str())
+            }
+            Assertion::MinSize(size) => format!("size at least {}", format_bytes(*size)),
+            Assertion::MaxSize(size) => format!("size at most {}", format_bytes(*size)),
+        }
+    }
+
+    /// Whether checking this assertion needs the response body
+    pub fn needs_body(&self) -> bool {
+        matches!(self, Assertion::BodyContains(_) | Assertion::BodyRegex(_))
+    }
+
+    /// Checks a header assertion; other assertions pass
+    fn check_headers(&self, headers: &HeaderMap) -> bool {
+        match self {
+            Assertion::Header(name, None) => headers.contains_key(name),
+            Assertion::Header(name, Some(expected)) => headers
+                .get_all(name)
+                .iter()
+                .any(|value| value.as_bytes().trim_ascii() == expected.as_bytes()),
+            Assertion::HeaderRegex(name, regex) => headers
+                .get_all(name)
+                .iter()
+                .any(|value| regex.is_match(&String::from_utf8_lossy(value.as_bytes()))),
+            _ => true,
+        }
+    }
+
+    /// Checks a body assertion; other assertions pass
+    fn check_body(&self, body: &str) -> bool {
+        match self {
+            Assertion::BodyContains(text) => body.contains(text.as_str()),
+            Assertion::BodyRegex(regex) => regex.is_match(body),
+            _ => true,
+        }
+    }
+
+    /// Checks a size assertion; other assertions pass
+    fn check_size(&self, size: u64) -> bool {
+        match self {
+            Assertion::MinSize(min) => size >= *min,
+            Assertion::MaxSize(max) => size <= *max,
+            _ => true,
+        }
+    }
+}
+
+/// Parses a regular expression for --assert-body-regex
+pub fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| format!("invalid regular expression \"{}\": {}", s, e))
+}
+
+fn parse_header_name(name: &str) -> Result<HeaderName, String> {
+    HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| format!("invalid header name \"{}\": {}", name.trim(), e))
+}
+
+/// Parses --assert-header: "Name: value" for an exact value, or just "Name"
+/// for a header that only has to be present
+pub fn parse_header_assertion(s: &str) -> Result<Assertion, String> {
+    match s.split_once(':') {
+        Some((name, value)) => {
+            let name: HeaderName = parse_header_name(name)?;
+            let value: HeaderValue = HeaderValue::from_str(value.trim())
+                .map_err(|e| format!("invalid header value for \"{}\": {}", name, e))?;
+            Ok(Assertion::Header(name, Some(value)))
+        }
+        None => Ok(Assertion::Header(parse_header_name(s)?, None)),
+    }
+}
+
+/// Parses --assert-header-regex: "Name: regex"
+pub fn parse_header_regex(s: &str) -> Result<Assertion, String> {
+    let (name, regex) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"Name: regex\", got \"{}\"", s))?;
+    Ok(Assertion::HeaderRegex(
+        parse_header_name(name)?,
+        parse_regex(regex.trim())?,
+    ))
+}
+
+/// Returns the index of the first header assertion the response fails
+pub fn failed_header_check(assertions: &[Assertion], headers: &HeaderMap) -> Option<usize> {
+    assertions
+        .iter()
+        .position(|assertion| !assertion.check_headers(headers))
+}
+
+/// Returns the index of the first body assertion the body fails
+pub fn failed_body_check(assertions: &[Assertion], body: &[u8]) -> Option<usize> {
+    let body: std::borrow::Cow<str> = String::from_utf8_lossy(body);
+    assertions
+        .iter()
+        .position(|assertion| !assertion.check_body(&body))
+}
+
+/// Bytes before each chunk a streamed --assert-body-regex also looks at, so
+/// matches that straddle chunks are found; longer ones need --buffer-bodies
+const REGEX_OVERLAP: usize = 4096;
+
+/// Checks the body assertions as the body streams in, keeping only enough
+/// of what came before each chunk to find a match that straddles it, so
+/// memory stays flat however large the responses are
+pub struct BodyMatcher {
+    assertions: Arc<[Assertion]>,
+    /// Whether each assertion has passed yet; ones that don't check the body
+    /// pass from the start
+    passed: Vec<bool>,
+    /// The end of the body so far, with the latest chunk
+    tail: Vec<u8>,
+    /// Bytes of the tail kept before the next chunk
+    overlap: usize,
+    /// Decoded bytes seen
+    pub size: u64,
+}
+
+impl BodyMatcher {
+    pub fn new(assertions: Arc<[Assertion]>) -> BodyMatcher {
+        let overlap: usize = assertions
+            .iter()
+            .map(|assertion| match assertion {
+                Assertion::BodyContains(text) => text.len().saturating_sub(1),
+                Assertion::BodyRegex(_) => REGEX_OVERLAP,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        BodyMatcher {
+            passed: assertions
+                .iter()
+                .map(|assertion| !assertion.needs_body())
+                .collect(),
+            assertions,
+            tail: Vec::new(),
+            overlap,
+            size: 0,
+        }
+    }
+
+    /// Index of the first body assertion no part of the body passed
+    pub fn failed(&mut self) -> Option<usize> {
+        // An empty body was never checked
+        if self.size == 0 {
+            self.check();
+        }
+        self.passed.iter().position(|passed| !passed)
+    }
+
+    fn check(&mut self) {
+        let window: std::borrow::Cow<str> = String::from_utf8_lossy(&self.tail);
+        for (assertion, passed) in self.assertions.iter().zip(self.passed.iter_mut()) {
+            if !*passed {
+                *passed = assertion.check_body(&window);
+            }
+        }
+    }
+}
+
+impl Write for BodyMatcher {
+    fn write(&mut self, chunk: &[u8]) -> io::Result<usize> {
+        self.size += chunk.len() as u64;
+        // Once everything has passed the rest only needs counting
+        if self.passed.iter().all(|passed| *passed) {
+            return Ok(chunk.len());
+        }
+        self.tail.extend_from_slice(chunk);
+        self.check();
+        let keep: usize = self.overlap.min(self.tail.len());
+        self.tail.drain(..self.tail.len() - keep);
+        Ok(chunk.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the index of the first size assertion a body of this many
+/// decoded bytes fails
+pub fn failed_size_check(assertions: &[Assertion], size: u64) -> Option<usize> {
+    assertions
+        .iter()
+        .position(|assertion| !assertion.check_size(size))
+}
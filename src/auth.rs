@@ -0,0 +1,259 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::header::{AUTHORIZATION, HeaderValue};
+use reqwest::{Client, Request, RequestBuilder, Response};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::sigv4::SigV4;
+
+/// How often a token file is checked for changes
+const TOKEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fraction of an OAuth2 token's lifetime after which it is refreshed
+const TOKEN_REFRESH_AT: f64 = 0.9;
+
+/// Delay before retrying a failed OAuth2 token refresh
+const TOKEN_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Parses "USER:PASSWORD" into a basic Authorization header value
+pub fn parse_basic_auth(s: &str) -> Result<HeaderValue, String> {
+    if !s.contains(':') {
+        return Err("expected USER:PASSWORD".to_string());
+    }
+
+    let mut value: HeaderValue = HeaderValue::from_str(&format!("Basic {}", STANDARD.encode(s)))
+        .map_err(|e| format!("invalid credentials: {}", e))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Builds a bearer Authorization header value from a token
+pub fn bearer_header(token: &str) -> Result<HeaderValue, String> {
+    let token: &str = token.trim();
+    if token.is_empty() {
+        return Err("token is empty".to_string());
+    }
+
+    let mut value: HeaderValue = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|e| format!("invalid token: {}", e))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Authorization that changes during a run and is applied to every request
+pub enum Auth {
+    BearerFile(TokenFile),
+    OAuth2(OAuth2),
+    SigV4(SigV4),
+}
+
+impl Auth {
+    pub async fn apply(&self, request: &mut Request) {
+        match self {
+            Auth::BearerFile(token_file) => {
+                request
+                    .headers_mut()
+                    .insert(AUTHORIZATION, token_file.header());
+            }
+            Auth::OAuth2(oauth2) => {
+                let value: HeaderValue = oauth2.header().await;
+                request.headers_mut().insert(AUTHORIZATION, value);
+            }
+            Auth::SigV4(sigv4) => sigv4.sign(request),
+        }
+    }
+}
+
+/// Builds a request with `auth` applied, ready to send
+pub async fn build(
+    request: RequestBuilder,
+    auth: Option<&Auth>,
+) -> Result<Request, reqwest::Error> {
+    let mut request: Request = request.build()?;
+    if let Some(auth) = auth {
+        auth.apply(&mut request).await;
+    }
+    Ok(request)
+}
+
+/// Bearer token read from a file, re-read whenever the file changes so long
+/// runs survive token rotation
+pub struct TokenFile {
+    path: PathBuf,
+    state: Mutex<TokenState>,
+}
+
+struct TokenState {
+    value: HeaderValue,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+impl TokenFile {
+    pub fn load(path: &str) -> Result<TokenFile, String> {
+        let path: PathBuf = PathBuf::from(path);
+        let (value, modified) = read_token(&path)?;
+        Ok(TokenFile {
+            path,
+            state: Mutex::new(TokenState {
+                value,
+                modified,
+                checked: Instant::now(),
+            }),
+        })
+    }
+
+    /// Returns the current token, checking the file for changes at most
+    /// once per `TOKEN_CHECK_INTERVAL`
+    fn header(&self) -> HeaderValue {
+        let mut state: MutexGuard<TokenState> =
+            self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if state.checked.elapsed() >= TOKEN_CHECK_INTERVAL {
+            state.checked = Instant::now();
+            let modified: Option<SystemTime> =
+                fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+            // Keep the previous token if the file is mid-rewrite or unreadable
+            if modified != state.modified
+                && let Ok((value, modified)) = read_token(&self.path)
+            {
+                state.value = value;
+                state.modified = modified;
+            }
+        }
+
+        state.value.clone()
+    }
+}
+
+fn read_token(path: &Path) -> Result<(HeaderValue, Option<SystemTime>), String> {
+    let modified: Option<SystemTime> = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let token: String = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let value: HeaderValue =
+        bearer_header(&token).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((value, modified))
+}
+
+/// Settings for the OAuth2 client credentials flow
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct OAuth2Config {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+/// Access token from an OAuth2 token endpoint (client credentials flow).
+///
+/// Tokens that report an `expires_in` are refreshed by the first request
+/// made after `TOKEN_REFRESH_AT` of their lifetime, while other requests
+/// keep using the current token.
+pub struct OAuth2 {
+    client: Client,
+    config: OAuth2Config,
+    token: Mutex<OAuth2Token>,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+struct OAuth2Token {
+    value: HeaderValue,
+    refresh_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<f64>,
+}
+
+impl OAuth2 {
+    /// Fetches the first access token
+    pub async fn connect(client: Client, config: OAuth2Config) -> Result<OAuth2, String> {
+        let token: OAuth2Token = fetch_token(&client, &config).await?;
+        Ok(OAuth2 {
+            client,
+            config,
+            token: Mutex::new(token),
+            refreshing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    fn current(&self) -> (HeaderValue, bool) {
+        let token: MutexGuard<OAuth2Token> = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        let expiring: bool = token
+            .refresh_at
+            .is_some_and(|refresh_at| Instant::now() >= refresh_at);
+        (token.value.clone(), expiring)
+    }
+
+    async fn header(&self) -> HeaderValue {
+        let (value, expiring) = self.current();
+        if !expiring {
+            return value;
+        }
+
+        // Only one request refreshes; the rest wait and reuse its token
+        let _refreshing: tokio::sync::MutexGuard<()> = self.refreshing.lock().await;
+        let (value, expiring) = self.current();
+        if !expiring {
+            return value;
+        }
+
+        let refreshed: Result<OAuth2Token, String> = fetch_token(&self.client, &self.config).await;
+        let mut token: MutexGuard<OAuth2Token> =
+            self.token.lock().unwrap_or_else(|e| e.into_inner());
+        match refreshed {
+            Ok(refreshed) => *token = refreshed,
+            // Keep the current token and try again shortly
+            Err(_) => token.refresh_at = Some(Instant::now() + TOKEN_RETRY_INTERVAL),
+        }
+        token.value.clone()
+    }
+}
+
+async fn fetch_token(client: &Client, config: &OAuth2Config) -> Result<OAuth2Token, String> {
+    let mut form: Vec<(&str, &str)> = vec![("grant_type", "client_credentials")];
+    if let Some(scope) = &config.scope {
+        form.push(("scope", scope));
+    }
+
+    let requested: Instant = Instant::now();
+    let response: Response = client
+        .post(&config.token_url)
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("request to {} failed: {}", config.token_url, e))?;
+    let status: reqwest::StatusCode = response.status();
+    let body: bytes::Bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("failed to read token response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!(
+            "{} returned {}: {}",
+            config.token_url,
+            status,
+            String::from_utf8_lossy(&body).trim()
+        ));
+    }
+
+    let token: TokenResponse =
+        serde_json::from_slice(&body).map_err(|e| format!("invalid token response: {}", e))?;
+    Ok(OAuth2Token {
+        value: bearer_header(&token.access_token)?,
+        // A lifetime too long to represent is as good as one that never ends
+        refresh_at: token.expires_in.and_then(|expires_in| {
+            Duration::try_from_secs_f64(expires_in.max(0.0) * TOKEN_REFRESH_AT)
+                .ok()
+                .and_then(|lifetime| requested.checked_add(lifetime))
+        }),
+    })
+}
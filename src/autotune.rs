@@ -0,0 +1,534 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::abort::parse_error_rate;
+use crate::compression;
+use crate::latencies::Latencies;
+use crate::rate::{self, Arrival, RateLimiter};
+use crate::thresholds::{self, Threshold};
+use crate::{LatencyStats, parse_duration, parse_header, parse_method, parse_percent};
+
+/// Step up concurrency to find where throughput stops growing, or search for
+/// the highest rate that meets an SLO
+#[derive(clap::Args, Debug)]
+pub struct AutotuneCommand {
+    /// URL to test (must include http:// or https://)
+    #[arg(value_name = "URL")]
+    url: String,
+
+    /// Concurrency of the first step
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    start: usize,
+
+    /// Highest concurrency to try; with --find-max-rate, the most requests
+    /// in flight, beyond which requests that come due count as failed
+    #[arg(long, value_name = "N", default_value_t = 1024)]
+    max: usize,
+
+    /// Multiply the concurrency by this after each step
+    #[arg(long, value_name = "FACTOR", default_value_t = 2.0, value_parser = parse_growth)]
+    growth: f64,
+
+    /// How long to hold each concurrency level or probe each rate, e.g. 10s
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "10s")]
+    step_duration: Duration,
+
+    /// Stop once a step raises throughput by less than this over the best
+    /// step so far, e.g. "5%"
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent, default_value = "5%")]
+    plateau: f64,
+
+    /// Stop once more than this share of a step's requests fail, e.g. "1%"
+    #[arg(long, value_name = "RATE", value_parser = parse_error_rate, default_value = "1%")]
+    max_error_rate: f64,
+
+    /// Instead of stepping concurrency, search for the highest request rate
+    /// at which every --slo holds
+    #[arg(long, requires = "slo", conflicts_with_all = ["start", "growth", "plateau"])]
+    find_max_rate: bool,
+
+    /// Condition a probed rate must meet, e.g. "p99<200ms" (repeatable; same
+    /// syntax as --threshold)
+    #[arg(long, value_name = "EXPR", value_parser = thresholds::parse_threshold, requires = "find_max_rate")]
+    slo: Vec<Threshold>,
+
+    /// Lowest rate to probe, in requests per second
+    #[arg(long, value_name = "RPS", default_value_t = 10.0, value_parser = rate::parse_rate, requires = "find_max_rate")]
+    min_rate: f64,
+
+    /// Highest rate to probe, in requests per second
+    #[arg(long, value_name = "RPS", default_value_t = 100_000.0, value_parser = rate::parse_rate, requires = "find_max_rate")]
+    max_rate: f64,
+
+    /// Stop searching once the passing and failing rates are this close,
+    /// e.g. "5%"
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent, default_value = "5%", requires = "find_max_rate")]
+    precision: f64,
+
+    /// HTTP method to use
+    #[arg(short = 'm', long, value_name = "METHOD", value_parser = parse_method, default_value = "GET")]
+    method: Method,
+
+    /// Add a header to every request, e.g. -H "Authorization: Bearer abc" (repeatable)
+    #[arg(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Total time allowed per request, e.g. 500ms, 10s (default: no timeout)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Skip TLS certificate verification (for self-signed staging certificates)
+    #[arg(short = 'k', long)]
+    insecure: bool,
+
+    /// Output file path for JSON report (optional)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    output: Option<String>,
+}
+
+fn parse_growth(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(growth) if growth > 1.0 && growth.is_finite() => Ok(growth),
+        _ => Err(format!("growth \"{}\" must be a number above 1", s)),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AutotuneReport {
+    url: String,
+    date: DateTime<Utc>,
+    steps: Vec<StepResult>,
+    /// Why the search stopped: "plateau", "errors" or "max"
+    stop_reason: String,
+    /// The lowest concurrency whose throughput came within --plateau of the
+    /// best step without too many errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    knee: Option<StepResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RateSearchReport {
+    url: String,
+    date: DateTime<Utc>,
+    slo: Vec<String>,
+    rounds: Vec<RoundResult>,
+    /// The highest probed rate that met every SLO
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_rate: Option<f64>,
+}
+
+/// Results of probing one request rate
+#[derive(Serialize, Deserialize, Debug)]
+struct RoundResult {
+    target_rps: f64,
+    requests: usize,
+    /// Requests that got an error or a status other than 2xx, or came due
+    /// with --max requests in flight
+    failed: usize,
+    error_rate: f64,
+    requests_per_sec: f64,
+    latency: LatencyStats,
+    passed: bool,
+}
+
+/// Results of holding one concurrency level
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StepResult {
+    concurrency: usize,
+    requests: usize,
+    /// Requests that got an error or a status other than 2xx
+    failed: usize,
+    error_rate: f64,
+    requests_per_sec: f64,
+    latency: LatencyStats,
+}
+
+/// Requests one worker finished before the step ended
+#[derive(Default)]
+struct WorkerStats {
+    failed: usize,
+    latencies: Latencies,
+}
+
+/// Sends requests back to back until `deadline`; a request still in flight
+/// then isn't counted
+async fn work(client: Client, method: Method, url: String, deadline: Instant) -> WorkerStats {
+    let mut stats: WorkerStats = WorkerStats::default();
+    loop {
+        let start: Instant = Instant::now();
+        let request = async {
+            let response: reqwest::Response = client.request(method.clone(), &url).send().await?;
+            let success: bool = response.status().is_success();
+            compression::drain(response).await?;
+            Ok::<bool, reqwest::Error>(success)
+        };
+        match tokio::time::timeout_at(deadline, request).await {
+            Err(_) => return stats,
+            Ok(success) => {
+                if success.is_ok_and(|success| success) {
+                    print!(".");
+                } else {
+                    print!("F");
+                    stats.failed += 1;
+                }
+                stats.latencies.record(start.elapsed());
+            }
+        }
+    }
+}
+
+/// Holds `concurrency` requests in flight for `duration`
+async fn run_step(command: &AutotuneCommand, client: &Client, concurrency: usize) -> StepResult {
+    let start: Instant = Instant::now();
+    let deadline: Instant = start + command.step_duration;
+    let mut tasks: JoinSet<WorkerStats> = JoinSet::new();
+    for _ in 0..concurrency {
+        tasks.spawn(work(
+            client.clone(),
+            command.method.clone(),
+            command.url.clone(),
+            deadline,
+        ));
+    }
+
+    let mut totals: WorkerStats = WorkerStats::default();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(stats) = result {
+            totals.failed += stats.failed;
+            totals.latencies.merge(&stats.latencies);
+        }
+    }
+
+    let requests: usize = totals.latencies.len();
+    StepResult {
+        concurrency,
+        requests,
+        failed: totals.failed,
+        error_rate: if requests == 0 {
+            0.0
+        } else {
+            totals.failed as f64 / requests as f64
+        },
+        requests_per_sec: requests as f64 / start.elapsed().as_secs_f64(),
+        latency: LatencyStats::from_latencies(&mut totals.latencies).unwrap_or_default(),
+    }
+}
+
+/// Sends requests at `rate` for --step-duration, then waits up to as long
+/// again for the ones still in flight; those that don't finish count as failed
+async fn probe_rate(command: &AutotuneCommand, client: &Client, rate: f64) -> RoundResult {
+    let start: Instant = Instant::now();
+    let deadline: Instant = start + command.step_duration;
+    let mut limiter: RateLimiter = RateLimiter::new(rate, Arrival::Uniform);
+    let mut tasks: JoinSet<Option<Duration>> = JoinSet::new();
+    let mut results: Vec<Option<Duration>> = Vec::new();
+    let mut dropped: usize = 0;
+
+    loop {
+        limiter.acquire().await;
+        if Instant::now() >= deadline {
+            break;
+        }
+        while let Some(result) = tasks.try_join_next() {
+            results.extend(result.ok());
+        }
+        if tasks.len() >= command.max {
+            print!("F");
+            dropped += 1;
+            continue;
+        }
+        let client: Client = client.clone();
+        let method: Method = command.method.clone();
+        let url: String = command.url.clone();
+        tasks.spawn(async move {
+            let sent: Instant = Instant::now();
+            let response: reqwest::Response = client.request(method, &url).send().await.ok()?;
+            let success: bool = response.status().is_success();
+            compression::drain(response).await.ok()?;
+            success.then(|| sent.elapsed())
+        });
+    }
+    let elapsed: Duration = start.elapsed();
+
+    let _ = tokio::time::timeout(command.step_duration, async {
+        while let Some(result) = tasks.join_next().await {
+            results.extend(result.ok());
+        }
+    })
+    .await;
+    let unfinished: usize = tasks.len();
+    tasks.abort_all();
+
+    let mut latencies: Latencies = Latencies::default();
+    let mut failed: usize = dropped + unfinished;
+    for result in results {
+        match result {
+            Some(duration) => {
+                print!(".");
+                latencies.record(duration);
+            }
+            None => {
+                print!("F");
+                failed += 1;
+            }
+        }
+    }
+
+    let requests: usize = latencies.len() + failed;
+    let error_rate: f64 = if requests == 0 {
+        0.0
+    } else {
+        failed as f64 / requests as f64
+    };
+    let requests_per_sec: f64 = requests as f64 / elapsed.as_secs_f64();
+    let latency: LatencyStats = LatencyStats::from_latencies(&mut latencies).unwrap_or_default();
+    let passed: bool = error_rate <= command.max_error_rate
+        && command
+            .slo
+            .iter()
+            .all(|slo| slo.check(&latency, error_rate, requests_per_sec).1);
+    RoundResult {
+        target_rps: rate,
+        requests,
+        failed,
+        error_rate,
+        requests_per_sec,
+        latency,
+        passed,
+    }
+}
+
+/// Runs the search, exiting with status 1 if no step or rate passed
+pub async fn run(command: AutotuneCommand) {
+    if command.start == 0 || command.start > command.max {
+        eprintln!("✗ --start must be at least 1 and no more than --max");
+        std::process::exit(1);
+    }
+    if command.min_rate > command.max_rate {
+        eprintln!("✗ --min-rate can't be higher than --max-rate");
+        std::process::exit(1);
+    }
+    let mut headers: HeaderMap = HeaderMap::new();
+    for (name, value) in &command.headers {
+        headers.append(name.clone(), value.clone());
+    }
+    let mut builder: reqwest::ClientBuilder = Client::builder().default_headers(headers);
+    if let Some(timeout) = command.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if command.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let client: Client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("✗ Failed to build HTTP client: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if command.find_max_rate {
+        find_max_rate(&command, &client).await;
+    } else {
+        find_knee(&command, &client).await;
+    }
+}
+
+/// Doubles the rate from --min-rate until a round breaks an SLO, then
+/// bisects between the last passing and first failing rates
+async fn find_max_rate(command: &AutotuneCommand, client: &Client) {
+    let slo: Vec<String> = command
+        .slo
+        .iter()
+        .map(|slo| slo.expression.clone())
+        .collect();
+    println!("Finding max rate: {} {}", command.method, command.url);
+    println!(
+        "SLO: {}, at most {:.2}% failed",
+        slo.join(", "),
+        command.max_error_rate * 100.0
+    );
+    println!(
+        "Rates: {} to {} req/s, {:.2}s per round",
+        command.min_rate,
+        command.max_rate,
+        command.step_duration.as_secs_f64()
+    );
+
+    let mut rounds: Vec<RoundResult> = Vec::new();
+    // Highest rate known to pass and lowest known to fail
+    let mut passing: Option<f64> = None;
+    let mut failing: Option<f64> = None;
+    let mut rate: f64 = command.min_rate;
+    loop {
+        println!("\n{:.2} req/s:", rate);
+        let round: RoundResult = probe_rate(command, client, rate).await;
+        println!(
+            "\n  {:.2} req/s, p50 {:.2}ms, p99 {:.2}ms, {:.2}% failed: {}",
+            round.requests_per_sec,
+            round.latency.p50_ms,
+            round.latency.p99_ms,
+            round.error_rate * 100.0,
+            if round.passed { "pass" } else { "fail" }
+        );
+        if round.passed {
+            passing = Some(rate);
+        } else {
+            failing = Some(rate);
+        }
+        rounds.push(round);
+
+        rate = match (passing, failing) {
+            (None, Some(_)) => break,
+            (Some(low), None) if low >= command.max_rate => break,
+            (Some(low), None) => (low * 2.0).min(command.max_rate),
+            (Some(low), Some(high)) if high - low <= low * command.precision => break,
+            (Some(low), Some(high)) => (low + high) / 2.0,
+            (None, None) => unreachable!("every round passes or fails"),
+        };
+    }
+
+    println!("\n\nResults:");
+    println!("========");
+    for round in &rounds {
+        println!(
+            "  {:.2} req/s: p99 {:.2}ms, {:.2}% failed, {}",
+            round.target_rps,
+            round.latency.p99_ms,
+            round.error_rate * 100.0,
+            if round.passed { "pass" } else { "fail" }
+        );
+    }
+    match passing {
+        Some(rate) if failing.is_none() => {
+            println!(
+                "Max rate: {:.2} req/s (--max-rate; the SLO held throughout)",
+                rate
+            )
+        }
+        Some(rate) => println!("Max rate: {:.2} req/s", rate),
+        None => println!("Max rate: none; the SLO failed at --min-rate"),
+    }
+
+    let report: RateSearchReport = RateSearchReport {
+        url: command.url.clone(),
+        date: Utc::now(),
+        slo,
+        rounds,
+        max_rate: passing,
+    };
+    save_report(command, &report);
+    if report.max_rate.is_none() {
+        std::process::exit(1);
+    }
+}
+
+/// Steps concurrency up until throughput plateaus or errors appear
+async fn find_knee(command: &AutotuneCommand, client: &Client) {
+    println!("Tuning concurrency: {} {}", command.method, command.url);
+    println!(
+        "Steps: {} to {}, x{} every {:.2}s",
+        command.start,
+        command.max,
+        command.growth,
+        command.step_duration.as_secs_f64()
+    );
+    println!(
+        "Stops when throughput grows less than {:.2}% or more than {:.2}% of requests fail",
+        command.plateau * 100.0,
+        command.max_error_rate * 100.0
+    );
+
+    let mut steps: Vec<StepResult> = Vec::new();
+    let mut best_rps: f64 = 0.0;
+    let mut concurrency: usize = command.start;
+    let stop_reason: &str = loop {
+        println!("\nConcurrency {}:", concurrency);
+        let step: StepResult = run_step(command, client, concurrency).await;
+        println!(
+            "\n  {:.2} req/s, p50 {:.2}ms, p95 {:.2}ms, {:.2}% failed",
+            step.requests_per_sec,
+            step.latency.p50_ms,
+            step.latency.p95_ms,
+            step.error_rate * 100.0
+        );
+
+        let failing: bool = step.error_rate > command.max_error_rate;
+        let plateaued: bool =
+            !steps.is_empty() && step.requests_per_sec < best_rps * (1.0 + command.plateau);
+        if !failing {
+            best_rps = best_rps.max(step.requests_per_sec);
+        }
+        steps.push(step);
+        if failing {
+            break "errors";
+        }
+        if plateaued {
+            break "plateau";
+        }
+        if concurrency >= command.max {
+            break "max";
+        }
+        concurrency = ((concurrency as f64 * command.growth).round() as usize)
+            .max(concurrency + 1)
+            .min(command.max);
+    };
+
+    // Past the knee, more concurrency only adds latency
+    let knee: Option<StepResult> = steps
+        .iter()
+        .filter(|step| step.error_rate <= command.max_error_rate)
+        .find(|step| step.requests_per_sec >= best_rps * (1.0 - command.plateau))
+        .cloned();
+
+    println!("\n\nResults:");
+    println!("========");
+    for step in &steps {
+        println!(
+            "  c={}: {:.2} req/s, p95 {:.2}ms, {:.2}% failed",
+            step.concurrency,
+            step.requests_per_sec,
+            step.latency.p95_ms,
+            step.error_rate * 100.0
+        );
+    }
+    match stop_reason {
+        "errors" => println!("Stopped: error rate went over the limit"),
+        "plateau" => println!("Stopped: throughput plateaued"),
+        _ => println!("Stopped: reached --max"),
+    }
+    match &knee {
+        Some(knee) => println!(
+            "Knee: concurrency {} ({:.2} req/s, p95 {:.2}ms)",
+            knee.concurrency, knee.requests_per_sec, knee.latency.p95_ms
+        ),
+        None => println!("Knee: none; every step failed too many requests"),
+    }
+
+    let report: AutotuneReport = AutotuneReport {
+        url: command.url.clone(),
+        date: Utc::now(),
+        steps,
+        stop_reason: stop_reason.to_string(),
+        knee,
+    };
+    save_report(command, &report);
+    if report.knee.is_none() {
+        std::process::exit(1);
+    }
+}
+
+fn save_report<T: Serialize>(command: &AutotuneCommand, report: &T) {
+    if let Some(output_path) = &command.output {
+        match fs::write(output_path, serde_json::to_string_pretty(report).unwrap()) {
+            Ok(_) => println!("\n✓ Report saved to: {}", output_path),
+            Err(e) => eprintln!("\n✗ Failed to save report: {}", e),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use crate::thresholds::{METRICS, Metric};
+use crate::{BaselineCheck, Report, parse_percent};
+
+/// How far the metrics may get worse when --baseline is given without
+/// --max-regression
+pub const DEFAULT_MAX_REGRESSIONS: [&str; 5] =
+    ["rps:10%", "p50:10%", "p95:10%", "p99:10%", "error_rate:1%"];
+
+/// How far one metric may get worse than the baseline, e.g. "p95:10%"
+#[derive(Clone, Debug)]
+pub struct MaxRegression {
+    pub expression: String,
+    metric: Metric,
+    /// A fraction of the baseline's value, or percentage points of the
+    /// error rate
+    tolerance: f64,
+}
+
+/// Parses "METRIC:PERCENT", with the metrics of --threshold
+pub fn parse_max_regression(s: &str) -> Result<MaxRegression, String> {
+    let expression: String = s.split_whitespace().collect();
+    let (name, tolerance) = expression
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"METRIC:PERCENT\", got \"{}\"", s))?;
+    let metric: Metric = Metric::parse(name)
+        .ok_or_else(|| format!("unknown metric \"{}\" (use {})", name, METRICS))?;
+    let tolerance: f64 = parse_percent(tolerance)?;
+    Ok(MaxRegression {
+        expression,
+        metric,
+        tolerance,
+    })
+}
+
+impl MaxRegression {
+    /// Compares the metric of `report` with the baseline's
+    pub fn check(&self, baseline: &Report, report: &Report) -> BaselineCheck {
+        let (expected, actual) = (self.metric.of(baseline), self.metric.of(report));
+        // The error rate is often zero in the baseline, so it is compared in
+        // percentage points rather than relative to it
+        let change: f64 = match self.metric {
+            Metric::ErrorRate => actual - expected,
+            _ if expected > 0.0 => (actual - expected) / expected,
+            _ => 0.0,
+        };
+        let worse: f64 = match self.metric {
+            Metric::Rps => -change,
+            _ => change,
+        };
+        BaselineCheck {
+            max_regression: self.expression.clone(),
+            baseline: expected,
+            actual,
+            change,
+            passed: worse <= self.tolerance,
+        }
+    }
+
+    /// Describes a check of this metric, e.g. "63.62ms vs 60.06ms, +5.9%"
+    pub fn describe(&self, check: &BaselineCheck) -> String {
+        let change: String = match self.metric {
+            Metric::ErrorRate => format!("{:+.2} pts", check.change * 100.0),
+            _ => format!("{:+.1}%", check.change * 100.0),
+        };
+        format!(
+            "{} vs {}, {}",
+            self.metric.format(check.actual),
+            self.metric.format(check.baseline),
+            change
+        )
+    }
+}
+
+/// Reads a JSON report saved with -o
+pub fn load(path: &str) -> Result<Report, String> {
+    let json: String = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
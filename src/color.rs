@@ -0,0 +1,54 @@
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set when the summary is colored
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Colors what is printed from here on if stdout is a terminal, unless
+/// --no-color or the NO_COLOR environment variable turns it off
+pub fn init(no_color: bool) {
+    let no_color: bool = no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    ENABLED.store(!no_color && io::stdout().is_terminal(), Ordering::Relaxed);
+}
+
+pub fn green(text: impl Display) -> String {
+    paint("32", text)
+}
+
+pub fn yellow(text: impl Display) -> String {
+    paint("33", text)
+}
+
+pub fn red(text: impl Display) -> String {
+    paint("31", text)
+}
+
+/// Bold red, for what made the run fail
+pub fn alert(text: impl Display) -> String {
+    paint("1;31", text)
+}
+
+/// Green for passing, red for failing
+pub fn verdict(ok: bool, text: impl Display) -> String {
+    if ok { green(text) } else { alert(text) }
+}
+
+/// Colors a status code by its class: green for 2xx, yellow for 3xx, red
+/// for errors
+pub fn status(status: u16) -> String {
+    match status {
+        200..=299 => green(status),
+        300..=399 => yellow(status),
+        400.. => red(status),
+        _ => status.to_string(),
+    }
+}
+
+fn paint(code: &str, text: impl Display) -> String {
+    if ENABLED.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
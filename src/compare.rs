@@ -0,0 +1,196 @@
+use crate::{Report, baseline, color, parse_percent};
+
+/// Compare two JSON reports
+#[derive(clap::Args, Debug)]
+pub struct CompareCommand {
+    /// Report of the earlier run, saved with -o
+    #[arg(value_name = "OLD")]
+    old: String,
+
+    /// Report of the run to check against it
+    #[arg(value_name = "NEW")]
+    new: String,
+
+    /// Largest drop in requests per second that isn't a regression
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent, default_value = "10%")]
+    rps_tolerance: f64,
+
+    /// Largest rise in p50, p95, p99 or any --percentiles latency that isn't
+    /// a regression
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent, default_value = "10%")]
+    latency_tolerance: f64,
+
+    /// Largest rise in the error rate, in percentage points, that isn't a
+    /// regression
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent, default_value = "1%")]
+    error_rate_tolerance: f64,
+}
+
+/// One metric of both reports
+struct Row {
+    name: String,
+    old: f64,
+    new: f64,
+    /// How a value is printed, e.g. "12.30ms"
+    format: fn(f64) -> String,
+    /// The change, as printed
+    change: String,
+    regressed: bool,
+}
+
+pub fn run(command: CompareCommand) {
+    color::init(false);
+    let old: Report = load(&command.old);
+    let new: Report = load(&command.new);
+
+    let mut rows: Vec<Row> = Vec::new();
+    let (old_rps, new_rps) = (old.requests_per_sec, new.requests_per_sec);
+    rows.push(Row {
+        name: "Requests/sec".to_string(),
+        old: old_rps,
+        new: new_rps,
+        format: |rps| format!("{:.2}", rps),
+        change: relative(old_rps, new_rps),
+        regressed: old_rps > 0.0 && (new_rps - old_rps) / old_rps < -command.rps_tolerance,
+    });
+
+    let (old_rate, new_rate) = (error_rate(&old, old.failed), error_rate(&new, new.failed));
+    rows.push(Row {
+        name: "Error rate".to_string(),
+        old: old_rate,
+        new: new_rate,
+        format: percent,
+        change: points(old_rate, new_rate),
+        regressed: new_rate - old_rate > command.error_rate_tolerance,
+    });
+    // Each kind of failure either run had, for where a change came from
+    for ((name, old_count), (_, new_count)) in
+        old.error_counts().into_iter().zip(new.error_counts())
+    {
+        if old_count == 0 && new_count == 0 {
+            continue;
+        }
+        let (old_rate, new_rate) = (error_rate(&old, old_count), error_rate(&new, new_count));
+        rows.push(Row {
+            name: format!("  {}", name),
+            old: old_rate,
+            new: new_rate,
+            format: percent,
+            change: points(old_rate, new_rate),
+            regressed: false,
+        });
+    }
+
+    let (before, after) = (&old.latency, &new.latency);
+    let checked: Option<f64> = Some(command.latency_tolerance);
+    rows.push(latency_row("avg", before.avg_ms, after.avg_ms, None));
+    rows.push(latency_row("p50", before.p50_ms, after.p50_ms, checked));
+    rows.push(latency_row("p95", before.p95_ms, after.p95_ms, checked));
+    rows.push(latency_row("p99", before.p99_ms, after.p99_ms, checked));
+    // --percentiles of the new run that the old one reported too
+    for stat in &after.percentiles {
+        if let Some(earlier) = before
+            .percentiles
+            .iter()
+            .find(|earlier| earlier.percentile == stat.percentile)
+            && ![50.0, 95.0, 99.0].contains(&stat.percentile)
+        {
+            let name: String = format!("p{}", stat.percentile);
+            rows.push(latency_row(&name, earlier.ms, stat.ms, checked));
+        }
+    }
+    rows.push(latency_row("max", before.max_ms, after.max_ms, None));
+
+    println!("Comparing {} with {}:", command.old, command.new);
+    println!(
+        "\n  {:<20}  {:>12}  {:>12}  {:>12}",
+        "", "Old", "New", "Change"
+    );
+    for row in &rows {
+        let line: String = format!(
+            "  {:<20}  {:>12}  {:>12}  {:>12}",
+            row.name,
+            (row.format)(row.old),
+            (row.format)(row.new),
+            row.change
+        );
+        if row.regressed {
+            println!("{}", color::alert(format!("{}  ⚠", line)));
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    let regressions: Vec<&str> = rows
+        .iter()
+        .filter(|row| row.regressed)
+        .map(|row| row.name.as_str())
+        .collect();
+    if regressions.is_empty() {
+        println!(
+            "\n{} No regressions beyond the tolerances",
+            color::green("✓")
+        );
+    } else {
+        println!(
+            "\n{}",
+            color::alert(format!(
+                "✗ Regressed beyond the tolerances: {}",
+                regressions.join(", ")
+            ))
+        );
+        std::process::exit(1);
+    }
+}
+
+/// A latency row, a regression when it rose by more than `tolerance`
+fn latency_row(name: &str, old_ms: f64, new_ms: f64, tolerance: Option<f64>) -> Row {
+    Row {
+        name: format!("Latency {}", name),
+        old: old_ms,
+        new: new_ms,
+        format: |ms| format!("{:.2}ms", ms),
+        change: relative(old_ms, new_ms),
+        regressed: tolerance
+            .is_some_and(|tolerance| old_ms > 0.0 && (new_ms - old_ms) / old_ms > tolerance),
+    }
+}
+
+fn load(path: &str) -> Report {
+    match baseline::load(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("✗ Failed to read report {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Fraction of the report's requests that `count` is
+fn error_rate(report: &Report, count: usize) -> f64 {
+    if report.total_requests == 0 {
+        0.0
+    } else {
+        count as f64 / report.total_requests as f64
+    }
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.2}%", rate * 100.0)
+}
+
+/// The change from `old` to `new` as a percentage of `old`
+fn relative(old: f64, new: f64) -> String {
+    if old > 0.0 {
+        format!("{:+.1}%", (new - old) / old * 100.0)
+    } else if new > 0.0 {
+        "new".to_string()
+    } else {
+        "-".to_string()
+    }
+}
+
+/// The change between two rates, in percentage points
+fn points(old: f64, new: f64) -> String {
+    format!("{:+.2} pts", (new - old) * 100.0)
+}
@@ -0,0 +1,156 @@
+use clap::ValueEnum;
+use reqwest::Response;
+use reqwest::header::CONTENT_ENCODING;
+use serde::Deserialize;
+use std::io::{self, Write};
+
+/// Response encoding to ask for with Accept-Encoding
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Br,
+    Zstd,
+    /// Ask for uncompressed responses
+    None,
+}
+
+impl Compression {
+    pub fn accept_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Br => "br",
+            Compression::Zstd => "zstd",
+            Compression::None => "identity",
+        }
+    }
+}
+
+/// Encoding applied to request bodies with --compress-body
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyEncoding {
+    Gzip,
+}
+
+impl BodyEncoding {
+    pub fn content_encoding(self) -> &'static str {
+        match self {
+            BodyEncoding::Gzip => "gzip",
+        }
+    }
+
+    pub fn encode(self, body: &[u8]) -> Vec<u8> {
+        match self {
+            BodyEncoding::Gzip => {
+                let mut encoder: flate2::write::GzEncoder<Vec<u8>> =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                // Writing to a Vec can't fail
+                let _ = encoder.write_all(body);
+                encoder.finish().unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Discards written bytes, counting them
+#[derive(Default)]
+pub struct Counter(pub u64);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Body that couldn't be read, or whose encoding was corrupt
+pub enum BodyError {
+    Read(reqwest::Error),
+    Decode,
+}
+
+/// Decompresses into `W` according to the response's Content-Encoding
+enum Decoder<W: Write> {
+    Identity(W),
+    Gzip(flate2::write::GzDecoder<W>),
+    Deflate(flate2::write::ZlibDecoder<W>),
+    Brotli(Box<brotli_decompressor::DecompressorWriter<W>>),
+    Zstd(zstd::stream::write::Decoder<'static, W>),
+}
+
+impl<W: Write> Decoder<W> {
+    fn new(encoding: &str, sink: W) -> io::Result<Decoder<W>> {
+        Ok(match encoding.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Decoder::Gzip(flate2::write::GzDecoder::new(sink)),
+            "deflate" => Decoder::Deflate(flate2::write::ZlibDecoder::new(sink)),
+            "br" => Decoder::Brotli(Box::new(brotli_decompressor::DecompressorWriter::new(
+                sink, 4096,
+            ))),
+            "zstd" => Decoder::Zstd(zstd::stream::write::Decoder::new(sink)?),
+            _ => Decoder::Identity(sink),
+        })
+    }
+
+    fn write_all(&mut self, chunk: &[u8]) -> io::Result<()> {
+        match self {
+            Decoder::Identity(sink) => sink.write_all(chunk),
+            Decoder::Gzip(decoder) => decoder.write_all(chunk),
+            Decoder::Deflate(decoder) => decoder.write_all(chunk),
+            Decoder::Brotli(decoder) => decoder.write_all(chunk),
+            Decoder::Zstd(decoder) => decoder.write_all(chunk),
+        }
+    }
+
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Decoder::Identity(sink) => Ok(sink),
+            Decoder::Gzip(decoder) => decoder.finish(),
+            Decoder::Deflate(decoder) => decoder.finish(),
+            Decoder::Brotli(mut decoder) => {
+                decoder.close()?;
+                decoder
+                    .into_inner()
+                    .map_err(|_| io::Error::other("truncated brotli stream"))
+            }
+            Decoder::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+        }
+    }
+}
+
+/// Reads the whole body chunk by chunk, decompressing it into `sink`, and
+/// returns the number of bytes received on the wire along with the sink
+pub async fn read_body<W: Write>(mut response: Response, sink: W) -> Result<(u64, W), BodyError> {
+    let encoding: String = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let mut decoder: Decoder<W> = Decoder::new(&encoding, sink).map_err(|_| BodyError::Decode)?;
+
+    let mut received: u64 = 0;
+    while let Some(chunk) = response.chunk().await.map_err(BodyError::Read)? {
+        received += chunk.len() as u64;
+        decoder.write_all(&chunk).map_err(|_| BodyError::Decode)?;
+    }
+    let sink: W = decoder.finish().map_err(|_| BodyError::Decode)?;
+    Ok((received, sink))
+}
+
+/// Reads and discards the whole body as it arrives, without decompressing
+/// it, and returns the number of bytes received
+pub async fn drain(mut response: Response) -> Result<u64, reqwest::Error> {
+    let mut received: u64 = 0;
+    while let Some(chunk) = response.chunk().await? {
+        received += chunk.len() as u64;
+    }
+    Ok(received)
+}
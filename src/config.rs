@@ -0,0 +1,919 @@
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::abort;
+use crate::assertions;
+use crate::auth::{self, OAuth2Config};
+use crate::baseline;
+use crate::compression::{BodyEncoding, Compression};
+use crate::cookies;
+use crate::data::DataOrder;
+use crate::form::{self, FormField};
+use crate::influx;
+use crate::pushgateway;
+use crate::rate::Arrival;
+use crate::resolve;
+use crate::scenario::{Scenario, StepConfig};
+use crate::shapes;
+use crate::sigv4;
+use crate::source;
+use crate::stages::{self, StageUnit};
+use crate::statsd;
+use crate::targets::{Target, TargetConfig, UrlOrder};
+use crate::thresholds;
+use crate::tls::TlsVersion;
+use crate::upload;
+use crate::{
+    Args, HttpVersion, parse_duration, parse_header, parse_method, parse_percentile, parse_proxy,
+    parse_size, parse_socks5, parse_status,
+};
+
+/// Scenario configuration file.
+///
+/// Every field is optional; values given as command line flags take
+/// precedence over the file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    url: Option<String>,
+    #[serde(default)]
+    urls: Vec<TargetConfig>,
+    url_file: Option<String>,
+    url_order: Option<UrlOrder>,
+    method: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    basic_auth: Option<String>,
+    bearer: Option<String>,
+    bearer_file: Option<String>,
+    oauth2: Option<OAuth2Config>,
+    aws_sigv4: Option<String>,
+    aws_profile: Option<String>,
+    body: Option<String>,
+    body_file: Option<String>,
+    #[serde(default)]
+    form: Vec<String>,
+    body_size: Option<String>,
+    body_random: Option<bool>,
+    graphql: Option<String>,
+    graphql_variables: Option<String>,
+    upload_rate: Option<String>,
+    data_csv: Option<String>,
+    data_order: Option<DataOrder>,
+    requests: Option<usize>,
+    concurrency: Option<usize>,
+    duration: Option<String>,
+    warmup: Option<String>,
+    warmup_requests: Option<usize>,
+    prewarm: Option<bool>,
+    think_time: Option<String>,
+    jitter: Option<String>,
+    rate: Option<f64>,
+    max_concurrency: Option<usize>,
+    burst: Option<usize>,
+    burst_interval: Option<String>,
+    arrival: Option<Arrival>,
+    #[serde(default)]
+    stages: Vec<String>,
+    shape: Option<String>,
+    stage_unit: Option<StageUnit>,
+    timeout: Option<String>,
+    connect_timeout: Option<String>,
+    cert: Option<String>,
+    key: Option<String>,
+    cacert: Option<String>,
+    insecure: Option<bool>,
+    tls_min_version: Option<TlsVersion>,
+    tls_max_version: Option<TlsVersion>,
+    http_version: Option<HttpVersion>,
+    unix_socket: Option<String>,
+    proxy: Option<String>,
+    socks5: Option<String>,
+    #[serde(default)]
+    resolve: Vec<String>,
+    dns_per_request: Option<bool>,
+    disable_keepalive: Option<bool>,
+    max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<String>,
+    fail_on_non_2xx: Option<bool>,
+    #[serde(default)]
+    expect_status: Vec<u16>,
+    #[serde(default)]
+    assert_body_contains: Vec<String>,
+    #[serde(default)]
+    assert_body_regex: Vec<String>,
+    #[serde(default)]
+    assert_header: Vec<String>,
+    #[serde(default)]
+    assert_header_regex: Vec<String>,
+    assert_min_size: Option<String>,
+    assert_max_size: Option<String>,
+    buffer_bodies: Option<bool>,
+    follow_redirects: Option<bool>,
+    max_redirects: Option<usize>,
+    cookies: Option<bool>,
+    #[serde(default)]
+    cookie: Vec<String>,
+    compression: Option<Compression>,
+    compress_body: Option<BodyEncoding>,
+    sessions: Option<bool>,
+    tcp_nodelay: Option<bool>,
+    tcp_keepalive: Option<String>,
+    tcp_keepalive_interval: Option<String>,
+    tcp_keepalive_retries: Option<u32>,
+    spread_ips: Option<bool>,
+    local_address: Option<LocalAddressConfig>,
+    output: Option<String>,
+    html: Option<String>,
+    markdown: Option<String>,
+    metrics_addr: Option<String>,
+    statsd: Option<String>,
+    dogstatsd: Option<bool>,
+    #[serde(default)]
+    statsd_tags: Vec<String>,
+    influx: Option<String>,
+    influx_bucket: Option<String>,
+    influx_org: Option<String>,
+    influx_token: Option<String>,
+    influx_samples: Option<bool>,
+    pushgateway: Option<String>,
+    hdr_out: Option<String>,
+    request_log: Option<String>,
+    csv: Option<String>,
+    parquet: Option<String>,
+    sqlite: Option<String>,
+    sqlite_samples: Option<bool>,
+    tag: Option<String>,
+    ui: Option<bool>,
+    quiet: Option<bool>,
+    no_color: Option<bool>,
+    verbose: Option<u8>,
+    #[serde(default)]
+    percentiles: Vec<f64>,
+    abort_on_error_rate: Option<String>,
+    abort_window: Option<usize>,
+    bucket: Option<String>,
+    #[serde(default)]
+    thresholds: Vec<String>,
+    baseline: Option<String>,
+    #[serde(default)]
+    max_regressions: Vec<String>,
+    #[serde(default)]
+    steps: Vec<StepConfig>,
+}
+
+/// One source address or CIDR block, or a list of them
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum LocalAddressConfig {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Config {
+    /// Loads a YAML or TOML (by `.toml` extension) scenario file
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let contents: String = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+
+        let config: Result<Config, String> = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&contents).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&contents).map_err(|e| e.to_string())
+        };
+        config.map_err(|e| format!("failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Fills in every flag that wasn't given on the command line.
+    ///
+    /// Relative file paths in the config are resolved against `base_dir`.
+    pub fn apply(
+        &self,
+        args: &mut Args,
+        matches: &ArgMatches,
+        base_dir: &Path,
+    ) -> Result<(), String> {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if !explicit("url_file")
+            && let Some(url_file) = &self.url_file
+        {
+            args.url_file = Some(if url_file == "-" {
+                url_file.clone()
+            } else {
+                resolve(base_dir, url_file)
+            });
+        }
+        if !explicit("url_order")
+            && let Some(url_order) = self.url_order
+        {
+            args.url_order = url_order;
+        }
+
+        if !explicit("method")
+            && let Some(method) = &self.method
+        {
+            args.method = Some(parse_method(method)?);
+        }
+
+        // Headers from the file are sent unless a flag sets the same name
+        let mut headers: Vec<(HeaderName, HeaderValue)> = Vec::new();
+        for (name, value) in &self.headers {
+            let (name, value) = parse_header(&format!("{}: {}", name, value))?;
+            if !args.headers.iter().any(|(cli_name, _)| *cli_name == name) {
+                headers.push((name, value));
+            }
+        }
+        headers.append(&mut args.headers);
+        args.headers = headers;
+
+        // Authentication is one of basic auth, a bearer token, a token file,
+        // OAuth2 or AWS request signing
+        let auth_flags: [&str; 5] = [
+            "basic_auth",
+            "bearer",
+            "bearer_file",
+            "oauth2_token_url",
+            "aws_sigv4",
+        ];
+        if !auth_flags.iter().any(|id| explicit(id)) {
+            let methods: usize = [
+                self.basic_auth.is_some(),
+                self.bearer.is_some(),
+                self.bearer_file.is_some(),
+                self.oauth2.is_some(),
+                self.aws_sigv4.is_some(),
+            ]
+            .iter()
+            .filter(|set| **set)
+            .count();
+            if methods > 1 {
+                return Err(
+                    "config sets more than one of basic_auth, bearer, bearer_file, oauth2 \
+                     and aws_sigv4"
+                        .to_string(),
+                );
+            }
+
+            if let Some(basic_auth) = &self.basic_auth {
+                args.basic_auth = Some(auth::parse_basic_auth(basic_auth)?);
+            }
+            if let Some(bearer) = &self.bearer {
+                args.bearer = Some(auth::bearer_header(bearer)?);
+            }
+            if let Some(path) = &self.bearer_file {
+                args.bearer_file = Some(resolve(base_dir, path));
+            }
+            if let Some(oauth2) = &self.oauth2 {
+                args.oauth2_token_url = Some(oauth2.token_url.clone());
+                args.oauth2_client_id = Some(oauth2.client_id.clone());
+                args.oauth2_client_secret = Some(oauth2.client_secret.clone());
+                args.oauth2_scope = oauth2.scope.clone();
+            }
+            if let Some(aws_sigv4) = &self.aws_sigv4 {
+                args.aws_sigv4 = Some(sigv4::parse_scope(aws_sigv4)?);
+            }
+        }
+        if !explicit("aws_profile") && self.aws_profile.is_some() {
+            args.aws_profile = self.aws_profile.clone();
+        }
+
+        if !explicit("data") && !explicit("data_file") {
+            match (&self.body, &self.body_file) {
+                (Some(_), Some(_)) => return Err("config sets both body and body_file".to_string()),
+                (Some(body), None) => args.data = Some(body.clone()),
+                (None, Some(path)) => args.data_file = Some(resolve(base_dir, path)),
+                (None, None) => {}
+            }
+        }
+        if !explicit("form") {
+            for field in &self.form {
+                args.form.push(match form::parse_form_field(field)? {
+                    FormField::File { name, path } => FormField::File {
+                        name,
+                        path: resolve(base_dir, &path),
+                    },
+                    text => text,
+                });
+            }
+        }
+        if !args.form.is_empty() && (args.data.is_some() || args.data_file.is_some()) {
+            return Err("form fields can't be combined with a body".to_string());
+        }
+        if !explicit("body_size")
+            && let Some(body_size) = &self.body_size
+        {
+            args.body_size = Some(parse_size(body_size)?);
+        }
+        if !explicit("body_random")
+            && let Some(body_random) = self.body_random
+        {
+            args.body_random = body_random;
+        }
+        if args.body_size.is_some()
+            && (args.data.is_some() || args.data_file.is_some() || !args.form.is_empty())
+        {
+            return Err("body_size can't be combined with a body or form fields".to_string());
+        }
+        if args.body_random && args.body_size.is_none() {
+            return Err("body_random requires body_size".to_string());
+        }
+        if !explicit("graphql")
+            && let Some(graphql) = &self.graphql
+        {
+            args.graphql = Some(resolve(base_dir, graphql));
+        }
+        if !explicit("variables")
+            && let Some(variables) = &self.graphql_variables
+        {
+            args.variables = Some(resolve(base_dir, variables));
+        }
+        if args.graphql.is_some()
+            && (args.data.is_some()
+                || args.data_file.is_some()
+                || !args.form.is_empty()
+                || args.body_size.is_some())
+        {
+            return Err("graphql can't be combined with another body".to_string());
+        }
+        if args.variables.is_some() && args.graphql.is_none() {
+            return Err("graphql_variables requires graphql".to_string());
+        }
+        if !explicit("upload_rate")
+            && let Some(upload_rate) = &self.upload_rate
+        {
+            args.upload_rate = Some(upload::parse_upload_rate(upload_rate)?);
+        }
+        if args.upload_rate.is_some() && !args.form.is_empty() {
+            return Err("upload_rate can't be combined with form fields".to_string());
+        }
+
+        if !explicit("data_csv")
+            && let Some(data_csv) = &self.data_csv
+        {
+            args.data_csv = Some(resolve(base_dir, data_csv));
+        }
+        if !explicit("data_order")
+            && let Some(data_order) = self.data_order
+        {
+            args.data_order = data_order;
+        }
+
+        // Requests, duration and stages are alternative ways to end a run
+        if !explicit("requests") && !explicit("duration") && !explicit("stages") {
+            let modes: usize = [
+                self.requests.is_some(),
+                self.duration.is_some(),
+                !self.stages.is_empty(),
+            ]
+            .iter()
+            .filter(|set| **set)
+            .count();
+            if modes > 1 {
+                return Err(
+                    "config sets more than one of requests, duration and stages".to_string()
+                );
+            }
+
+            if let Some(requests) = self.requests {
+                args.requests = requests;
+            }
+            if let Some(duration) = &self.duration {
+                args.duration = Some(parse_duration(duration)?);
+            }
+            for stage in &self.stages {
+                args.stages.push(stages::parse_stage(stage)?);
+            }
+        }
+        if !explicit("shape")
+            && let Some(shape) = &self.shape
+        {
+            args.shape = Some(shapes::parse_shape(shape)?);
+        }
+        if args.shape.is_some() && (args.duration.is_none() || !args.stages.is_empty()) {
+            return Err("shape needs a duration and can't be combined with stages".to_string());
+        }
+        if !explicit("warmup") && !explicit("warmup_requests") {
+            if self.warmup.is_some() && self.warmup_requests.is_some() {
+                return Err("config sets both warmup and warmup_requests".to_string());
+            }
+            if let Some(warmup) = &self.warmup {
+                args.warmup = Some(parse_duration(warmup)?);
+            }
+            if let Some(warmup_requests) = self.warmup_requests {
+                args.warmup_requests = Some(warmup_requests);
+            }
+        }
+        if (args.warmup.is_some() || args.warmup_requests.is_some())
+            && (!args.stages.is_empty() || args.shape.is_some())
+        {
+            return Err("warmup can't be combined with stages or a shape".to_string());
+        }
+        if !explicit("think_time")
+            && let Some(think_time) = &self.think_time
+        {
+            args.think_time = Some(parse_duration(think_time)?);
+        }
+        if !explicit("jitter")
+            && let Some(jitter) = &self.jitter
+        {
+            args.jitter = Some(parse_duration(jitter)?);
+        }
+        if args.jitter.is_some() && args.think_time.is_none() {
+            return Err("jitter requires think_time".to_string());
+        }
+
+        if !explicit("stage_unit")
+            && let Some(stage_unit) = self.stage_unit
+        {
+            args.stage_unit = stage_unit;
+        }
+        if !explicit("concurrency")
+            && let Some(concurrency) = self.concurrency
+        {
+            args.concurrency = concurrency;
+        }
+        if !explicit("rate") && self.rate.is_some() {
+            args.rate = self.rate;
+        }
+        if !explicit("max_concurrency") && self.max_concurrency.is_some() {
+            args.max_concurrency = self.max_concurrency;
+        }
+        if args.max_concurrency.is_some() && args.rate.is_none() {
+            return Err("max_concurrency requires rate".to_string());
+        }
+        if !explicit("burst") && self.burst.is_some() {
+            args.burst = self.burst;
+        }
+        if !explicit("burst_interval")
+            && let Some(interval) = &self.burst_interval
+        {
+            args.burst_interval = parse_duration(interval)?;
+        }
+        if args.burst.is_some()
+            && (args.rate.is_some()
+                || !args.stages.is_empty()
+                || args.shape.is_some()
+                || args.think_time.is_some())
+        {
+            return Err(
+                "burst can't be combined with rate, stages, a shape or think_time".to_string(),
+            );
+        }
+        if self.burst_interval.is_some() && args.burst.is_none() {
+            return Err("burst_interval requires burst".to_string());
+        }
+        if !explicit("arrival")
+            && let Some(arrival) = self.arrival
+        {
+            args.arrival = arrival;
+        }
+        if !explicit("timeout")
+            && let Some(timeout) = &self.timeout
+        {
+            args.timeout = Some(parse_duration(timeout)?);
+        }
+        if !explicit("connect_timeout")
+            && let Some(connect_timeout) = &self.connect_timeout
+        {
+            args.connect_timeout = Some(parse_duration(connect_timeout)?);
+        }
+        if !explicit("cert") && !explicit("key") {
+            match (&self.cert, &self.key) {
+                (Some(cert), Some(key)) => {
+                    args.cert = Some(resolve(base_dir, cert));
+                    args.key = Some(resolve(base_dir, key));
+                }
+                (None, None) => {}
+                _ => return Err("config must set both cert and key".to_string()),
+            }
+        }
+        if !explicit("cacert")
+            && !explicit("insecure")
+            && let Some(cacert) = &self.cacert
+        {
+            args.cacert = Some(resolve(base_dir, cacert));
+        }
+        if !explicit("insecure")
+            && !explicit("cacert")
+            && let Some(insecure) = self.insecure
+        {
+            args.insecure = insecure;
+        }
+        if args.cacert.is_some() && args.insecure {
+            return Err("config sets both cacert and insecure".to_string());
+        }
+        if !explicit("tls_min_version") && self.tls_min_version.is_some() {
+            args.tls_min_version = self.tls_min_version;
+        }
+        if !explicit("tls_max_version") && self.tls_max_version.is_some() {
+            args.tls_max_version = self.tls_max_version;
+        }
+        if !explicit("http1_1")
+            && !explicit("http2")
+            && !explicit("http2_prior_knowledge")
+            && let Some(http_version) = self.http_version
+        {
+            args.http1_1 = http_version == HttpVersion::Http1;
+            args.http2 = http_version == HttpVersion::Http2;
+            args.http2_prior_knowledge = http_version == HttpVersion::Http2PriorKnowledge;
+        }
+        // A Unix socket, an HTTP proxy and a SOCKS5 proxy are alternative routes
+        let route_flags: [&str; 3] = ["unix_socket", "proxy", "socks5"];
+        if !route_flags.iter().any(|id| explicit(id)) {
+            let routes: usize = [
+                self.unix_socket.is_some(),
+                self.proxy.is_some(),
+                self.socks5.is_some(),
+            ]
+            .iter()
+            .filter(|set| **set)
+            .count();
+            if routes > 1 {
+                return Err(
+                    "config sets more than one of unix_socket, proxy and socks5".to_string()
+                );
+            }
+
+            if let Some(unix_socket) = &self.unix_socket {
+                args.unix_socket = Some(resolve(base_dir, unix_socket));
+            }
+            if let Some(proxy) = &self.proxy {
+                args.proxy = Some(parse_proxy(proxy)?);
+            }
+            if let Some(socks5) = &self.socks5 {
+                args.socks5 = Some(parse_socks5(socks5)?);
+            }
+        }
+        if !explicit("resolve") {
+            for entry in &self.resolve {
+                args.resolve.push(resolve::parse_resolve(entry)?);
+            }
+        }
+        if !explicit("dns_per_request")
+            && let Some(dns_per_request) = self.dns_per_request
+        {
+            args.dns_per_request = dns_per_request;
+        }
+        if !explicit("disable_keepalive")
+            && let Some(disable_keepalive) = self.disable_keepalive
+        {
+            args.disable_keepalive = disable_keepalive;
+        }
+        if !explicit("max_idle_per_host") && self.max_idle_per_host.is_some() {
+            args.max_idle_per_host = self.max_idle_per_host;
+        }
+        if !explicit("pool_idle_timeout")
+            && let Some(pool_idle_timeout) = &self.pool_idle_timeout
+        {
+            args.pool_idle_timeout = Some(parse_duration(pool_idle_timeout)?);
+        }
+        if !explicit("prewarm")
+            && let Some(prewarm) = self.prewarm
+        {
+            args.prewarm = prewarm;
+        }
+        if (args.disable_keepalive || args.dns_per_request) && args.prewarm {
+            return Err(
+                "prewarm opens connections for reuse, which disable_keepalive and \
+                 dns_per_request turn off"
+                    .to_string(),
+            );
+        }
+        if (args.disable_keepalive || args.dns_per_request)
+            && (args.max_idle_per_host.is_some() || args.pool_idle_timeout.is_some())
+        {
+            return Err(
+                "config sets max_idle_per_host or pool_idle_timeout, but connections are not \
+                 reused with disable_keepalive or dns_per_request"
+                    .to_string(),
+            );
+        }
+        if !explicit("fail_on_non_2xx")
+            && !explicit("expect_status")
+            && let Some(fail_on_non_2xx) = self.fail_on_non_2xx
+        {
+            args.fail_on_non_2xx = fail_on_non_2xx;
+        }
+        if !explicit("expect_status") && !explicit("fail_on_non_2xx") {
+            for status in &self.expect_status {
+                args.expect_status.push(parse_status(&status.to_string())?);
+            }
+        }
+        if args.fail_on_non_2xx && !args.expect_status.is_empty() {
+            return Err("expect_status can't be combined with fail_on_non_2xx".to_string());
+        }
+        if !explicit("assert_body_contains") {
+            args.assert_body_contains
+                .extend(self.assert_body_contains.iter().cloned());
+        }
+        if !explicit("assert_body_regex") {
+            for regex in &self.assert_body_regex {
+                args.assert_body_regex.push(assertions::parse_regex(regex)?);
+            }
+        }
+        if !explicit("assert_header") {
+            for header in &self.assert_header {
+                args.assert_header
+                    .push(assertions::parse_header_assertion(header)?);
+            }
+        }
+        if !explicit("assert_header_regex") {
+            for header in &self.assert_header_regex {
+                args.assert_header_regex
+                    .push(assertions::parse_header_regex(header)?);
+            }
+        }
+        if !explicit("assert_min_size")
+            && let Some(size) = &self.assert_min_size
+        {
+            args.assert_min_size = Some(parse_size(size)?);
+        }
+        if !explicit("assert_max_size")
+            && let Some(size) = &self.assert_max_size
+        {
+            args.assert_max_size = Some(parse_size(size)?);
+        }
+        if !explicit("buffer_bodies")
+            && let Some(buffer_bodies) = self.buffer_bodies
+        {
+            args.buffer_bodies = buffer_bodies;
+        }
+        if !explicit("follow_redirects")
+            && let Some(follow_redirects) = self.follow_redirects
+        {
+            args.follow_redirects = follow_redirects;
+        }
+        if !explicit("max_redirects") && self.max_redirects.is_some() {
+            args.max_redirects = self.max_redirects;
+        }
+        if !explicit("cookies")
+            && let Some(cookies) = self.cookies
+        {
+            args.cookies = cookies;
+        }
+        if !explicit("cookie") {
+            for cookie in &self.cookie {
+                args.cookie.push(cookies::parse_cookie(cookie)?);
+            }
+        }
+        if !explicit("compression") && self.compression.is_some() {
+            args.compression = self.compression;
+        }
+        if !explicit("compress_body") && self.compress_body.is_some() {
+            args.compress_body = self.compress_body;
+        }
+        if !explicit("sessions")
+            && let Some(sessions) = self.sessions
+        {
+            args.sessions = sessions;
+        }
+        if !explicit("no_tcp_nodelay")
+            && let Some(tcp_nodelay) = self.tcp_nodelay
+        {
+            args.no_tcp_nodelay = !tcp_nodelay;
+        }
+        if !explicit("tcp_keepalive")
+            && let Some(tcp_keepalive) = &self.tcp_keepalive
+        {
+            args.tcp_keepalive = Some(parse_duration(tcp_keepalive)?);
+        }
+        if !explicit("tcp_keepalive_interval")
+            && let Some(tcp_keepalive_interval) = &self.tcp_keepalive_interval
+        {
+            args.tcp_keepalive_interval = Some(parse_duration(tcp_keepalive_interval)?);
+        }
+        if !explicit("tcp_keepalive_retries") && self.tcp_keepalive_retries.is_some() {
+            args.tcp_keepalive_retries = self.tcp_keepalive_retries;
+        }
+        if args.tcp_keepalive.is_none()
+            && (args.tcp_keepalive_interval.is_some() || args.tcp_keepalive_retries.is_some())
+        {
+            return Err(
+                "config sets tcp_keepalive_interval or tcp_keepalive_retries without tcp_keepalive"
+                    .to_string(),
+            );
+        }
+        if !explicit("spread_ips")
+            && let Some(spread_ips) = self.spread_ips
+        {
+            args.spread_ips = spread_ips;
+        }
+        if !explicit("local_address") {
+            match &self.local_address {
+                Some(LocalAddressConfig::One(address)) => {
+                    args.local_address = vec![source::parse_source(address)?];
+                }
+                Some(LocalAddressConfig::Many(addresses)) => {
+                    args.local_address = addresses
+                        .iter()
+                        .map(|address| source::parse_source(address))
+                        .collect::<Result<_, _>>()?;
+                }
+                None => {}
+            }
+        }
+        if !explicit("output") && self.output.is_some() {
+            args.output = self.output.clone();
+        }
+        if !explicit("html") && self.html.is_some() {
+            args.html = self.html.clone();
+        }
+        if !explicit("markdown") && self.markdown.is_some() {
+            args.markdown = self.markdown.clone();
+        }
+        if !explicit("metrics_addr")
+            && let Some(addr) = &self.metrics_addr
+        {
+            args.metrics_addr = Some(
+                addr.parse()
+                    .map_err(|_| format!("invalid metrics_addr \"{}\"", addr))?,
+            );
+        }
+        if !explicit("statsd")
+            && let Some(addr) = &self.statsd
+        {
+            args.statsd = Some(statsd::parse_statsd(addr)?);
+        }
+        if !explicit("dogstatsd")
+            && let Some(dogstatsd) = self.dogstatsd
+        {
+            args.dogstatsd = dogstatsd;
+        }
+        if !explicit("statsd_tag") {
+            for tag in &self.statsd_tags {
+                args.statsd_tag.push(statsd::parse_statsd_tag(tag)?);
+            }
+        }
+        if args.dogstatsd && args.statsd.is_none() {
+            return Err("dogstatsd needs statsd".to_string());
+        }
+        if !args.statsd_tag.is_empty() && !args.dogstatsd {
+            return Err("statsd_tags needs dogstatsd".to_string());
+        }
+        if !explicit("influx")
+            && let Some(url) = &self.influx
+        {
+            args.influx = Some(influx::parse_influx(url)?);
+        }
+        if !explicit("influx_bucket") && self.influx_bucket.is_some() {
+            args.influx_bucket = self.influx_bucket.clone();
+        }
+        if !explicit("influx_org") && self.influx_org.is_some() {
+            args.influx_org = self.influx_org.clone();
+        }
+        if !explicit("influx_token") && self.influx_token.is_some() {
+            args.influx_token = self.influx_token.clone();
+        }
+        if !explicit("influx_samples")
+            && let Some(influx_samples) = self.influx_samples
+        {
+            args.influx_samples = influx_samples;
+        }
+        if args.influx.is_some() != args.influx_bucket.is_some() {
+            return Err("influx and influx_bucket go together".to_string());
+        }
+        if (args.influx_org.is_some() || args.influx_token.is_some() || args.influx_samples)
+            && args.influx.is_none()
+        {
+            return Err("influx_org, influx_token and influx_samples need influx".to_string());
+        }
+        if !explicit("pushgateway")
+            && let Some(url) = &self.pushgateway
+        {
+            args.pushgateway = Some(pushgateway::parse_pushgateway(url)?);
+        }
+        if !explicit("hdr_out") && self.hdr_out.is_some() {
+            args.hdr_out = self.hdr_out.clone();
+        }
+        if !explicit("request_log") && self.request_log.is_some() {
+            args.request_log = self.request_log.clone();
+        }
+        if !explicit("csv") && self.csv.is_some() {
+            args.csv = self.csv.clone();
+        }
+        if !explicit("parquet") && self.parquet.is_some() {
+            args.parquet = self.parquet.clone();
+        }
+        if !explicit("sqlite") && self.sqlite.is_some() {
+            args.sqlite = self.sqlite.clone();
+        }
+        if !explicit("sqlite_samples")
+            && let Some(sqlite_samples) = self.sqlite_samples
+        {
+            args.sqlite_samples = sqlite_samples;
+        }
+        if !explicit("tag") && self.tag.is_some() {
+            args.tag = self.tag.clone();
+        }
+        if args.sqlite_samples && args.sqlite.is_none() {
+            return Err("sqlite_samples needs sqlite".to_string());
+        }
+        if !explicit("ui")
+            && let Some(ui) = self.ui
+        {
+            args.ui = ui;
+        }
+        if !explicit("quiet")
+            && let Some(quiet) = self.quiet
+        {
+            args.quiet = quiet;
+        }
+        if args.ui && args.quiet {
+            return Err("ui and quiet can't be combined".to_string());
+        }
+        if !explicit("no_color")
+            && let Some(no_color) = self.no_color
+        {
+            args.no_color = no_color;
+        }
+        if !explicit("verbose")
+            && let Some(verbose) = self.verbose
+        {
+            args.verbose = verbose;
+        }
+        if args.ui && args.verbose > 0 {
+            return Err("ui and verbose can't be combined".to_string());
+        }
+        if !explicit("percentiles") {
+            for percentile in &self.percentiles {
+                args.percentiles
+                    .push(parse_percentile(&percentile.to_string())?);
+            }
+        }
+        if !explicit("abort_on_error_rate")
+            && let Some(rate) = &self.abort_on_error_rate
+        {
+            args.abort_on_error_rate = Some(abort::parse_error_rate(rate)?);
+        }
+        if !explicit("abort_window")
+            && let Some(window) = self.abort_window
+        {
+            args.abort_window = window;
+        }
+        if self.abort_window.is_some() && args.abort_on_error_rate.is_none() {
+            return Err("abort_window requires abort_on_error_rate".to_string());
+        }
+        if !explicit("bucket")
+            && let Some(bucket) = &self.bucket
+        {
+            args.bucket = Some(parse_duration(bucket)?);
+        }
+        if !explicit("threshold") {
+            for threshold in &self.thresholds {
+                args.threshold.push(thresholds::parse_threshold(threshold)?);
+            }
+        }
+        if !explicit("baseline") && self.baseline.is_some() {
+            args.baseline = self.baseline.clone();
+        }
+        if !explicit("max_regression") {
+            for max_regression in &self.max_regressions {
+                args.max_regression
+                    .push(baseline::parse_max_regression(max_regression)?);
+            }
+        }
+        if !args.max_regression.is_empty() && args.baseline.is_none() {
+            return Err("max_regressions needs baseline".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the weighted URLs from `url` and `urls`
+    pub fn targets(&self) -> Result<Vec<Target>, String> {
+        let mut targets: Vec<Target> = Vec::new();
+        if let Some(url) = &self.url {
+            targets.push(Target {
+                url: url.clone(),
+                weight: 1,
+            });
+        }
+        for target in &self.urls {
+            targets.push(target.to_target()?);
+        }
+        Ok(targets)
+    }
+
+    pub fn has_steps(&self) -> bool {
+        !self.steps.is_empty()
+    }
+
+    /// Builds the multi-step scenario, if the file defines steps
+    pub fn scenario(&self, base_url: Option<&str>) -> Result<Option<Scenario>, String> {
+        if self.steps.is_empty() {
+            return Ok(None);
+        }
+        Scenario::new(&self.steps, base_url).map(Some)
+    }
+}
+
+fn resolve(base_dir: &Path, path: &str) -> String {
+    let path: PathBuf = base_dir.join(path);
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,135 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+use crate::latencies::Latencies;
+
+tokio::task_local! {
+    /// DNS lookup time of the connection being established
+    static LOOKUP: Cell<Option<Duration>>;
+
+    /// When the TLS handshake of the connection being established started
+    static HANDSHAKE: Cell<Option<Instant>>;
+}
+
+/// Records how long the lookup for the connection being established took.
+///
+/// The resolver runs inside the connector, so this reaches the connection
+/// that asked for the lookup even when many connect at once.
+pub fn record_lookup(duration: Duration) {
+    let _ = LOOKUP.try_with(|lookup| lookup.set(Some(duration)));
+}
+
+/// Records that the TLS handshake of the connection being established has
+/// started, once the TCP connection is open; like lookups, the handshake
+/// runs inside the connector
+pub fn record_handshake_start() {
+    let _ = HANDSHAKE.try_with(|handshake| {
+        if handshake.get().is_none() {
+            handshake.set(Some(Instant::now()));
+        }
+    });
+}
+
+/// Counts connections the client manages to establish (including the TLS
+/// handshake), so reuse of pooled connections can be reported, and times
+/// their DNS lookup, connect and TLS handshake phases
+#[derive(Clone, Default)]
+pub struct ConnectionCounter {
+    opened: Arc<AtomicUsize>,
+    timings: Arc<Mutex<Timings>>,
+}
+
+/// Phase durations of the connections established so far
+#[derive(Default)]
+pub struct Timings {
+    pub lookups: Latencies,
+    /// TCP connect, after the lookup
+    pub connects: Latencies,
+    /// TLS handshakes of HTTPS connections, after the TCP connect
+    pub handshakes: Latencies,
+}
+
+impl ConnectionCounter {
+    pub fn opened(&self) -> usize {
+        self.opened.load(Ordering::Relaxed)
+    }
+
+    pub fn take_timings(&self) -> Timings {
+        let mut timings: MutexGuard<Timings> =
+            self.timings.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *timings)
+    }
+}
+
+impl<S> Layer<S> for ConnectionCounter {
+    type Service = Counted<S>;
+
+    fn layer(&self, inner: S) -> Counted<S> {
+        Counted {
+            inner,
+            opened: Arc::clone(&self.opened),
+            timings: Arc::clone(&self.timings),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Counted<S> {
+    inner: S,
+    opened: Arc<AtomicUsize>,
+    timings: Arc<Mutex<Timings>>,
+}
+
+impl<S, R> Service<R> for Counted<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting: S::Future = self.inner.call(request);
+        let opened: Arc<AtomicUsize> = Arc::clone(&self.opened);
+        let timings: Arc<Mutex<Timings>> = Arc::clone(&self.timings);
+        let connecting = async move {
+            let start: Instant = Instant::now();
+            let connection: S::Response = connecting.await?;
+            let end: Instant = Instant::now();
+            opened.fetch_add(1, Ordering::Relaxed);
+
+            // Addresses given as IPs or pinned with --resolve need no lookup,
+            // and plain HTTP connections no handshake
+            let lookup: Option<Duration> = LOOKUP.with(Cell::get);
+            let handshake: Option<Instant> = HANDSHAKE.with(Cell::get);
+            let connected: Instant = handshake.unwrap_or(end);
+            let mut timings: MutexGuard<Timings> =
+                timings.lock().unwrap_or_else(|e| e.into_inner());
+            timings.lookups.extend(lookup);
+            timings.connects.record(
+                connected
+                    .saturating_duration_since(start)
+                    .saturating_sub(lookup.unwrap_or_default()),
+            );
+            timings
+                .handshakes
+                .extend(handshake.map(|handshake| end.saturating_duration_since(handshake)));
+            Ok(connection)
+        };
+        Box::pin(LOOKUP.scope(
+            Cell::new(None),
+            HANDSHAKE.scope(Cell::new(None), connecting),
+        ))
+    }
+}
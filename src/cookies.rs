@@ -0,0 +1,24 @@
+use reqwest::Url;
+use reqwest::cookie::Jar;
+
+/// Parses a "NAME=VALUE" cookie to seed the jar with
+pub fn parse_cookie(s: &str) -> Result<String, String> {
+    match s.trim().split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() && !value.contains(';') => {
+            Ok(format!("{}={}", name.trim(), value.trim()))
+        }
+        _ => Err(format!("expected NAME=VALUE, got \"{}\"", s)),
+    }
+}
+
+/// Creates the cookie store shared by every request, seeded with `cookies`
+/// for every host in `urls`
+pub fn jar(cookies: &[String], urls: &[Url]) -> Jar {
+    let jar: Jar = Jar::default();
+    for url in urls {
+        for cookie in cookies {
+            jar.add_cookie_str(&format!("{}; Path=/", cookie), url);
+        }
+    }
+    jar
+}
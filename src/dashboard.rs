@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::latencies::Latencies;
+use crate::{Outcome, Sample};
+
+/// How often the dashboard is redrawn
+const REFRESH: Duration = Duration::from_millis(250);
+
+/// Seconds the rolling percentiles and error rate cover
+const ROLLING_SECS: usize = 10;
+
+/// Seconds of history the sparkline shows
+const SPARKLINE_SECS: usize = 60;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Switches to the alternate screen and hides the cursor
+const ENTER: &str = "\x1b[?1049h\x1b[?25l";
+/// Shows the cursor and returns to the normal screen
+const LEAVE: &str = "\x1b[?25h\x1b[?1049l";
+
+/// Requests that finished in one second of the run
+#[derive(Default)]
+struct Second {
+    requests: usize,
+    errors: usize,
+    latencies: Latencies,
+}
+
+/// What the dashboard shows, fed by the aggregator as requests finish
+pub struct Feed {
+    start: Instant,
+    requests: usize,
+    errors: usize,
+    /// Responses per status code
+    statuses: BTreeMap<u16, usize>,
+    /// The last minute, one entry per second, the current one last
+    seconds: VecDeque<Second>,
+    /// Seconds since the start the last entry is for
+    current: u64,
+}
+
+impl Feed {
+    fn new(start: Instant) -> Feed {
+        Feed {
+            start,
+            requests: 0,
+            errors: 0,
+            statuses: BTreeMap::new(),
+            seconds: VecDeque::from([Second::default()]),
+            current: 0,
+        }
+    }
+
+    pub fn record(&mut self, sample: &Sample) {
+        self.requests += 1;
+        let failed: bool = !matches!(sample.outcome, Outcome::Response(_));
+        self.errors += failed as usize;
+        if let Some(peer) = &sample.peer {
+            *self.statuses.entry(peer.status).or_default() += 1;
+        }
+        self.advance(sample.finished);
+        if let Some(second) = self.seconds.back_mut() {
+            second.requests += 1;
+            second.errors += failed as usize;
+            second.latencies.record(sample.duration);
+        }
+    }
+
+    /// Counts from `start` on, once the warm-up is over
+    pub fn restart(&mut self, start: Instant) {
+        *self = Feed::new(start);
+    }
+
+    /// Starts a new second for each that passed by `now`
+    fn advance(&mut self, now: Instant) {
+        let second: u64 = now.saturating_duration_since(self.start).as_secs();
+        while self.current < second {
+            self.current += 1;
+            self.seconds.push_back(Second::default());
+            if self.seconds.len() > SPARKLINE_SECS {
+                self.seconds.pop_front();
+            }
+        }
+    }
+
+    /// The dashboard's lines, `width` columns wide at most
+    fn render(&mut self, title: &str, goal: &str, width: usize) -> Vec<String> {
+        self.advance(Instant::now());
+        let elapsed: Duration = self.start.elapsed();
+        // The second under way is partial, so rates come from the one before
+        let finished: usize = self.seconds.len().saturating_sub(1);
+        let last: Option<&Second> = finished
+            .checked_sub(1)
+            .and_then(|index| self.seconds.get(index));
+        let rolling: Vec<&Second> = self.seconds.iter().rev().take(ROLLING_SECS).collect();
+        let mut latencies: Latencies = Latencies::default();
+        for second in &rolling {
+            latencies.merge(&second.latencies);
+        }
+        let rolling_requests: usize = rolling.iter().map(|second| second.requests).sum();
+        let rolling_errors: usize = rolling.iter().map(|second| second.errors).sum();
+
+        let mut lines: Vec<String> = vec![
+            title.to_string(),
+            format!("Elapsed: {:.1}s {}", elapsed.as_secs_f64(), goal),
+            String::new(),
+            format!(
+                "Requests: {} ({} failed)   Rate: {} req/s   Average: {:.1} req/s",
+                self.requests,
+                self.errors,
+                last.map_or(0, |second| second.requests),
+                self.requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+            ),
+            format!(
+                "Error rate (last {}s): {:.2}%",
+                ROLLING_SECS,
+                percent(rolling_errors, rolling_requests)
+            ),
+        ];
+        lines.push(format!(
+            "Latency (last {}s): p50 {:.2}ms   p95 {:.2}ms   p99 {:.2}ms   max {:.2}ms",
+            ROLLING_SECS,
+            millis(latencies.percentile(50.0)),
+            millis(latencies.percentile(95.0)),
+            millis(latencies.percentile(99.0)),
+            millis(latencies.max())
+        ));
+
+        lines.push(String::new());
+        let mut statuses: String = String::from("Status codes:");
+        if self.statuses.is_empty() {
+            statuses.push_str(" none yet");
+        }
+        for (status, count) in &self.statuses {
+            let _ = write!(
+                statuses,
+                "  {}: {} ({:.1}%)",
+                status,
+                count,
+                percent(*count, self.requests)
+            );
+        }
+        lines.push(statuses);
+
+        // One bar per finished second, the newest on the right
+        let p95s: Vec<f64> = self
+            .seconds
+            .iter_mut()
+            .take(finished)
+            .map(|second| millis(second.latencies.percentile(95.0)))
+            .collect();
+        let peak: f64 = p95s.iter().copied().fold(0.0, f64::max);
+        let shown: &[f64] = &p95s[p95s.len().saturating_sub(width.saturating_sub(2))..];
+        lines.push(String::new());
+        lines.push(format!(
+            "p95 latency per second (last {}s, peak {:.2}ms):",
+            p95s.len(),
+            peak
+        ));
+        lines.push(format!("  {}", sparkline(shown)));
+
+        lines
+            .into_iter()
+            .map(|line| line.chars().take(width).collect())
+            .collect()
+    }
+}
+
+/// Live view of the run drawn over the terminal with --ui, in place of
+/// the progress markers
+pub struct Dashboard {
+    feed: Arc<Mutex<Feed>>,
+    /// Set by Ctrl-C, which closes the dashboard for the run to stop
+    interrupted: Arc<AtomicBool>,
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl Dashboard {
+    /// Opens the dashboard, or returns `None` when stdout isn't a terminal
+    /// to draw it on
+    pub fn start(title: String, goal: String, start: Instant) -> Option<Dashboard> {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        let feed: Arc<Mutex<Feed>> = Arc::new(Mutex::new(Feed::new(start)));
+        let (stop, mut stopped) = oneshot::channel::<()>();
+        let drawn: Arc<Mutex<Feed>> = Arc::clone(&feed);
+        let interrupted: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let interrupt: Arc<AtomicBool> = Arc::clone(&interrupted);
+        let task: JoinHandle<()> = tokio::spawn(async move {
+            print!("{}", ENTER);
+            let mut ticker: tokio::time::Interval = tokio::time::interval(REFRESH);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = &mut stopped => break,
+                    // Interrupting the run leaves the alternate screen and
+                    // lets the run loop stop and report
+                    _ = tokio::signal::ctrl_c() => {
+                        interrupt.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+                let lines: Vec<String> = drawn.lock().unwrap_or_else(|e| e.into_inner()).render(
+                    &title,
+                    &goal,
+                    terminal_width(),
+                );
+                draw(&lines);
+            }
+            print!("{}", LEAVE);
+            let _ = io::stdout().flush();
+            // Tokio keeps SIGINT once it has it, so from here on Ctrl-C has
+            // to exit by itself, as it would without the dashboard
+            tokio::spawn(async {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    std::process::exit(130);
+                }
+            });
+        });
+        Some(Dashboard {
+            feed,
+            interrupted,
+            stop,
+            task,
+        })
+    }
+
+    /// Whether Ctrl-C was pressed to stop the run
+    pub fn interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    /// Where the aggregator records finished requests
+    pub fn feed(&self) -> Arc<Mutex<Feed>> {
+        Arc::clone(&self.feed)
+    }
+
+    /// Closes the dashboard and restores the terminal for the results
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+/// One bar per value, as tall against the others as it is against the
+/// largest of them
+pub fn sparkline(values: &[f64]) -> String {
+    let peak: f64 = values.iter().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            if peak > 0.0 {
+                BARS[((value / peak) * (BARS.len() - 1) as f64).round() as usize]
+            } else {
+                BARS[0]
+            }
+        })
+        .collect()
+}
+
+/// Redraws the screen from the top, clearing what the last frame left
+fn draw(lines: &[String]) {
+    let mut frame: String = String::from("\x1b[H");
+    for line in lines {
+        let _ = write!(frame, "{}\x1b[K\r\n", line);
+    }
+    frame.push_str("\x1b[J");
+    let mut stdout: io::StdoutLock = io::stdout().lock();
+    let _ = stdout.write_all(frame.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Columns of the terminal, from $COLUMNS where the shell exports it
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|columns: &usize| *columns > 0)
+        .unwrap_or(100)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / whole as f64
+}
@@ -0,0 +1,96 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Rows of a CSV file, keyed by the column names in its header row
+pub struct DataSet {
+    rows: Vec<HashMap<String, String>>,
+}
+
+impl DataSet {
+    pub fn load(path: &str) -> Result<DataSet, String> {
+        let mut reader: csv::Reader<std::fs::File> = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let columns: Vec<String> = reader
+            .headers()
+            .map_err(|e| format!("failed to read {}: {}", path, e))?
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+
+        let mut rows: Vec<HashMap<String, String>> = Vec::new();
+        for record in reader.records() {
+            let record: csv::StringRecord =
+                record.map_err(|e| format!("failed to parse {}: {}", path, e))?;
+            rows.push(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(|value| value.to_string()))
+                    .collect(),
+            );
+        }
+
+        if rows.is_empty() {
+            return Err(format!("no rows found in {}", path));
+        }
+        Ok(DataSet { rows })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn row(&self, index: usize) -> &HashMap<String, String> {
+        &self.rows[index]
+    }
+}
+
+/// Order in which CSV rows are used
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataOrder {
+    /// Use each row once, in order; the test ends after the last row
+    Sequential,
+    /// Pick a row at random for every request
+    Random,
+    /// Cycle through the rows in order, starting over after the last one
+    Wrap,
+}
+
+/// Chooses the row for each request
+pub struct RowPicker {
+    order: DataOrder,
+    len: usize,
+    next: usize,
+}
+
+impl RowPicker {
+    pub fn new(data: &DataSet, order: DataOrder) -> Self {
+        RowPicker {
+            order,
+            len: data.len(),
+            next: 0,
+        }
+    }
+
+    /// Returns the index of the next row, or None once sequential rows run out
+    pub fn next(&mut self) -> Option<usize> {
+        match self.order {
+            DataOrder::Sequential if self.next >= self.len => None,
+            DataOrder::Sequential => {
+                self.next += 1;
+                Some(self.next - 1)
+            }
+            DataOrder::Random => Some(rand::rng().random_range(0..self.len)),
+            DataOrder::Wrap => {
+                let index: usize = self.next;
+                self.next = (self.next + 1) % self.len;
+                Some(index)
+            }
+        }
+    }
+}
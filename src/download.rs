@@ -0,0 +1,357 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{CONTENT_RANGE, HeaderMap, HeaderName, HeaderValue, RANGE};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+use crate::grpc::print_latency;
+use crate::latencies::Latencies;
+use crate::{LatencyStats, format_bytes, parse_duration, parse_header, parse_size};
+
+/// Download a large object in parallel Range requests
+#[derive(clap::Args, Debug)]
+pub struct DownloadCommand {
+    /// URL of the object to download (must include http:// or https://)
+    #[arg(value_name = "URL")]
+    url: String,
+
+    /// Bytes fetched by each Range request, e.g. 8MB (powers of 1024)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "8MB")]
+    range_size: usize,
+
+    /// Number of times to download the whole object
+    #[arg(short = 'n', long, default_value = "1")]
+    downloads: usize,
+
+    /// Keep downloading for a fixed duration instead, e.g. 30s, 5m
+    #[arg(short = 'z', long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "downloads")]
+    duration: Option<Duration>,
+
+    /// Number of ranges to download concurrently
+    #[arg(short = 'c', long, default_value = "8")]
+    concurrency: usize,
+
+    /// Add a header to every request, e.g. -H "Authorization: Bearer abc" (repeatable)
+    #[arg(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Total time allowed per range, e.g. 30s (default: no timeout)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Time allowed to establish a connection, e.g. 2s (default: no timeout)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    connect_timeout: Option<Duration>,
+
+    /// Skip TLS certificate verification (for self-signed staging certificates)
+    #[arg(short = 'k', long)]
+    insecure: bool,
+
+    /// Output file path for JSON report (optional)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    output: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DownloadReport {
+    url: String,
+    date: DateTime<Utc>,
+    object_size: u64,
+    range_size: usize,
+    concurrency: usize,
+    total_duration_secs: f64,
+    /// Range requests sent
+    ranges: usize,
+    successful: usize,
+    /// Ranges that errored, got a status other than 206, or ended short
+    failed: usize,
+    timed_out: usize,
+    /// Ranges per response status, e.g. {"206": 64}
+    status_codes: BTreeMap<String, usize>,
+    bytes_downloaded: u64,
+    /// Body bytes received per second across all workers
+    throughput_bytes_per_sec: f64,
+    /// Time to download each range in full
+    latency: LatencyStats,
+}
+
+/// How a single Range request ended
+struct RangeResult {
+    status: Option<StatusCode>,
+    bytes: u64,
+    /// The server sent every byte of the range
+    complete: bool,
+    timed_out: bool,
+    duration: Duration,
+}
+
+/// Reads the total size from a Content-Range header such as "bytes 0-0/1048576"
+fn total_size(content_range: &str) -> Option<u64> {
+    content_range
+        .strip_prefix("bytes ")?
+        .split_once('/')?
+        .1
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Finds the object's size with a one-byte Range request, which also checks
+/// that the server supports ranges; unlike HEAD it works for presigned GET URLs
+async fn probe(client: &Client, url: &str) -> Result<u64, String> {
+    let response: reqwest::Response = client
+        .get(url)
+        .header(RANGE, "bytes=0-0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::OK => {
+            return Err(
+                "the server ignored the Range header and sent the whole object (200 OK)"
+                    .to_string(),
+            );
+        }
+        status => return Err(format!("expected 206 Partial Content, got {}", status)),
+    }
+    response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(total_size)
+        .ok_or_else(|| "the response has no Content-Range with the object size".to_string())
+}
+
+/// Downloads bytes `first..=last`, counting the body without keeping it
+async fn fetch_range(client: Client, url: String, first: u64, last: u64) -> RangeResult {
+    let start: Instant = Instant::now();
+    let mut result: RangeResult = RangeResult {
+        status: None,
+        bytes: 0,
+        complete: false,
+        timed_out: false,
+        duration: Duration::ZERO,
+    };
+
+    match client
+        .get(&url)
+        .header(RANGE, format!("bytes={}-{}", first, last))
+        .send()
+        .await
+    {
+        Ok(mut response) => {
+            result.status = Some(response.status());
+            loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => result.bytes += chunk.len() as u64,
+                    Ok(None) => {
+                        result.complete = response.status() == StatusCode::PARTIAL_CONTENT
+                            && result.bytes == last - first + 1;
+                        break;
+                    }
+                    Err(e) => {
+                        result.timed_out = e.is_timeout();
+                        break;
+                    }
+                }
+            }
+        }
+        Err(e) => result.timed_out = e.is_timeout(),
+    }
+
+    result.duration = start.elapsed();
+    result
+}
+
+/// Runs the download test
+pub async fn run(command: DownloadCommand) {
+    let mut headers: HeaderMap = HeaderMap::new();
+    for (name, value) in &command.headers {
+        headers.append(name.clone(), value.clone());
+    }
+    let mut builder: reqwest::ClientBuilder = Client::builder().default_headers(headers);
+    if let Some(timeout) = command.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(connect_timeout) = command.connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if command.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let client: Client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("✗ Failed to build HTTP client: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if command.range_size == 0 {
+        eprintln!("✗ --range-size must be at least 1 byte");
+        std::process::exit(1);
+    }
+    let object_size: u64 = match probe(&client, &command.url).await {
+        Ok(0) => {
+            eprintln!("✗ {} is empty", command.url);
+            std::process::exit(1);
+        }
+        Ok(size) => size,
+        Err(e) => {
+            eprintln!("✗ Failed to get the object size: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let ranges: Vec<(u64, u64)> = (0..object_size)
+        .step_by(command.range_size)
+        .map(|first| {
+            let last: u64 = (first + command.range_size as u64).min(object_size) - 1;
+            (first, last)
+        })
+        .collect();
+
+    println!("Downloading: {}", command.url);
+    println!(
+        "Object size: {} in {} ranges of {}",
+        format_bytes(object_size),
+        ranges.len(),
+        format_bytes(command.range_size as u64)
+    );
+    match command.duration {
+        Some(duration) => println!("Duration: {:.2}s", duration.as_secs_f64()),
+        None => println!("Downloads: {}", command.downloads),
+    }
+    println!("Concurrency: {}", command.concurrency);
+    println!();
+
+    let planned_ranges: Option<usize> = command
+        .duration
+        .is_none()
+        .then(|| command.downloads * ranges.len());
+    let start: Instant = Instant::now();
+    let deadline: Option<Instant> = command.duration.map(|duration| start + duration);
+    let mut tasks: JoinSet<RangeResult> = JoinSet::new();
+    let mut status_codes: BTreeMap<u16, usize> = BTreeMap::new();
+    let mut latencies: Latencies = Latencies::default();
+    let mut successful: usize = 0;
+    let mut timed_out: usize = 0;
+    let mut bytes_downloaded: u64 = 0;
+    let mut spawned: usize = 0;
+
+    let mut record = |result: RangeResult| {
+        if result.complete {
+            print!(".");
+            successful += 1;
+        } else if result.timed_out {
+            print!("T");
+            timed_out += 1;
+        } else {
+            print!("F");
+        }
+        if let Some(status) = result.status {
+            *status_codes.entry(status.as_u16()).or_default() += 1;
+        }
+        bytes_downloaded += result.bytes;
+        latencies.record(result.duration);
+        if latencies.len().is_multiple_of(50) {
+            match planned_ranges {
+                Some(total) => println!(" {}/{}", latencies.len(), total),
+                None => println!(" {}", latencies.len()),
+            }
+        }
+    };
+
+    loop {
+        let finished: bool = match deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => spawned >= command.downloads * ranges.len(),
+        };
+        if finished {
+            break;
+        }
+
+        if tasks.len() >= command.concurrency.max(1) {
+            if let Some(Ok(result)) = tasks.join_next().await {
+                record(result);
+            }
+            continue;
+        }
+
+        let (first, last) = ranges[spawned % ranges.len()];
+        tasks.spawn(fetch_range(
+            client.clone(),
+            command.url.clone(),
+            first,
+            last,
+        ));
+        spawned += 1;
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(result) = result {
+            record(result);
+        }
+    }
+
+    let total_ranges: usize = latencies.len();
+    if !total_ranges.is_multiple_of(50) {
+        println!();
+    }
+
+    let total_duration: Duration = start.elapsed();
+    let failed: usize = total_ranges - successful;
+    let throughput: f64 = bytes_downloaded as f64 / total_duration.as_secs_f64();
+    println!("\n\nResults:");
+    println!("========");
+    println!("Total time: {:.2}s", total_duration.as_secs_f64());
+    println!("Ranges: {}", total_ranges);
+    println!("Successful: {}", successful);
+    println!("Failed: {}", failed);
+    if timed_out > 0 {
+        println!("  Timed out: {}", timed_out);
+    }
+    if !status_codes.is_empty() {
+        println!("Status codes:");
+        for (status, count) in &status_codes {
+            println!("  {}: {}", status, count);
+        }
+    }
+    println!("Downloaded: {}", format_bytes(bytes_downloaded));
+    println!("Throughput: {}/s", format_bytes(throughput as u64));
+
+    let latency_stats: Option<LatencyStats> = LatencyStats::from_latencies(&mut latencies);
+    if let Some(latency) = &latency_stats {
+        print_latency("Latency per range", latency);
+    }
+
+    let report: DownloadReport = DownloadReport {
+        url: command.url.clone(),
+        date: Utc::now(),
+        object_size,
+        range_size: command.range_size,
+        concurrency: command.concurrency,
+        total_duration_secs: total_duration.as_secs_f64(),
+        ranges: total_ranges,
+        successful,
+        failed,
+        timed_out,
+        status_codes: status_codes
+            .into_iter()
+            .map(|(status, count)| (status.to_string(), count))
+            .collect(),
+        bytes_downloaded,
+        throughput_bytes_per_sec: throughput,
+        latency: latency_stats.unwrap_or_default(),
+    };
+
+    if let Some(output_path) = &command.output {
+        match fs::write(output_path, serde_json::to_string_pretty(&report).unwrap()) {
+            Ok(_) => println!("\n✓ Report saved to: {}", output_path),
+            Err(e) => eprintln!("\n✗ Failed to save report: {}", e),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use std::fmt::Write;
+
+const FIRST_NAMES: &[&str] = &[
+    "James",
+    "Mary",
+    "John",
+    "Patricia",
+    "Robert",
+    "Jennifer",
+    "Michael",
+    "Linda",
+    "David",
+    "Elizabeth",
+    "William",
+    "Barbara",
+    "Richard",
+    "Susan",
+    "Joseph",
+    "Jessica",
+    "Thomas",
+    "Sarah",
+    "Charles",
+    "Karen",
+    "Daniel",
+    "Lisa",
+    "Matthew",
+    "Nancy",
+    "Anthony",
+    "Sandra",
+    "Mark",
+    "Ashley",
+    "Wei",
+    "Aiko",
+    "Mateo",
+    "Sofia",
+    "Arjun",
+    "Priya",
+    "Olga",
+    "Kwame",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smith",
+    "Johnson",
+    "Williams",
+    "Brown",
+    "Jones",
+    "Garcia",
+    "Miller",
+    "Davis",
+    "Rodriguez",
+    "Martinez",
+    "Hernandez",
+    "Lopez",
+    "Gonzalez",
+    "Wilson",
+    "Anderson",
+    "Thomas",
+    "Taylor",
+    "Moore",
+    "Jackson",
+    "Martin",
+    "Lee",
+    "Perez",
+    "Thompson",
+    "White",
+    "Harris",
+    "Clark",
+    "Lewis",
+    "Walker",
+    "Chen",
+    "Tanaka",
+    "Silva",
+    "Novak",
+    "Patel",
+    "Ivanova",
+    "Mensah",
+    "Murphy",
+];
+
+const EMAIL_DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+
+/// Generator for realistic-looking random values, e.g. `{{fake.email}}`
+#[derive(Clone, Copy, Debug)]
+pub enum Fake {
+    FirstName,
+    LastName,
+    Name,
+    Username,
+    Email,
+    Ipv4,
+}
+
+impl Fake {
+    /// Looks up a generator by the name after "fake."
+    pub fn from_name(name: &str) -> Option<Fake> {
+        match name {
+            "first_name" => Some(Fake::FirstName),
+            "last_name" => Some(Fake::LastName),
+            "name" => Some(Fake::Name),
+            "username" => Some(Fake::Username),
+            "email" => Some(Fake::Email),
+            "ipv4" => Some(Fake::Ipv4),
+            _ => None,
+        }
+    }
+
+    pub fn write(self, output: &mut String) {
+        let mut rng = rand::rng();
+        let first: &str = FIRST_NAMES.choose(&mut rng).copied().unwrap_or_default();
+        let last: &str = LAST_NAMES.choose(&mut rng).copied().unwrap_or_default();
+
+        match self {
+            Fake::FirstName => output.push_str(first),
+            Fake::LastName => output.push_str(last),
+            Fake::Name => {
+                let _ = write!(output, "{} {}", first, last);
+            }
+            // A random suffix keeps usernames and emails mostly unique
+            Fake::Username => {
+                let _ = write!(
+                    output,
+                    "{}{}{}",
+                    first.to_lowercase(),
+                    last.to_lowercase(),
+                    rng.random_range(1..10_000)
+                );
+            }
+            Fake::Email => {
+                let domain: &str = EMAIL_DOMAINS.choose(&mut rng).copied().unwrap_or_default();
+                let _ = write!(
+                    output,
+                    "{}.{}{}@{}",
+                    first.to_lowercase(),
+                    last.to_lowercase(),
+                    rng.random_range(1..10_000),
+                    domain
+                );
+            }
+            Fake::Ipv4 => {
+                let _ = write!(
+                    output,
+                    "{}.{}.{}.{}",
+                    rng.random_range(1..=223),
+                    rng.random_range(0..=255),
+                    rng.random_range(0..=255),
+                    rng.random_range(1..=254)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use reqwest::multipart::{Form, Part};
+use std::io;
+
+/// One multipart/form-data field from `--form`
+#[derive(Clone, Debug)]
+pub enum FormField {
+    Text {
+        name: String,
+        value: String,
+    },
+    /// File streamed from disk for every request
+    File {
+        name: String,
+        path: String,
+    },
+}
+
+/// Parses a curl-style "NAME=VALUE" field, or "NAME=@PATH" for a file
+pub fn parse_form_field(s: &str) -> Result<FormField, String> {
+    let (name, value) = s
+        .split_once('=')
+        .filter(|(name, _)| !name.trim().is_empty())
+        .ok_or_else(|| format!("expected NAME=VALUE or NAME=@PATH, got \"{}\"", s))?;
+    let name: String = name.trim().to_string();
+    Ok(match value.strip_prefix('@') {
+        Some(path) if !path.is_empty() => FormField::File {
+            name,
+            path: path.to_string(),
+        },
+        Some(_) => return Err(format!("missing file path in \"{}\"", s)),
+        None => FormField::Text {
+            name,
+            value: value.to_string(),
+        },
+    })
+}
+
+/// Builds the form for one request, opening each file so its contents are
+/// streamed instead of held in memory
+pub async fn build(fields: &[FormField]) -> io::Result<Form> {
+    let mut form: Form = Form::new();
+    for field in fields {
+        form = match field {
+            FormField::Text { name, value } => form.text(name.clone(), value.clone()),
+            FormField::File { name, path } => form.part(name.clone(), Part::file(path).await?),
+        };
+    }
+    Ok(form)
+}
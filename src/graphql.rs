@@ -0,0 +1,34 @@
+use serde_json::{Map, Value, json};
+use std::fs;
+
+/// Reads a query and optional JSON variables file and wraps them in the
+/// `{"query": ..., "variables": ...}` envelope GraphQL servers expect
+pub fn load_request(query_path: &str, variables_path: Option<&str>) -> Result<String, String> {
+    let query: String = fs::read_to_string(query_path)
+        .map_err(|e| format!("failed to read {}: {}", query_path, e))?;
+    let variables: Value = match variables_path {
+        Some(path) => {
+            let contents: String =
+                fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+            let variables: Value = serde_json::from_str(&contents)
+                .map_err(|e| format!("invalid JSON in {}: {}", path, e))?;
+            if !variables.is_object() {
+                return Err(format!("{} must contain a JSON object", path));
+            }
+            variables
+        }
+        None => Value::Object(Map::new()),
+    };
+    Ok(json!({ "query": query, "variables": variables }).to_string())
+}
+
+/// True when a response body carries a non-empty GraphQL `errors` array,
+/// which servers send with a 200 status
+pub fn has_errors(body: &[u8]) -> bool {
+    serde_json::from_slice::<Value>(body).is_ok_and(|response| {
+        response
+            .get("errors")
+            .and_then(Value::as_array)
+            .is_some_and(|errors| !errors.is_empty())
+    })
+}
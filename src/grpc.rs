@@ -0,0 +1,655 @@
+use chrono::{DateTime, Utc};
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor, ServiceDescriptor,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::tokio_stream;
+use tonic::metadata::MetadataMap;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Request, Status, Streaming};
+
+use crate::latencies::Latencies;
+use crate::rate::{self, Arrival, RateLimiter};
+use crate::reflection;
+use crate::{LatencyStats, parse_duration, parse_header};
+
+/// Load test a gRPC method, unary or streaming
+#[derive(clap::Args, Debug)]
+pub struct GrpcCommand {
+    /// Server address, e.g. http://localhost:50051 (https:// for TLS)
+    #[arg(value_name = "ADDRESS")]
+    address: String,
+
+    /// Method to call, e.g. helloworld.Greeter/SayHello
+    #[arg(value_name = "METHOD")]
+    method: String,
+
+    /// .proto file defining the service (repeatable)
+    #[arg(long, value_name = "FILE", required_unless_present_any = ["descriptor_set", "reflect"])]
+    proto: Vec<String>,
+
+    /// Directory to look for imported .proto files in (repeatable; default:
+    /// the directory of each --proto file)
+    #[arg(
+        short = 'I',
+        long = "import-path",
+        value_name = "DIR",
+        requires = "proto"
+    )]
+    import_paths: Vec<String>,
+
+    /// Compiled descriptor set defining the service, e.g. from
+    /// protoc --include_imports --descriptor_set_out
+    #[arg(long, value_name = "FILE", conflicts_with = "proto")]
+    descriptor_set: Option<String>,
+
+    /// Fetch the service definition from the server through gRPC server
+    /// reflection instead of local files
+    #[arg(long, conflicts_with_all = ["proto", "descriptor_set"])]
+    reflect: bool,
+
+    /// Request message as JSON (default: an empty message); client-streaming
+    /// methods also take an array of messages to send on each call
+    #[arg(short = 'd', long, value_name = "JSON", conflicts_with = "data_file")]
+    data: Option<String>,
+
+    /// Read the request message as JSON from a file
+    #[arg(long, value_name = "FILE")]
+    data_file: Option<String>,
+
+    /// Add metadata to every call, e.g. -H "authorization: Bearer abc" (repeatable)
+    #[arg(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Total number of calls to make
+    #[arg(short = 'n', long, default_value = "100")]
+    requests: usize,
+
+    /// Run for a fixed duration instead of a call count, e.g. 30s, 5m
+    #[arg(short = 'z', long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "requests")]
+    duration: Option<Duration>,
+
+    /// Number of calls to run concurrently (all share one HTTP/2 connection)
+    #[arg(short = 'c', long, default_value = "10")]
+    concurrency: usize,
+
+    /// Pace calls at a fixed rate (calls per second)
+    #[arg(short = 'q', long, value_name = "RPS", value_parser = rate::parse_rate)]
+    rate: Option<f64>,
+
+    /// Deadline for every call, e.g. 500ms (sent as grpc-timeout)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Output file path for JSON report (optional)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    output: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct GrpcReport {
+    address: String,
+    method: String,
+    date: DateTime<Utc>,
+    total_requests: usize,
+    concurrency: usize,
+    total_duration_secs: f64,
+    successful: usize,
+    failed: usize,
+    /// Calls per gRPC status code, e.g. {"OK": 98, "UNAVAILABLE": 2}
+    status_codes: BTreeMap<String, usize>,
+    requests_per_sec: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_rps: Option<f64>,
+    latency: LatencyStats,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    streaming: Option<StreamReport>,
+}
+
+/// Response message timing for server-streaming and bidirectional methods
+#[derive(Serialize, Deserialize, Debug)]
+struct StreamReport {
+    messages_received: usize,
+    /// Time from starting a call to its first response message
+    time_to_first_message: LatencyStats,
+    /// Time between consecutive response messages of a call
+    inter_arrival: LatencyStats,
+}
+
+pub fn print_latency(title: &str, latency: &LatencyStats) {
+    println!("\n{}:", title);
+    println!("  Min: {:.2}ms", latency.min_ms);
+    println!("  Avg: {:.2}ms", latency.avg_ms);
+    println!("  p50: {:.2}ms", latency.p50_ms);
+    println!("  p95: {:.2}ms", latency.p95_ms);
+    println!("  p99: {:.2}ms", latency.p99_ms);
+    println!("  Max: {:.2}ms", latency.max_ms);
+}
+
+/// Canonical name of a gRPC status code, as used in the gRPC docs
+pub fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+/// Loads the service definitions from .proto files or a descriptor set
+fn load_descriptors(command: &GrpcCommand) -> Result<DescriptorPool, String> {
+    if let Some(path) = &command.descriptor_set {
+        let bytes: Vec<u8> =
+            fs::read(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        return DescriptorPool::decode(bytes.as_slice())
+            .map_err(|e| format!("invalid descriptor set {}: {}", path, e));
+    }
+
+    let mut includes: Vec<&Path> = command.import_paths.iter().map(Path::new).collect();
+    if includes.is_empty() {
+        for proto in &command.proto {
+            let dir: &Path = Path::new(proto).parent().unwrap_or(Path::new("."));
+            let dir: &Path = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            if !includes.contains(&dir) {
+                includes.push(dir);
+            }
+        }
+    }
+    let mut compiler: protox::Compiler =
+        protox::Compiler::new(includes).map_err(|e| e.to_string())?;
+    compiler.include_imports(true);
+    compiler
+        .open_files(&command.proto)
+        .map_err(|e| e.to_string())?;
+    Ok(compiler.descriptor_pool())
+}
+
+/// Splits "package.Service/Method" (or "package.Service.Method") into the
+/// service and method names
+fn split_method(name: &str) -> Result<(&str, &str), String> {
+    let name: &str = name.trim_start_matches('/');
+    name.rsplit_once('/')
+        .or_else(|| name.rsplit_once('.'))
+        .ok_or_else(|| format!("expected SERVICE/METHOD, got \"{}\"", name))
+}
+
+fn find_method(
+    pool: &DescriptorPool,
+    service: &str,
+    method: &str,
+) -> Result<MethodDescriptor, String> {
+    let service: ServiceDescriptor = pool
+        .get_service_by_name(service)
+        .ok_or_else(|| format!("service \"{}\" not found", service))?;
+    service
+        .methods()
+        .find(|candidate| candidate.name() == method)
+        .ok_or_else(|| format!("method \"{}\" not found in {}", method, service.full_name()))
+}
+
+/// Builds the request messages from their JSON form: one object, or for
+/// client-streaming methods an array of objects sent one after another
+fn parse_messages(method: &MethodDescriptor, json: &str) -> Result<Vec<DynamicMessage>, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let values: Vec<serde_json::Value> = match value {
+        serde_json::Value::Array(values) if method.is_client_streaming() => values,
+        serde_json::Value::Array(_) => {
+            return Err(format!(
+                "{} takes a single message, not an array",
+                method.name()
+            ));
+        }
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .map(|value| DynamicMessage::deserialize(method.input(), value).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Encodes and decodes messages whose types are only known at runtime
+#[derive(Clone)]
+struct DynamicCodec {
+    response: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> DynamicEncoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> DynamicDecoder {
+        DynamicDecoder(self.response.clone())
+    }
+}
+
+struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(format!("failed to encode request: {}", e)))
+    }
+}
+
+struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(format!("failed to decode response: {}", e)))
+    }
+}
+
+/// How one call ended, and when its response messages arrived
+struct CallResult {
+    code: Code,
+    duration: Duration,
+    /// Arrival time of each response message, from the start of the call;
+    /// only tracked for server-streaming methods
+    arrivals: Vec<Duration>,
+}
+
+/// Request messages sent on a client stream, in order
+type MessageStream = tokio_stream::Iter<std::vec::IntoIter<DynamicMessage>>;
+
+/// Everything needed to make a call; cloned for each one
+#[derive(Clone)]
+struct Call {
+    channel: Channel,
+    path: PathAndQuery,
+    codec: DynamicCodec,
+    /// Request messages; methods without client streaming take exactly one
+    messages: Arc<Vec<DynamicMessage>>,
+    metadata: MetadataMap,
+    timeout: Option<Duration>,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+impl Call {
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request: Request<T> = Request::new(message);
+        *request.metadata_mut() = self.metadata.clone();
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout);
+        }
+        request
+    }
+
+    /// Sends the request messages and reads every response message
+    async fn exchange(&self, start: Instant, arrivals: &mut Vec<Duration>) -> Result<(), Status> {
+        let mut client: tonic::client::Grpc<Channel> =
+            tonic::client::Grpc::new(self.channel.clone());
+        client
+            .ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let path: PathAndQuery = self.path.clone();
+        let codec: DynamicCodec = self.codec.clone();
+        let messages: Vec<DynamicMessage> = self.messages.as_ref().clone();
+        let mut stream: Streaming<DynamicMessage> =
+            match (self.client_streaming, self.server_streaming) {
+                (false, false) => {
+                    let request: Request<DynamicMessage> = self.request(messages[0].clone());
+                    client.unary(request, path, codec).await?;
+                    return Ok(());
+                }
+                (true, false) => {
+                    let request: Request<MessageStream> =
+                        self.request(tokio_stream::iter(messages));
+                    client.client_streaming(request, path, codec).await?;
+                    return Ok(());
+                }
+                (false, true) => {
+                    let request: Request<DynamicMessage> = self.request(messages[0].clone());
+                    client
+                        .server_streaming(request, path, codec)
+                        .await?
+                        .into_inner()
+                }
+                (true, true) => {
+                    let request: Request<MessageStream> =
+                        self.request(tokio_stream::iter(messages));
+                    client.streaming(request, path, codec).await?.into_inner()
+                }
+            };
+        while stream.message().await?.is_some() {
+            arrivals.push(start.elapsed());
+        }
+        Ok(())
+    }
+
+    async fn run(self) -> CallResult {
+        let start: Instant = Instant::now();
+        let mut arrivals: Vec<Duration> = Vec::new();
+        let result: Result<(), Status> = match self.timeout {
+            // The server may not enforce the deadline, so it's enforced here too
+            Some(timeout) => tokio::time::timeout(timeout, self.exchange(start, &mut arrivals))
+                .await
+                .unwrap_or_else(|_| Err(Status::deadline_exceeded("deadline exceeded"))),
+            None => self.exchange(start, &mut arrivals).await,
+        };
+        let duration: Duration = start.elapsed();
+        let code: Code = match result {
+            Ok(()) => Code::Ok,
+            // Servers differ in how they end a call past its deadline
+            Err(_) if self.timeout.is_some_and(|timeout| duration >= timeout) => {
+                Code::DeadlineExceeded
+            }
+            Err(status) => status.code(),
+        };
+        CallResult {
+            code,
+            duration,
+            arrivals,
+        }
+    }
+}
+
+/// Runs the gRPC load test
+pub async fn run(command: GrpcCommand) {
+    let (service_name, method_name) = match split_method(&command.method) {
+        Ok(names) => names,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut endpoint: Endpoint = match Endpoint::from_str(&command.address) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            eprintln!("✗ Invalid address {}: {}", command.address, e);
+            std::process::exit(1);
+        }
+    };
+    if command.address.starts_with("https://") {
+        endpoint = match endpoint.tls_config(ClientTlsConfig::new().with_native_roots()) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                eprintln!("✗ Failed to set up TLS: {}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+    // Connection failures show up as UNAVAILABLE calls rather than aborting the run
+    let channel: Channel = endpoint.connect_lazy();
+    let mut headers: HeaderMap = HeaderMap::new();
+    for (name, value) in &command.headers {
+        headers.append(name.clone(), value.clone());
+    }
+    let metadata: MetadataMap = MetadataMap::from_headers(headers);
+
+    let pool: Result<DescriptorPool, String> = if command.reflect {
+        reflection::load(&channel, &metadata, service_name).await
+    } else {
+        load_descriptors(&command)
+    };
+    let pool: DescriptorPool = match pool {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("✗ Failed to load service definitions: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let method: MethodDescriptor = match find_method(&pool, service_name, method_name) {
+        Ok(method) => method,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let json: String = match (&command.data, &command.data_file) {
+        (Some(data), _) => data.clone(),
+        (None, Some(path)) => match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("✗ Failed to read data file {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => "{}".to_string(),
+    };
+    let messages: Vec<DynamicMessage> = match parse_messages(&method, &json) {
+        Ok(messages) => messages,
+        Err(e) => {
+            eprintln!(
+                "✗ Invalid request for {}: {}",
+                method.input().full_name(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let method_name: String = format!("{}/{}", method.parent_service().full_name(), method.name());
+    let path: PathAndQuery = match PathAndQuery::from_str(&format!("/{}", method_name)) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("✗ Invalid method path: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let call: Call = Call {
+        channel,
+        path,
+        codec: DynamicCodec {
+            response: method.output(),
+        },
+        messages: Arc::new(messages),
+        metadata,
+        timeout: command.timeout,
+        client_streaming: method.is_client_streaming(),
+        server_streaming: method.is_server_streaming(),
+    };
+
+    println!("Load testing: gRPC {} at {}", method_name, command.address);
+    match (call.client_streaming, call.server_streaming) {
+        (false, false) => {}
+        (true, false) => println!(
+            "Client streaming: {} messages per call",
+            call.messages.len()
+        ),
+        (false, true) => println!("Server streaming"),
+        (true, true) => println!(
+            "Bidirectional streaming: {} messages per call",
+            call.messages.len()
+        ),
+    }
+    match command.duration {
+        Some(duration) => println!("Duration: {:.2}s", duration.as_secs_f64()),
+        None => println!("Total requests: {}", command.requests),
+    }
+    println!("Concurrency: {}", command.concurrency);
+    if let Some(rate) = command.rate {
+        println!("Target rate: {:.2} req/s", rate);
+    }
+    if let Some(timeout) = command.timeout {
+        println!("Deadline: {:.2}s", timeout.as_secs_f64());
+    }
+    println!();
+
+    let planned_requests: Option<usize> = command.duration.is_none().then_some(command.requests);
+    let start: Instant = Instant::now();
+    let deadline: Option<Instant> = command.duration.map(|duration| start + duration);
+    let mut tasks: JoinSet<CallResult> = JoinSet::new();
+    let mut limiter: Option<RateLimiter> = command
+        .rate
+        .map(|rate| RateLimiter::new(rate, Arrival::Uniform));
+    let mut codes: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut latencies: Latencies = Latencies::default();
+    let mut first_messages: Latencies = Latencies::default();
+    let mut inter_arrivals: Latencies = Latencies::default();
+    let mut spawned: usize = 0;
+
+    let mut record = |result: CallResult| {
+        match result.code {
+            Code::Ok => print!("."),
+            Code::DeadlineExceeded => print!("T"),
+            _ => print!("F"),
+        }
+        *codes.entry(code_name(result.code)).or_default() += 1;
+        latencies.record(result.duration);
+        if let Some(first) = result.arrivals.first() {
+            first_messages.record(*first);
+        }
+        for pair in result.arrivals.windows(2) {
+            inter_arrivals.record(pair[1] - pair[0]);
+        }
+        if latencies.len().is_multiple_of(50) {
+            match planned_requests {
+                Some(total) => println!(" {}/{}", latencies.len(), total),
+                None => println!(" {}", latencies.len()),
+            }
+        }
+    };
+
+    loop {
+        let finished: bool = match deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => spawned >= command.requests,
+        };
+        if finished {
+            break;
+        }
+
+        if tasks.len() >= command.concurrency.max(1) {
+            if let Some(Ok(result)) = tasks.join_next().await {
+                record(result);
+            }
+            continue;
+        }
+
+        if let Some(limiter) = limiter.as_mut() {
+            limiter.acquire().await;
+        }
+
+        tasks.spawn(call.clone().run());
+        spawned += 1;
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(result) = result {
+            record(result);
+        }
+    }
+
+    let total_requests: usize = latencies.len();
+    if !total_requests.is_multiple_of(50) {
+        println!();
+    }
+
+    let total_duration: Duration = start.elapsed();
+    let successful: usize = codes.get("OK").copied().unwrap_or(0);
+    let failed: usize = total_requests - successful;
+    println!("\n\nResults:");
+    println!("========");
+    println!("Total time: {:.2}s", total_duration.as_secs_f64());
+    println!("Total requests: {}", total_requests);
+    println!("Successful: {}", successful);
+    println!("Failed: {}", failed);
+    println!("Status codes:");
+    for (code, count) in &codes {
+        println!("  {}: {}", code, count);
+    }
+    let requests_per_sec: f64 = total_requests as f64 / total_duration.as_secs_f64();
+    match command.rate {
+        Some(rate) => println!(
+            "Requests/sec: {:.2} (target: {:.2})",
+            requests_per_sec, rate
+        ),
+        None => println!("Requests/sec: {:.2}", requests_per_sec),
+    }
+
+    let messages_received: usize = first_messages.len() + inter_arrivals.len();
+    if call.server_streaming {
+        println!(
+            "Messages received: {} ({:.2} per call)",
+            messages_received,
+            messages_received as f64 / total_requests.max(1) as f64
+        );
+    }
+
+    let latency_stats: Option<LatencyStats> = LatencyStats::from_latencies(&mut latencies);
+    if let Some(latency) = &latency_stats {
+        print_latency("Latency", latency);
+    }
+    let streaming: Option<StreamReport> = call.server_streaming.then(|| StreamReport {
+        messages_received,
+        time_to_first_message: LatencyStats::from_latencies(&mut first_messages)
+            .unwrap_or_default(),
+        inter_arrival: LatencyStats::from_latencies(&mut inter_arrivals).unwrap_or_default(),
+    });
+    if let Some(streaming) = &streaming {
+        print_latency("Time to first message", &streaming.time_to_first_message);
+        print_latency("Message inter-arrival", &streaming.inter_arrival);
+    }
+
+    let report: GrpcReport = GrpcReport {
+        address: command.address.clone(),
+        method: method_name,
+        date: Utc::now(),
+        total_requests,
+        concurrency: command.concurrency,
+        total_duration_secs: total_duration.as_secs_f64(),
+        successful,
+        failed,
+        status_codes: codes
+            .into_iter()
+            .map(|(code, count)| (code.to_string(), count))
+            .collect(),
+        requests_per_sec,
+        target_rps: command.rate,
+        latency: latency_stats.unwrap_or_default(),
+        streaming,
+    };
+
+    if let Some(output_path) = &command.output {
+        match fs::write(output_path, serde_json::to_string_pretty(&report).unwrap()) {
+            Ok(_) => println!("\n✓ Report saved to: {}", output_path),
+            Err(e) => eprintln!("\n✗ Failed to save report: {}", e),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use base64::Engine;
+use hdrhistogram::Histogram;
+use hdrhistogram::serialization::interval_log::IntervalLogWriterBuilder;
+use hdrhistogram::serialization::{Serializer, V2DeflateSerializer};
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+use crate::latencies::Latencies;
+
+/// Longest latency a histogram holds; slower requests are recorded as this
+const HIGHEST_NANOS: u64 = 3_600_000_000_000;
+
+/// Significant digits kept, as the HdrHistogram tools default to
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Interval maximums read as milliseconds
+const MAX_VALUE_DIVISOR: f64 = 1_000_000.0;
+
+/// HdrHistogram interval log with one compressed histogram of latencies in
+/// nanoseconds for each interval of a run, as read by HistogramLogProcessor
+/// and the other HDR tools.
+///
+/// Each interval is compressed as soon as it is written, so only its line
+/// is kept, not its latencies.
+pub struct IntervalLog {
+    interval: Duration,
+    /// Intervals written so far
+    intervals: u32,
+    /// Their lines, in order
+    lines: Vec<u8>,
+    serializer: V2DeflateSerializer,
+    /// First failure, after which no more intervals are written
+    error: Option<String>,
+}
+
+impl IntervalLog {
+    pub fn new(interval: Duration) -> IntervalLog {
+        IntervalLog {
+            interval,
+            intervals: 0,
+            lines: Vec::new(),
+            serializer: V2DeflateSerializer::new(),
+            error: None,
+        }
+    }
+
+    /// Adds the latencies of the next interval
+    pub fn write(&mut self, latencies: &Latencies) {
+        if self.error.is_none()
+            && let Err(e) = self.line(latencies)
+        {
+            self.error = Some(e);
+        }
+        self.intervals += 1;
+    }
+
+    fn line(&mut self, latencies: &Latencies) -> Result<(), String> {
+        let mut histogram: Histogram<u64> =
+            Histogram::new_with_bounds(1, HIGHEST_NANOS, SIGNIFICANT_DIGITS)
+                .map_err(|e| e.to_string())?;
+        for (duration, count) in latencies.counts() {
+            let nanos: u64 = duration.as_nanos().clamp(1, HIGHEST_NANOS as u128) as u64;
+            histogram.saturating_record_n(nanos, count);
+        }
+        let mut compressed: Vec<u8> = Vec::new();
+        self.serializer
+            .serialize(&histogram, &mut compressed)
+            .map_err(|e| e.to_string())?;
+        // The same line IntervalLogWriter::write_histogram writes
+        writeln!(
+            self.lines,
+            "{:.3},{:.3},{:.3},{}",
+            (self.interval * self.intervals).as_secs_f64(),
+            self.interval.as_secs_f64(),
+            histogram.max() as f64 / MAX_VALUE_DIVISOR,
+            base64::engine::general_purpose::STANDARD.encode(&compressed)
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    /// Returns the log of a run that began at `start`
+    pub fn finish(self, start: SystemTime) -> Result<Vec<u8>, String> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let mut log: Vec<u8> = Vec::new();
+        let mut serializer: V2DeflateSerializer = V2DeflateSerializer::new();
+        let mut writer = IntervalLogWriterBuilder::new()
+            .add_comment(&format!("Logged with loadster {}", crate::VERSION))
+            .with_start_time(start)
+            .with_base_time(start)
+            .with_max_value_divisor(MAX_VALUE_DIVISOR)
+            .begin_log_with(&mut log, &mut serializer)
+            .map_err(|e| e.to_string())?;
+        writer
+            .write_comment("\"StartTimestamp\",\"Interval_Length\",\"Interval_Max\",\"Interval_Compressed_Histogram\"")
+            .map_err(|e| e.to_string())?;
+        drop(writer);
+        log.extend_from_slice(&self.lines);
+        Ok(log)
+    }
+}
@@ -0,0 +1,364 @@
+use std::fmt::Write;
+
+use crate::{Report, VERSION};
+
+/// Size of the charts, in SVG units; they scale to the page
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 260.0;
+
+/// Room left around the plot for the axis labels
+const LEFT: f64 = 60.0;
+const RIGHT: f64 = 20.0;
+const TOP: f64 = 20.0;
+const BOTTOM: f64 = 40.0;
+
+/// Lines drawn across the plot, besides the axis
+const GRID_LINES: usize = 5;
+
+const STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif; color: #1f2328; max-width: 880px; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 1.5em; margin-bottom: 0.2em; }
+h2 { font-size: 1.15em; margin-top: 2em; border-bottom: 1px solid #d0d7de; padding-bottom: 0.3em; }
+.meta { color: #656d76; margin-top: 0; }
+.cards { display: grid; grid-template-columns: repeat(auto-fill, minmax(150px, 1fr)); gap: 0.75em; }
+.card { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.6em 0.8em; }
+.card .label { color: #656d76; font-size: 0.85em; }
+.card .value { font-size: 1.35em; font-weight: 600; }
+svg { width: 100%; height: auto; }
+svg text { font-size: 12px; fill: #656d76; }
+.legend span { margin-right: 1.2em; }
+.legend i { display: inline-block; width: 0.8em; height: 0.8em; margin-right: 0.3em; border-radius: 2px; }
+table { border-collapse: collapse; width: 100%; }
+td, th { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #eaeef2; }
+td.bar { width: 60%; }
+td.bar div { height: 0.9em; border-radius: 2px; }
+.pass { color: #1a7f37; }
+.fail { color: #cf222e; font-weight: 600; }
+footer { margin-top: 3em; color: #8c959f; font-size: 0.85em; }
+";
+
+const P50_COLOR: &str = "#0969da";
+const P95_COLOR: &str = "#bf8700";
+const P99_COLOR: &str = "#cf222e";
+const BAR_COLOR: &str = "#54aeff";
+const ERROR_COLOR: &str = "#cf222e";
+
+/// A standalone page of the results, with the charts drawn as inline SVG so
+/// it needs nothing but a browser
+pub fn render(report: &Report) -> String {
+    let mut html: String = String::new();
+    let title: String = format!("loadster report: {}", escape(&report.url));
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+    let mut meta: String = format!(
+        "{} · {} requests over {:.2}s with concurrency {}",
+        report.date.format("%Y-%m-%d %H:%M:%S UTC"),
+        report.total_requests,
+        report.total_duration_secs,
+        report.concurrency
+    );
+    if let Some(tag) = &report.tag {
+        let _ = write!(meta, " · tag {}", escape(tag));
+    }
+    let _ = writeln!(html, "<p class=\"meta\">{}</p>", meta);
+
+    summary(&mut html, report);
+    latency_over_time(&mut html, report);
+    histogram(&mut html, report);
+    status_breakdown(&mut html, report);
+    checks(&mut html, report);
+
+    let _ = write!(
+        html,
+        "<footer>Generated by loadster {}</footer>\n</body>\n</html>\n",
+        VERSION
+    );
+    html
+}
+
+fn summary(html: &mut String, report: &Report) {
+    let error_rate: f64 = if report.total_requests == 0 {
+        0.0
+    } else {
+        report.failed as f64 / report.total_requests as f64
+    };
+    let cards: [(&str, String); 8] = [
+        ("Requests/sec", format!("{:.2}", report.requests_per_sec)),
+        ("Successful", report.successful.to_string()),
+        ("Failed", report.failed.to_string()),
+        ("Error rate", format!("{:.2}%", error_rate * 100.0)),
+        ("p50", format!("{:.2}ms", report.latency.p50_ms)),
+        ("p95", format!("{:.2}ms", report.latency.p95_ms)),
+        ("p99", format!("{:.2}ms", report.latency.p99_ms)),
+        ("Max", format!("{:.2}ms", report.latency.max_ms)),
+    ];
+    html.push_str("<h2>Summary</h2>\n<div class=\"cards\">\n");
+    for (label, value) in cards {
+        let _ = writeln!(
+            html,
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>",
+            label, value
+        );
+    }
+    html.push_str("</div>\n");
+}
+
+fn latency_over_time(html: &mut String, report: &Report) {
+    html.push_str("<h2>Latency over time</h2>\n");
+    if report.timeseries.is_empty() {
+        html.push_str("<p>No requests completed.</p>\n");
+        return;
+    }
+    let series: [(&str, &str, Vec<f64>); 3] = [
+        (
+            "p50",
+            P50_COLOR,
+            report.timeseries.iter().map(|point| point.p50_ms).collect(),
+        ),
+        (
+            "p95",
+            P95_COLOR,
+            report.timeseries.iter().map(|point| point.p95_ms).collect(),
+        ),
+        (
+            "p99",
+            P99_COLOR,
+            report.timeseries.iter().map(|point| point.p99_ms).collect(),
+        ),
+    ];
+    let peak: f64 = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter().copied())
+        .fold(0.0, f64::max);
+    let top: f64 = nice(peak);
+    let seconds: usize = report.timeseries.len();
+
+    let mut svg: String = open_svg();
+    grid(&mut svg, top, |value| format!("{}ms", number(value)));
+    // One point per second, in the middle of it
+    let x = |index: usize| LEFT + (index as f64 + 0.5) / seconds as f64 * plot_width();
+    let y = |value: f64| TOP + plot_height() * (1.0 - value / top);
+    for (_, color, values) in &series {
+        let points: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| format!("{:.1},{:.1}", x(index), y(*value)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+            color,
+            points.join(" ")
+        );
+    }
+    // A handful of second marks along the bottom
+    let step: usize = seconds.div_ceil(10).max(1);
+    for index in (0..seconds).step_by(step) {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}s</text>",
+            x(index),
+            HEIGHT - BOTTOM + 18.0,
+            report.timeseries[index].second
+        );
+    }
+    svg.push_str("</svg>\n");
+    html.push_str(&svg);
+
+    html.push_str("<p class=\"legend\">");
+    for (name, color, _) in &series {
+        let _ = write!(
+            html,
+            "<span><i style=\"background: {}\"></i>{}</span>",
+            color, name
+        );
+    }
+    html.push_str("</p>\n");
+}
+
+fn histogram(html: &mut String, report: &Report) {
+    html.push_str("<h2>Latency distribution</h2>\n");
+    let slices = &report.latency_distribution;
+    if slices.is_empty() {
+        html.push_str("<p>No requests completed.</p>\n");
+        return;
+    }
+    let peak: f64 = slices
+        .iter()
+        .map(|slice| slice.requests as f64)
+        .fold(0.0, f64::max);
+    let top: f64 = nice(peak);
+
+    let mut svg: String = open_svg();
+    grid(&mut svg, top, number);
+    let width: f64 = plot_width() / slices.len() as f64;
+    for (index, slice) in slices.iter().enumerate() {
+        let height: f64 = plot_height() * slice.requests as f64 / top;
+        let x: f64 = LEFT + index as f64 * width;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\">\
+             <title>up to {:.2}ms: {} requests</title></rect>",
+            x + 2.0,
+            TOP + plot_height() - height,
+            width - 4.0,
+            height,
+            BAR_COLOR,
+            slice.up_to_ms,
+            slice.requests
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">≤{:.2}ms</text>",
+            x + width / 2.0,
+            HEIGHT - BOTTOM + 18.0,
+            slice.up_to_ms
+        );
+    }
+    svg.push_str("</svg>\n");
+    html.push_str(&svg);
+}
+
+fn status_breakdown(html: &mut String, report: &Report) {
+    html.push_str("<h2>Status codes and errors</h2>\n");
+    let mut rows: Vec<(String, usize, &str)> = report
+        .status_codes
+        .iter()
+        .map(|(status, count)| (status.clone(), *count, status_color(status)))
+        .collect();
+    rows.extend(
+        report
+            .error_counts()
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(name, count)| (name.to_string(), count, ERROR_COLOR)),
+    );
+    if rows.is_empty() {
+        html.push_str("<p>No responses or errors.</p>\n");
+        return;
+    }
+    let peak: usize = rows.iter().map(|(_, count, _)| *count).max().unwrap_or(1);
+    html.push_str("<table>\n");
+    for (name, count, color) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td class=\"bar\"><div style=\"width: {:.1}%; background: {}\"></div></td></tr>",
+            escape(&name),
+            count,
+            count as f64 / peak as f64 * 100.0,
+            color
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+/// Thresholds and --baseline checks, when there were any
+fn checks(html: &mut String, report: &Report) {
+    let checks: Vec<(&str, &str, bool)> = report
+        .thresholds
+        .iter()
+        .map(|threshold| ("threshold", threshold.threshold.as_str(), threshold.passed))
+        .chain(report.baseline.iter().flat_map(|baseline| {
+            baseline
+                .checks
+                .iter()
+                .map(|check| ("baseline", check.max_regression.as_str(), check.passed))
+        }))
+        .collect();
+    if checks.is_empty() {
+        return;
+    }
+    html.push_str("<h2>Checks</h2>\n<table>\n");
+    for (kind, name, passed) in checks {
+        let _ = writeln!(
+            html,
+            "<tr><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>",
+            if passed { "pass" } else { "fail" },
+            if passed { "✓" } else { "✗" },
+            escape(name),
+            kind
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn open_svg() -> String {
+    format!(
+        "<svg viewBox=\"0 0 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+        WIDTH, HEIGHT
+    )
+}
+
+/// Horizontal lines from zero up to `top`, labelled on the left
+fn grid(svg: &mut String, top: f64, label: impl Fn(f64) -> String) {
+    for line in 0..=GRID_LINES {
+        let value: f64 = top * line as f64 / GRID_LINES as f64;
+        let y: f64 = TOP + plot_height() * (1.0 - line as f64 / GRID_LINES as f64);
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"{}\"/>\
+             <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            LEFT,
+            y,
+            WIDTH - RIGHT,
+            y,
+            if line == 0 { "#8c959f" } else { "#eaeef2" },
+            LEFT - 6.0,
+            y + 4.0,
+            label(value)
+        );
+    }
+}
+
+fn plot_width() -> f64 {
+    WIDTH - LEFT - RIGHT
+}
+
+fn plot_height() -> f64 {
+    HEIGHT - TOP - BOTTOM
+}
+
+/// The smallest of 1, 2 or 5 times a power of ten at or above `value`, so
+/// the grid falls on round numbers
+fn nice(value: f64) -> f64 {
+    if value <= 0.0 || !value.is_finite() {
+        return 1.0;
+    }
+    let magnitude: f64 = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= value)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// A grid label, without decimals it doesn't need
+fn number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Green for 2xx, amber for 3xx and red for errors, like the summary
+fn status_color(status: &str) -> &'static str {
+    match status.chars().next() {
+        Some('2') => "#2da44e",
+        Some('3') => "#bf8700",
+        _ => ERROR_COLOR,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Url};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::latencies::Latencies;
+use crate::request_log::{Entry, category};
+use crate::{Sample, parse_http_url};
+
+/// Seconds a second stays open after a later one has begun, for requests
+/// queued behind the rest of their iteration
+const LATENESS: i64 = 2;
+
+/// Most lines sent in one write
+const MAX_BATCH: usize = 5000;
+
+/// Longest wait for InfluxDB to answer
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where and how to write, with --influx
+pub struct InfluxTarget {
+    pub url: Url,
+    pub bucket: String,
+    pub org: Option<String>,
+    pub token: Option<String>,
+    /// Write every request too, with --influx-samples
+    pub samples: bool,
+}
+
+/// Requests that finished within one second
+#[derive(Default)]
+struct Second {
+    requests: u64,
+    errors: u64,
+    received: u64,
+    latencies: Latencies,
+}
+
+/// Writes the run to InfluxDB with line protocol while it goes on, with
+/// --influx: the requests of every second as a `loadster` point, and each
+/// request as a `loadster_sample` point with --influx-samples. Writing
+/// happens on a task of its own, so a slow database never holds up the
+/// aggregator.
+pub struct Influx {
+    /// ",target=…" and ",test=…" of every point
+    tags: String,
+    samples: bool,
+    /// Seconds not yet written, by Unix time
+    seconds: BTreeMap<i64, Second>,
+    /// The last second written; later requests from it are counted in the
+    /// oldest one still open so no point is written twice
+    written: Option<i64>,
+    batch: String,
+    lines: usize,
+    batches: mpsc::UnboundedSender<String>,
+    task: JoinHandle<Result<usize, String>>,
+}
+
+impl Influx {
+    /// Checks InfluxDB answers at `target` before the run, then starts
+    /// writing to it, tagging points with the run's `url` and `tag`
+    pub async fn start(
+        target: InfluxTarget,
+        url: &str,
+        tag: Option<&str>,
+    ) -> Result<Influx, String> {
+        let client: Client = Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let ping: reqwest::Response = client
+            .get(endpoint(&target.url, "ping"))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !ping.status().is_success() {
+            return Err(format!("/ping answered {}", ping.status()));
+        }
+
+        let mut write: Url = endpoint(&target.url, "api/v2/write");
+        {
+            let mut query = write.query_pairs_mut();
+            query.append_pair("bucket", &target.bucket);
+            if let Some(org) = &target.org {
+                query.append_pair("org", org);
+            }
+            query.append_pair("precision", "ns");
+        }
+        let (batches, mut queue) = mpsc::unbounded_channel::<String>();
+        let token: Option<String> = target.token;
+        let task: JoinHandle<Result<usize, String>> = tokio::spawn(async move {
+            let mut written: usize = 0;
+            while let Some(batch) = queue.recv().await {
+                let lines: usize = batch.lines().count();
+                let mut request: reqwest::RequestBuilder = client.post(write.clone()).body(batch);
+                if let Some(token) = &token {
+                    request =
+                        request.header(reqwest::header::AUTHORIZATION, format!("Token {}", token));
+                }
+                // Once a write fails the rest would too, so they are dropped
+                // rather than left to time out one by one
+                let response: reqwest::Response =
+                    request.send().await.map_err(|e| e.to_string())?;
+                let status: reqwest::StatusCode = response.status();
+                if !status.is_success() {
+                    // InfluxDB says why on the first line, as JSON
+                    let body: String = response.text().await.unwrap_or_default();
+                    let reason: &str = body.lines().next().unwrap_or_default();
+                    return Err(format!("{} {}", status, reason.trim())
+                        .trim_end()
+                        .to_string());
+                }
+                written += lines;
+            }
+            Ok(written)
+        });
+
+        let mut tags: String = format!(",target={}", escape(url));
+        if let Some(tag) = tag {
+            let _ = write!(tags, ",test={}", escape(tag));
+        }
+        Ok(Influx {
+            tags,
+            samples: target.samples,
+            seconds: BTreeMap::new(),
+            written: None,
+            batch: String::new(),
+            lines: 0,
+            batches,
+            task,
+        })
+    }
+
+    /// Counts the request `worker` sent in the second it finished, writing
+    /// the seconds that are over
+    pub fn record(&mut self, sample: &Sample, worker: usize) {
+        let ago: Duration = Instant::now().saturating_duration_since(sample.finished);
+        let finished: DateTime<Utc> =
+            Utc::now() - chrono::Duration::from_std(ago).unwrap_or_default();
+        let mut second: i64 = finished.timestamp();
+        if let Some(written) = self.written {
+            second = second.max(written + 1);
+        }
+        let tally: &mut Second = self.seconds.entry(second).or_default();
+        tally.requests += 1;
+        tally.latencies.record(sample.duration);
+        if category(&sample.outcome).is_some() {
+            tally.errors += 1;
+        }
+        if let Some(body) = sample.peer.as_ref().and_then(|peer| peer.body.as_ref()) {
+            tally.received += body.received;
+        }
+
+        if self.samples {
+            self.sample(&Entry::of(sample, worker));
+        }
+        let newest: i64 = self.seconds.keys().next_back().copied().unwrap_or(second);
+        let mut over: bool = false;
+        while let Some(entry) = self.seconds.first_entry()
+            && *entry.key() < newest - LATENESS
+        {
+            let (second, tally) = entry.remove_entry();
+            self.second(second, tally);
+            over = true;
+        }
+        if over || self.lines >= MAX_BATCH {
+            self.send();
+        }
+    }
+
+    /// Writes the seconds still open and waits for every write, returning
+    /// how many points were written
+    pub async fn finish(mut self) -> Result<usize, String> {
+        for (second, tally) in std::mem::take(&mut self.seconds) {
+            self.second(second, tally);
+        }
+        self.send();
+        let Influx { batches, task, .. } = self;
+        drop(batches);
+        match task.await {
+            Ok(written) => written,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    fn second(&mut self, second: i64, mut tally: Second) {
+        let error_rate: f64 = tally.errors as f64 / tally.requests.max(1) as f64;
+        let _ = writeln!(
+            self.batch,
+            "loadster{} requests={}i,errors={}i,error_rate={},received_bytes={}i,avg_ms={},p50_ms={},p95_ms={},p99_ms={},max_ms={} {}",
+            self.tags,
+            tally.requests,
+            tally.errors,
+            error_rate,
+            tally.received,
+            ms(tally.latencies.mean()),
+            ms(tally.latencies.percentile(50.0)),
+            ms(tally.latencies.percentile(95.0)),
+            ms(tally.latencies.percentile(99.0)),
+            ms(tally.latencies.max()),
+            second * 1_000_000_000
+        );
+        self.lines += 1;
+        self.written = Some(second);
+    }
+
+    fn sample(&mut self, entry: &Entry) {
+        let _ = write!(self.batch, "loadster_sample{}", self.tags);
+        if let Some(status) = entry.status {
+            let _ = write!(self.batch, ",status={}", status);
+        }
+        if let Some(error) = entry.error {
+            let _ = write!(self.batch, ",error={}", error);
+        }
+        let _ = write!(
+            self.batch,
+            " latency_ms={},worker={}i",
+            entry.latency_ms, entry.worker
+        );
+        if let Some(bytes) = entry.bytes {
+            let _ = write!(self.batch, ",bytes={}i", bytes);
+        }
+        let _ = writeln!(
+            self.batch,
+            " {}",
+            entry.timestamp.timestamp_nanos_opt().unwrap_or_default()
+        );
+        self.lines += 1;
+    }
+
+    fn send(&mut self) {
+        if !self.batch.is_empty() {
+            let _ = self.batches.send(std::mem::take(&mut self.batch));
+            self.lines = 0;
+        }
+    }
+}
+
+/// `path` under the InfluxDB at `base`, which may sit under a path of its
+/// own behind a proxy
+fn endpoint(base: &Url, path: &str) -> Url {
+    let mut url: Url = base.clone();
+    url.set_path(&format!("{}/{}", base.path().trim_end_matches('/'), path));
+    url.set_query(None);
+    url
+}
+
+/// Whole microseconds, in milliseconds, like the request log
+fn ms(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// A tag value, with the characters that end one escaped
+fn escape(value: &str) -> String {
+    let mut escaped: String = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Checks an --influx URL is http:// or https:// with a host
+pub fn parse_influx(s: &str) -> Result<Url, String> {
+    parse_http_url(s, "InfluxDB")
+}
@@ -0,0 +1,262 @@
+use hdrhistogram::Histogram;
+use std::time::Duration;
+
+/// Latencies kept exactly before they are folded into a histogram; about
+/// the memory the histogram itself takes
+const EXACT_LIMIT: usize = 4096;
+
+/// Significant digits the histogram keeps, so percentiles are within 0.1%
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Longest latency recorded, a day; slower ones count as this
+const MAX_MICROS: u64 = 86_400_000_000;
+
+/// Latencies of a set of requests.
+///
+/// A few thousand are kept exactly; past that they go into a histogram of
+/// microseconds that grows only with the slowest value, so memory stays flat
+/// and percentiles need no sort however many requests a run makes.
+#[derive(Clone, Debug)]
+pub enum Latencies {
+    Exact(Vec<Duration>),
+    Histogram(Histogram<u64>),
+}
+
+impl Default for Latencies {
+    fn default() -> Self {
+        Latencies::Exact(Vec::new())
+    }
+}
+
+impl Latencies {
+    pub fn record(&mut self, duration: Duration) {
+        match self {
+            Latencies::Exact(durations) if durations.len() < EXACT_LIMIT => {
+                durations.push(duration);
+            }
+            _ => record(self.histogram(), duration),
+        }
+    }
+
+    /// Adds every latency of `other`
+    pub fn merge(&mut self, other: &Latencies) {
+        match other {
+            Latencies::Exact(durations) => self.extend(durations.iter().copied()),
+            Latencies::Histogram(other) => self
+                .histogram()
+                .add(other)
+                // Both resize themselves, so any value fits
+                .expect("auto-resizing histograms accept every value"),
+        }
+    }
+
+    /// The histogram, folding in the exact latencies if there isn't one yet
+    fn histogram(&mut self) -> &mut Histogram<u64> {
+        if let Latencies::Exact(durations) = self {
+            let mut histogram: Histogram<u64> =
+                Histogram::new(SIGNIFICANT_DIGITS).expect("3 significant digits are supported");
+            for duration in durations.iter() {
+                record(&mut histogram, *duration);
+            }
+            *self = Latencies::Histogram(histogram);
+        }
+        match self {
+            Latencies::Histogram(histogram) => histogram,
+            Latencies::Exact(_) => unreachable!("just replaced with a histogram"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Latencies::Exact(durations) => durations.len(),
+            Latencies::Histogram(histogram) => histogram.len() as usize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn min(&self) -> Duration {
+        match self {
+            Latencies::Exact(durations) => durations.iter().min().copied().unwrap_or_default(),
+            Latencies::Histogram(histogram) => Duration::from_micros(histogram.min()),
+        }
+    }
+
+    pub fn max(&self) -> Duration {
+        match self {
+            Latencies::Exact(durations) => durations.iter().max().copied().unwrap_or_default(),
+            Latencies::Histogram(histogram) => Duration::from_micros(histogram.max()),
+        }
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self {
+            Latencies::Exact(durations) if durations.is_empty() => Duration::ZERO,
+            Latencies::Exact(durations) => {
+                durations.iter().sum::<Duration>() / durations.len() as u32
+            }
+            Latencies::Histogram(histogram) => Duration::from_secs_f64(histogram.mean() / 1e6),
+        }
+    }
+
+    /// Latency `percentile` percent of the requests were at or under,
+    /// sorting the exact latencies in place first
+    pub fn percentile(&mut self, percentile: f64) -> Duration {
+        match self {
+            Latencies::Exact(durations) if durations.is_empty() => Duration::ZERO,
+            Latencies::Exact(durations) => {
+                durations.sort_unstable();
+                let index: usize = (durations.len() as f64 * percentile / 100.0) as usize;
+                durations[index.min(durations.len() - 1)]
+            }
+            Latencies::Histogram(histogram) => {
+                Duration::from_micros(histogram.value_at_quantile(percentile / 100.0))
+            }
+        }
+    }
+
+    /// How many requests fell in each of `count` equal slices of the range
+    /// from the fastest to the slowest, by the upper end of the slice
+    pub fn distribution(&self, count: usize) -> Vec<(Duration, u64)> {
+        let (min, max) = (self.min(), self.max());
+        if self.is_empty() || count == 0 {
+            return Vec::new();
+        }
+        let width: Duration = (max - min) / count as u32;
+        if width.is_zero() {
+            return vec![(max, self.len() as u64)];
+        }
+        let mut slices: Vec<(Duration, u64)> = (1..=count)
+            .map(|slice| (min + width * slice as u32, 0))
+            .collect();
+        // Rounding leaves the last slice a little short of the slowest
+        slices[count - 1].0 = max;
+        for (latency, requests) in self.counts() {
+            let index: u128 = latency.saturating_sub(min).as_nanos() / width.as_nanos();
+            slices[(index as usize).min(count - 1)].1 += requests;
+        }
+        slices
+    }
+
+    /// Each distinct latency with how many requests took it
+    pub fn counts(&self) -> Vec<(Duration, u64)> {
+        match self {
+            Latencies::Exact(durations) => {
+                durations.iter().map(|duration| (*duration, 1)).collect()
+            }
+            Latencies::Histogram(histogram) => histogram
+                .iter_recorded()
+                .map(|value| {
+                    (
+                        Duration::from_micros(value.value_iterated_to()),
+                        value.count_at_value(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromIterator<Duration> for Latencies {
+    fn from_iter<I: IntoIterator<Item = Duration>>(durations: I) -> Self {
+        let mut latencies: Latencies = Latencies::default();
+        for duration in durations {
+            latencies.record(duration);
+        }
+        latencies
+    }
+}
+
+impl Extend<Duration> for Latencies {
+    fn extend<I: IntoIterator<Item = Duration>>(&mut self, durations: I) {
+        for duration in durations {
+            self.record(duration);
+        }
+    }
+}
+
+/// Records `duration` in microseconds, growing the histogram to fit it
+/// (saturating_record would clamp to its current range instead)
+fn record(histogram: &mut Histogram<u64>, duration: Duration) {
+    let micros: u64 = duration.as_micros().min(MAX_MICROS as u128) as u64;
+    histogram
+        .record(micros)
+        .expect("auto-resizing histograms accept values up to a day");
+}
+
+/// Highest rate recorded, a terabyte per second; faster ones, such as a
+/// body sent before the clock could tick, count as this
+const MAX_RATE: f64 = 1e12;
+
+/// Per-request rates, in bytes per second, kept in a histogram of whole
+/// bytes per second like the latencies past `EXACT_LIMIT`, so memory stays
+/// flat however many requests a run makes. The sum and extremes are kept
+/// exactly.
+#[derive(Clone, Debug)]
+pub struct Rates {
+    histogram: Histogram<u64>,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Rates {
+            histogram: Histogram::new(SIGNIFICANT_DIGITS)
+                .expect("3 significant digits are supported"),
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: 0.0,
+        }
+    }
+}
+
+impl Rates {
+    pub fn record(&mut self, rate: f64) {
+        // Nothing sent in no time at all is no rate
+        let rate: f64 = if rate.is_nan() {
+            0.0
+        } else {
+            rate.clamp(0.0, MAX_RATE)
+        };
+        self.histogram
+            .record(rate.round() as u64)
+            .expect("auto-resizing histograms accept rates up to a terabyte per second");
+        self.sum += rate;
+        self.min = self.min.min(rate);
+        self.max = self.max.max(rate);
+    }
+
+    pub fn len(&self) -> usize {
+        self.histogram.len() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn min(&self) -> f64 {
+        if self.is_empty() { 0.0 } else { self.min }
+    }
+
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.is_empty() {
+            0.0
+        } else {
+            self.sum / self.len() as f64
+        }
+    }
+
+    /// Rate `percentile` percent of the requests were at or under, to
+    /// within 0.1%
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        self.histogram.value_at_quantile(percentile / 100.0) as f64
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use crate::baseline;
+use crate::thresholds;
+use crate::{LatencyStats, Report};
+
+/// The results as Markdown tables, to paste into a pull request or an issue
+pub fn render(report: &Report) -> String {
+    let mut markdown: String = String::new();
+    let _ = writeln!(
+        markdown,
+        "## Load test of `{}`\n",
+        report.url.replace('`', "'")
+    );
+    let mut meta: Vec<String> = vec![
+        format!("**Date:** {}", report.date.format("%Y-%m-%d %H:%M:%S UTC")),
+        format!("**Duration:** {:.2}s", report.total_duration_secs),
+        format!("**Concurrency:** {}", report.concurrency),
+    ];
+    if let Some(tag) = &report.tag {
+        meta.push(format!("**Tag:** {}", cell(tag)));
+    }
+    let _ = writeln!(markdown, "{}\n", meta.join(" · "));
+
+    let error_rate: f64 = if report.total_requests == 0 {
+        0.0
+    } else {
+        report.failed as f64 / report.total_requests as f64
+    };
+    markdown.push_str("| Requests | Successful | Failed | Error rate | Requests/sec |\n");
+    markdown.push_str("|---:|---:|---:|---:|---:|\n");
+    let _ = writeln!(
+        markdown,
+        "| {} | {} | {} | {:.2}% | {:.2} |",
+        report.total_requests,
+        report.successful,
+        report.failed,
+        error_rate * 100.0,
+        report.requests_per_sec
+    );
+
+    markdown.push_str("\n### Latency\n\n");
+    latency(&mut markdown, &report.latency);
+
+    let errors: Vec<(&str, usize)> = report
+        .error_counts()
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    if !report.status_codes.is_empty() || !errors.is_empty() {
+        markdown.push_str("\n### Responses\n\n| Response | Requests | Share |\n|---|---:|---:|\n");
+        let rows = report
+            .status_codes
+            .iter()
+            .map(|(status, count)| (status.as_str(), *count))
+            .chain(errors);
+        for (name, count) in rows {
+            let share: f64 = if report.total_requests == 0 {
+                0.0
+            } else {
+                count as f64 / report.total_requests as f64
+            };
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {:.2}% |",
+                cell(name),
+                count,
+                share * 100.0
+            );
+        }
+    }
+
+    if !report.thresholds.is_empty() {
+        markdown.push_str("\n### Thresholds\n\n| | Threshold | Actual |\n|---|---|---:|\n");
+        for threshold in &report.thresholds {
+            // The report keeps the value alone; the expression gives its unit
+            let actual: String = thresholds::parse_threshold(&threshold.threshold)
+                .map_or(threshold.actual.to_string(), |parsed| {
+                    parsed.format_value(threshold.actual)
+                });
+            let _ = writeln!(
+                markdown,
+                "| {} | `{}` | {} |",
+                mark(threshold.passed),
+                code(&threshold.threshold),
+                actual
+            );
+        }
+        if let Some(passed) = report.thresholds_passed {
+            let _ = writeln!(markdown, "\n{}", verdict(passed, "thresholds"));
+        }
+    }
+
+    if let Some(report_baseline) = &report.baseline {
+        let _ = writeln!(
+            markdown,
+            "\n### Baseline\n\nAgainst `{}`:\n\n| | Max regression | Result |\n|---|---|---|",
+            code(&report_baseline.path)
+        );
+        for check in &report_baseline.checks {
+            let result: String = baseline::parse_max_regression(&check.max_regression)
+                .map_or(String::new(), |parsed| parsed.describe(check));
+            let _ = writeln!(
+                markdown,
+                "| {} | `{}` | {} |",
+                mark(check.passed),
+                code(&check.max_regression),
+                result
+            );
+        }
+        let _ = writeln!(
+            markdown,
+            "\n{}",
+            verdict(report_baseline.passed, "baseline checks")
+        );
+    }
+
+    if let Some(aborted) = &report.aborted {
+        let _ = writeln!(
+            markdown,
+            "\n**Aborted:** error rate {:.2}% over the last {} requests went above {:.2}%",
+            aborted.error_rate * 100.0,
+            aborted.window,
+            aborted.limit * 100.0
+        );
+    }
+    markdown
+}
+
+/// The percentiles asked for, or p50, p95 and p99, between avg and max
+fn latency(markdown: &mut String, latency: &LatencyStats) {
+    let mut columns: Vec<(String, f64)> = vec![("avg".to_string(), latency.avg_ms)];
+    if latency.percentiles.is_empty() {
+        columns.push(("p50".to_string(), latency.p50_ms));
+        columns.push(("p95".to_string(), latency.p95_ms));
+        columns.push(("p99".to_string(), latency.p99_ms));
+    }
+    for stat in &latency.percentiles {
+        columns.push((format!("p{}", stat.percentile), stat.ms));
+    }
+    columns.push(("max".to_string(), latency.max_ms));
+
+    let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+    let values: Vec<String> = columns
+        .iter()
+        .map(|(_, ms)| format!("{:.2}ms", ms))
+        .collect();
+    let _ = writeln!(markdown, "| {} |", names.join(" | "));
+    let _ = writeln!(markdown, "|{}", "---:|".repeat(columns.len()));
+    let _ = writeln!(markdown, "| {} |", values.join(" | "));
+}
+
+fn mark(passed: bool) -> &'static str {
+    if passed { "✅" } else { "❌" }
+}
+
+fn verdict(passed: bool, what: &str) -> String {
+    if passed {
+        format!("**✅ All {} passed**", what)
+    } else {
+        format!("**❌ Some {} failed**", what)
+    }
+}
+
+/// Text for a table cell, where a pipe would end it
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Text for a code span in a table cell, which a backtick would end
+fn code(text: &str) -> String {
+    cell(&text.replace('`', "'"))
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::Sample;
+use crate::request_log::category;
+
+/// Upper bounds of the latency histogram's buckets, in seconds
+const BUCKETS: [f64; 14] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Most of a scrape request read before it is answered
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// Counters of the run so far, fed by the aggregator as requests finish
+#[derive(Default)]
+pub struct Metrics {
+    requests: u64,
+    /// Responses per status code
+    statuses: BTreeMap<u16, u64>,
+    /// Failed requests per kind, named like the request log's errors
+    errors: BTreeMap<&'static str, u64>,
+    /// Requests that took up to each of `BUCKETS`, not counting the ones in
+    /// the buckets below
+    buckets: [u64; BUCKETS.len()],
+    latency_sum_secs: f64,
+    bytes_received: u64,
+}
+
+impl Metrics {
+    pub fn record(&mut self, sample: &Sample) {
+        self.requests += 1;
+        if let Some(peer) = &sample.peer {
+            *self.statuses.entry(peer.status).or_default() += 1;
+            if let Some(body) = &peer.body {
+                self.bytes_received += body.received;
+            }
+        }
+        if let Some(error) = category(&sample.outcome) {
+            *self.errors.entry(error).or_default() += 1;
+        }
+        let secs: f64 = sample.duration.as_secs_f64();
+        self.latency_sum_secs += secs;
+        if let Some(bucket) = BUCKETS.iter().position(|bound| secs <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+    }
+
+    /// The counters in Prometheus' text exposition format
+    fn render(&self) -> String {
+        let mut text: String = String::new();
+        header(
+            &mut text,
+            "loadster_requests_total",
+            "counter",
+            "Requests completed since measuring started.",
+        );
+        let _ = writeln!(text, "loadster_requests_total {}", self.requests);
+
+        header(
+            &mut text,
+            "loadster_responses_total",
+            "counter",
+            "Responses received, by status code.",
+        );
+        for (status, count) in &self.statuses {
+            let _ = writeln!(
+                text,
+                "loadster_responses_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        header(
+            &mut text,
+            "loadster_errors_total",
+            "counter",
+            "Failed requests.",
+        );
+        let failed: u64 = self.errors.values().sum();
+        let _ = writeln!(text, "loadster_errors_total {}", failed);
+        header(
+            &mut text,
+            "loadster_errors_by_kind_total",
+            "counter",
+            "Failed requests, by kind of failure.",
+        );
+        for (error, count) in &self.errors {
+            let _ = writeln!(
+                text,
+                "loadster_errors_by_kind_total{{error=\"{}\"}} {}",
+                error, count
+            );
+        }
+
+        header(
+            &mut text,
+            "loadster_request_duration_seconds",
+            "histogram",
+            "Time from sending each request to the end of its response.",
+        );
+        let mut cumulative: u64 = 0;
+        for (bound, count) in BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                text,
+                "loadster_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            text,
+            "loadster_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.requests
+        );
+        let _ = writeln!(
+            text,
+            "loadster_request_duration_seconds_sum {}",
+            self.latency_sum_secs
+        );
+        let _ = writeln!(
+            text,
+            "loadster_request_duration_seconds_count {}",
+            self.requests
+        );
+
+        header(
+            &mut text,
+            "loadster_received_bytes_total",
+            "counter",
+            "Response body bytes received on the wire.",
+        );
+        let _ = writeln!(
+            text,
+            "loadster_received_bytes_total {}",
+            self.bytes_received
+        );
+        text
+    }
+}
+
+pub fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+}
+
+/// Serves the run's metrics for Prometheus to scrape at /metrics while it
+/// goes on, with --metrics-addr
+pub struct MetricsServer {
+    metrics: Arc<Mutex<Metrics>>,
+    pub addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    pub async fn start(addr: SocketAddr) -> io::Result<MetricsServer> {
+        let listener: TcpListener = TcpListener::bind(addr).await?;
+        let addr: SocketAddr = listener.local_addr()?;
+        let metrics: Arc<Mutex<Metrics>> = Arc::new(Mutex::new(Metrics::default()));
+        let served: Arc<Mutex<Metrics>> = Arc::clone(&metrics);
+        let task: JoinHandle<()> = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let metrics: Arc<Mutex<Metrics>> = Arc::clone(&served);
+                tokio::spawn(async move {
+                    let _ = serve(stream, &metrics).await;
+                });
+            }
+        });
+        Ok(MetricsServer {
+            metrics,
+            addr,
+            task,
+        })
+    }
+
+    /// Where the aggregator records finished requests
+    pub fn metrics(&self) -> Arc<Mutex<Metrics>> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Stops serving once the run is over
+    pub fn finish(self) {
+        self.task.abort();
+    }
+}
+
+/// Answers one scrape and closes the connection
+async fn serve(mut stream: TcpStream, metrics: &Mutex<Metrics>) -> io::Result<()> {
+    let mut request: Vec<u8> = Vec::new();
+    let mut buffer: [u8; 1024] = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read: usize = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() > MAX_REQUEST {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let line: String = String::from_utf8_lossy(&request)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path: &str = path.split('?').next().unwrap_or(path);
+    let (status, body): (&str, String) = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => (
+            "200 OK",
+            metrics.lock().unwrap_or_else(|e| e.into_inner()).render(),
+        ),
+        _ => (
+            "404 Not Found",
+            "Metrics are served at /metrics\n".to_string(),
+        ),
+    };
+    let mut response: String = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    if method != "HEAD" {
+        response.push_str(&body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
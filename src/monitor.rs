@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeMetrics};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// How often loadster samples its own resource use
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Share of the CPU cores, or of the runtime's time, past which the client
+/// itself may have limited the run
+const BUSY_LIMIT: f64 = 0.9;
+
+/// Share of the open file limit past which new connections may fail
+const FILES_LIMIT: f64 = 0.9;
+
+/// Linux reports process CPU time in ticks of 1/100s
+const TICKS_PER_SEC: f64 = 100.0;
+
+/// How hard loadster itself worked during the run, so a client bottleneck
+/// isn't mistaken for the server's
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClientReport {
+    /// Process CPU time as a share of every core, where the OS reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent_avg: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent_peak: Option<f64>,
+    pub cores: usize,
+    /// Time the runtime's threads spent running tasks rather than waiting
+    pub runtime_busy_percent_avg: f64,
+    pub runtime_busy_percent_peak: f64,
+    pub runtime_threads: usize,
+    /// Tasks waiting in the runtime's shared queue for a thread
+    pub queue_depth_avg: f64,
+    pub queue_depth_peak: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files_peak: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files_limit: Option<usize>,
+    /// Signs the client was saturated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Samples loadster's CPU, runtime and file use until finished
+pub struct Monitor {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<ClientReport>,
+}
+
+/// Counters read at one instant
+struct Reading {
+    at: Instant,
+    cpu: Option<Duration>,
+    busy: Duration,
+}
+
+/// Use over one interval
+struct Window {
+    cpu: Option<f64>,
+    busy: f64,
+    queue_depth: usize,
+    open_files: Option<usize>,
+}
+
+impl Monitor {
+    /// Starts sampling the current runtime
+    pub fn start() -> Monitor {
+        let (stop, mut stopped) = oneshot::channel::<()>();
+        let task: JoinHandle<ClientReport> = tokio::spawn(async move {
+            let metrics: RuntimeMetrics = Handle::current().metrics();
+            let cores: usize = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+            let mut last: Reading = read(&metrics);
+            let mut windows: Vec<Window> = Vec::new();
+            let mut ticker: tokio::time::Interval = tokio::time::interval(INTERVAL);
+            ticker.tick().await;
+            loop {
+                let stopping: bool = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut stopped => true,
+                };
+                let now: Reading = read(&metrics);
+                let elapsed: f64 = now.at.duration_since(last.at).as_secs_f64();
+                // A sliver at the end says little, unless it is all there is
+                if elapsed >= INTERVAL.as_secs_f64() / 2.0 || windows.is_empty() {
+                    windows.push(Window {
+                        cpu: now.cpu.zip(last.cpu).map(|(now, last)| {
+                            (now - last).as_secs_f64() / elapsed.max(f64::EPSILON) / cores as f64
+                        }),
+                        busy: (now.busy - last.busy).as_secs_f64()
+                            / elapsed.max(f64::EPSILON)
+                            / metrics.num_workers() as f64,
+                        queue_depth: metrics.global_queue_depth(),
+                        open_files: open_files(),
+                    });
+                }
+                last = now;
+                if stopping {
+                    break;
+                }
+            }
+            summarize(&windows, cores, metrics.num_workers())
+        });
+        Monitor { stop, task }
+    }
+
+    /// Stops sampling and reports what was seen
+    pub async fn finish(self) -> Option<ClientReport> {
+        let _ = self.stop.send(());
+        self.task.await.ok()
+    }
+}
+
+fn read(metrics: &RuntimeMetrics) -> Reading {
+    Reading {
+        at: Instant::now(),
+        cpu: cpu_time(),
+        busy: (0..metrics.num_workers())
+            .map(|worker| metrics.worker_total_busy_duration(worker))
+            .sum(),
+    }
+}
+
+fn summarize(windows: &[Window], cores: usize, threads: usize) -> ClientReport {
+    let count: f64 = windows.len().max(1) as f64;
+    let cpu: Vec<f64> = windows.iter().filter_map(|window| window.cpu).collect();
+    let cpu_avg: Option<f64> =
+        (!cpu.is_empty()).then(|| cpu.iter().sum::<f64>() / cpu.len() as f64);
+    let busy_avg: f64 = windows.iter().map(|window| window.busy).sum::<f64>() / count;
+    let queue_avg: f64 = windows
+        .iter()
+        .map(|window| window.queue_depth)
+        .sum::<usize>() as f64
+        / count;
+    let open_files_peak: Option<usize> =
+        windows.iter().filter_map(|window| window.open_files).max();
+    let open_files_limit: Option<usize> = open_files_limit();
+
+    let mut warnings: Vec<String> = Vec::new();
+    if let Some(cpu) = cpu_avg
+        && cpu >= BUSY_LIMIT
+    {
+        warnings.push(format!(
+            "loadster used {:.0}% of the CPU on average",
+            cpu * 100.0
+        ));
+    }
+    if busy_avg >= BUSY_LIMIT {
+        warnings.push(format!(
+            "runtime threads were {:.0}% busy on average; try more --threads",
+            busy_avg * 100.0
+        ));
+    }
+    if queue_avg >= threads as f64 {
+        warnings.push(format!(
+            "{:.0} tasks on average waited for one of {} runtime threads",
+            queue_avg, threads
+        ));
+    }
+    if let (Some(peak), Some(limit)) = (open_files_peak, open_files_limit)
+        && peak as f64 >= limit as f64 * FILES_LIMIT
+    {
+        warnings.push(format!(
+            "{} of {} file descriptors were open; raise the limit with ulimit -n",
+            peak, limit
+        ));
+    }
+
+    ClientReport {
+        cpu_percent_avg: cpu_avg.map(|cpu| cpu * 100.0),
+        cpu_percent_peak: cpu_avg.map(|_| cpu.iter().copied().fold(0.0, f64::max) * 100.0),
+        cores,
+        runtime_busy_percent_avg: busy_avg * 100.0,
+        runtime_busy_percent_peak: windows.iter().map(|window| window.busy).fold(0.0, f64::max)
+            * 100.0,
+        runtime_threads: threads,
+        queue_depth_avg: queue_avg,
+        queue_depth_peak: windows
+            .iter()
+            .map(|window| window.queue_depth)
+            .max()
+            .unwrap_or(0),
+        open_files_peak,
+        open_files_limit,
+        warnings,
+    }
+}
+
+/// User plus system CPU time of the process so far, on Linux
+fn cpu_time() -> Option<Duration> {
+    let stat: String = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may hold spaces, so fields are counted after it
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some(Duration::from_secs_f64((utime + stime) / TICKS_PER_SEC))
+}
+
+/// File descriptors the process has open, on Linux
+fn open_files() -> Option<usize> {
+    Some(fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+/// Soft limit on open file descriptors, on Linux
+fn open_files_limit() -> Option<usize> {
+    let limits: String = fs::read_to_string("/proc/self/limits").ok()?;
+    let line: &str = limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?;
+    line.split_whitespace().nth(3)?.parse().ok()
+}
@@ -0,0 +1,392 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::request_log::Entry;
+
+/// Rows buffered before they are written out as a row group
+const ROW_GROUP_ROWS: usize = 128 * 1024;
+
+/// zstd level the pages are compressed at
+const ZSTD_LEVEL: i32 = 3;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// Physical types, encodings and the like from the Parquet format
+mod format {
+    pub const INT32: i32 = 1;
+    pub const INT64: i32 = 2;
+    pub const DOUBLE: i32 = 5;
+    pub const BYTE_ARRAY: i32 = 6;
+
+    pub const REQUIRED: i32 = 0;
+    pub const OPTIONAL: i32 = 1;
+
+    pub const UTF8: i32 = 0;
+    pub const TIMESTAMP_MICROS: i32 = 10;
+
+    pub const PLAIN: i32 = 0;
+    pub const RLE: i32 = 3;
+
+    pub const ZSTD: i32 = 6;
+
+    pub const DATA_PAGE: i32 = 0;
+}
+
+/// How a column is stored
+struct Column {
+    name: &'static str,
+    physical: i32,
+    optional: bool,
+    converted: Option<i32>,
+}
+
+/// The columns, in the order of `Entry`'s fields
+const COLUMNS: [Column; 6] = [
+    Column {
+        name: "timestamp",
+        physical: format::INT64,
+        optional: false,
+        converted: Some(format::TIMESTAMP_MICROS),
+    },
+    Column {
+        name: "worker",
+        physical: format::INT32,
+        optional: false,
+        converted: None,
+    },
+    Column {
+        name: "status",
+        physical: format::INT32,
+        optional: true,
+        converted: None,
+    },
+    Column {
+        name: "latency_ms",
+        physical: format::DOUBLE,
+        optional: false,
+        converted: None,
+    },
+    Column {
+        name: "bytes",
+        physical: format::INT64,
+        optional: true,
+        converted: None,
+    },
+    Column {
+        name: "error",
+        physical: format::BYTE_ARRAY,
+        optional: true,
+        converted: Some(format::UTF8),
+    },
+];
+
+/// A column's values in a row group, PLAIN encoded, with whether each row
+/// has one when the column is optional
+#[derive(Default)]
+struct Values {
+    plain: Vec<u8>,
+    defined: Vec<bool>,
+}
+
+impl Values {
+    fn push(&mut self, value: Option<impl AsRef<[u8]>>) {
+        self.defined.push(value.is_some());
+        if let Some(value) = value {
+            self.plain.extend_from_slice(value.as_ref());
+        }
+    }
+}
+
+/// Where a column chunk landed in the file
+struct Chunk {
+    offset: u64,
+    uncompressed: u64,
+    compressed: u64,
+}
+
+struct RowGroup {
+    rows: usize,
+    chunks: Vec<Chunk>,
+}
+
+/// Writes requests to a Parquet file as a row group every
+/// `ROW_GROUP_ROWS`, each column a single zstd-compressed page
+pub struct Writer {
+    file: BufWriter<File>,
+    /// Bytes written so far, where the next page goes
+    offset: u64,
+    columns: [Values; 6],
+    rows: usize,
+    row_groups: Vec<RowGroup>,
+}
+
+impl Writer {
+    pub fn create(path: &str) -> io::Result<Writer> {
+        let mut file: BufWriter<File> = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        Ok(Writer {
+            file,
+            offset: MAGIC.len() as u64,
+            columns: Default::default(),
+            rows: 0,
+            row_groups: Vec::new(),
+        })
+    }
+
+    pub fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        let [timestamps, workers, statuses, latencies, bytes, errors] = &mut self.columns;
+        timestamps.push(Some(entry.timestamp.timestamp_micros().to_le_bytes()));
+        workers.push(Some((entry.worker as i32).to_le_bytes()));
+        statuses.push(entry.status.map(|status| i32::from(status).to_le_bytes()));
+        latencies.push(Some(entry.latency_ms.to_le_bytes()));
+        bytes.push(entry.bytes.map(|bytes| (bytes as i64).to_le_bytes()));
+        errors.push(entry.error.map(byte_array));
+        self.rows += 1;
+        if self.rows == ROW_GROUP_ROWS {
+            self.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the rows left and the footer describing every row group
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.rows > 0 {
+            self.write_row_group()?;
+        }
+        let footer: Vec<u8> = self.footer();
+        self.file.write_all(&footer)?;
+        self.file.write_all(&(footer.len() as u32).to_le_bytes())?;
+        self.file.write_all(MAGIC)?;
+        self.file.flush()
+    }
+
+    fn write_row_group(&mut self) -> io::Result<()> {
+        let mut chunks: Vec<Chunk> = Vec::with_capacity(COLUMNS.len());
+        for (column, values) in COLUMNS.iter().zip(std::mem::take(&mut self.columns)) {
+            // A data page is the definition levels of an optional column
+            // followed by the values that aren't null
+            let mut page: Vec<u8> = Vec::new();
+            if column.optional {
+                let levels: Vec<u8> = levels(&values.defined);
+                page.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+                page.extend_from_slice(&levels);
+            }
+            page.extend_from_slice(&values.plain);
+            let compressed: Vec<u8> = zstd::bulk::compress(&page, ZSTD_LEVEL)?;
+
+            let mut header: Compact = Compact::default();
+            header.i32(1, format::DATA_PAGE);
+            header.i32(2, page.len() as i32);
+            header.i32(3, compressed.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, self.rows as i32);
+            header.i32(2, format::PLAIN);
+            header.i32(3, format::RLE);
+            header.i32(4, format::RLE);
+            header.end_struct();
+            let header: Vec<u8> = header.finish();
+
+            self.file.write_all(&header)?;
+            self.file.write_all(&compressed)?;
+            chunks.push(Chunk {
+                offset: self.offset,
+                uncompressed: (header.len() + page.len()) as u64,
+                compressed: (header.len() + compressed.len()) as u64,
+            });
+            self.offset += (header.len() + compressed.len()) as u64;
+        }
+        self.row_groups.push(RowGroup {
+            rows: self.rows,
+            chunks,
+        });
+        self.rows = 0;
+        Ok(())
+    }
+
+    /// The FileMetaData: the schema, and where each column chunk is
+    fn footer(&self) -> Vec<u8> {
+        let mut meta: Compact = Compact::default();
+        meta.i32(1, 1);
+        meta.list(2, Compact::STRUCT, COLUMNS.len() + 1);
+        meta.begin_element();
+        meta.binary(4, b"schema");
+        meta.i32(5, COLUMNS.len() as i32);
+        meta.end_struct();
+        for column in &COLUMNS {
+            meta.begin_element();
+            meta.i32(1, column.physical);
+            meta.i32(
+                3,
+                if column.optional {
+                    format::OPTIONAL
+                } else {
+                    format::REQUIRED
+                },
+            );
+            meta.binary(4, column.name.as_bytes());
+            if let Some(converted) = column.converted {
+                meta.i32(6, converted);
+            }
+            meta.end_struct();
+        }
+        let rows: usize = self.row_groups.iter().map(|group| group.rows).sum();
+        meta.i64(3, rows as i64);
+        meta.list(4, Compact::STRUCT, self.row_groups.len());
+        for group in &self.row_groups {
+            meta.begin_element();
+            meta.list(1, Compact::STRUCT, group.chunks.len());
+            for (column, chunk) in COLUMNS.iter().zip(&group.chunks) {
+                meta.begin_element();
+                meta.i64(2, chunk.offset as i64);
+                meta.begin_struct(3);
+                meta.i32(1, column.physical);
+                let encodings: &[i32] = if column.optional {
+                    &[format::PLAIN, format::RLE]
+                } else {
+                    &[format::PLAIN]
+                };
+                meta.list(2, Compact::I32, encodings.len());
+                for encoding in encodings {
+                    meta.i32_element(*encoding);
+                }
+                meta.list(3, Compact::BINARY, 1);
+                meta.binary_element(column.name.as_bytes());
+                meta.i32(4, format::ZSTD);
+                meta.i64(5, group.rows as i64);
+                meta.i64(6, chunk.uncompressed as i64);
+                meta.i64(7, chunk.compressed as i64);
+                meta.i64(9, chunk.offset as i64);
+                meta.end_struct();
+                meta.end_struct();
+            }
+            let uncompressed: u64 = group.chunks.iter().map(|chunk| chunk.uncompressed).sum();
+            meta.i64(2, uncompressed as i64);
+            meta.i64(3, group.rows as i64);
+            meta.end_struct();
+        }
+        meta.binary(6, format!("loadster version {}", crate::VERSION).as_bytes());
+        meta.finish()
+    }
+}
+
+/// A string as PLAIN encodes it, after its length
+fn byte_array(value: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = (value.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+/// Definition levels of an optional column as RLE runs one bit wide: 1 for
+/// a value, 0 for a null
+fn levels(defined: &[bool]) -> Vec<u8> {
+    let mut encoded: Vec<u8> = Vec::new();
+    let mut index: usize = 0;
+    while index < defined.len() {
+        let run: usize = defined[index..]
+            .iter()
+            .take_while(|value| **value == defined[index])
+            .count();
+        varint(&mut encoded, (run as u64) << 1);
+        encoded.push(defined[index] as u8);
+        index += run;
+    }
+    encoded
+}
+
+fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Thrift's compact protocol, which Parquet's metadata is written in
+#[derive(Default)]
+struct Compact {
+    buffer: Vec<u8>,
+    /// Id of the last field of each struct being written, innermost last
+    fields: Vec<i16>,
+}
+
+impl Compact {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last: i16 = self.fields.last().copied().unwrap_or(0);
+        match id - last {
+            delta @ 1..=15 => self.buffer.push(((delta as u8) << 4) | kind),
+            _ => {
+                self.buffer.push(kind);
+                varint(&mut self.buffer, zigzag(id.into()));
+            }
+        }
+        match self.fields.last_mut() {
+            Some(last) => *last = id,
+            None => self.fields.push(id),
+        }
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, Self::I32);
+        self.i32_element(value);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, Self::I64);
+        varint(&mut self.buffer, zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.binary_element(value);
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.buffer.push(((len as u8) << 4) | kind);
+        } else {
+            self.buffer.push(0xf0 | kind);
+            varint(&mut self.buffer, len as u64);
+        }
+    }
+
+    fn i32_element(&mut self, value: i32) {
+        varint(&mut self.buffer, zigzag(value.into()));
+    }
+
+    fn binary_element(&mut self, value: &[u8]) {
+        varint(&mut self.buffer, value.len() as u64);
+        self.buffer.extend_from_slice(value);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.fields.push(0);
+    }
+
+    /// Starts a struct in a list
+    fn begin_element(&mut self) {
+        self.fields.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buffer.push(0);
+        self.fields.pop();
+    }
+
+    /// Ends the outermost struct
+    fn finish(mut self) -> Vec<u8> {
+        self.buffer.push(0);
+        self.buffer
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
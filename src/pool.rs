@@ -0,0 +1,182 @@
+use rand::Rng;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::Session;
+
+/// One request, or one scenario iteration, handed to a worker
+pub struct Job {
+    pub seq: u64,
+    pub stage: Option<usize>,
+    pub row: Option<usize>,
+    pub target: usize,
+    /// How late it was sent against the --rate schedule
+    pub behind: Option<Duration>,
+    /// Sent during the warm-up, so not recorded
+    pub warmup: bool,
+}
+
+/// --think-time pause, varied by up to --jitter either way
+#[derive(Clone, Copy)]
+pub struct ThinkTime {
+    pub pause: Duration,
+    pub jitter: Option<Duration>,
+}
+
+impl ThinkTime {
+    /// Draws the next pause
+    pub fn draw(&self) -> Duration {
+        match self.jitter {
+            Some(jitter) if !jitter.is_zero() => {
+                let offset: f64 = rand::rng().random_range(-1.0..=1.0) * jitter.as_secs_f64();
+                Duration::from_secs_f64((self.pause.as_secs_f64() + offset).max(0.0))
+            }
+            _ => self.pause,
+        }
+    }
+}
+
+/// What workers tell the run loop
+enum Event {
+    /// A job finished, its requests sent to the aggregator
+    Done,
+    /// The worker can take another job, after its think time
+    Ready(usize),
+}
+
+/// Fixed set of worker tasks the run loop hands jobs to.
+///
+/// Each worker takes one job at a time and pauses for its think time before
+/// the next, so the workers that aren't ready are exactly the slots in use.
+pub struct Pool {
+    jobs: Vec<mpsc::Sender<Job>>,
+    events: mpsc::UnboundedReceiver<Event>,
+    /// Workers waiting for a job, the most recently freed last
+    ready: Vec<usize>,
+    /// Jobs handed out that haven't finished
+    in_flight: usize,
+    workers: JoinSet<()>,
+}
+
+impl Pool {
+    /// Starts `size` workers that run each job with `run`, given the
+    /// worker's index, which sends its requests on to the aggregator
+    /// itself. The first workers keep one of `sessions` for every job; `run`
+    /// gets `None` on the others and returns the session to keep, if any.
+    pub fn start<F, Fut>(
+        size: usize,
+        sessions: Vec<Session>,
+        think: Option<ThinkTime>,
+        run: F,
+    ) -> Pool
+    where
+        F: Fn(usize, Option<Session>, Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<Session>> + Send,
+    {
+        let run: Arc<F> = Arc::new(run);
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let mut sessions = sessions.into_iter();
+        let mut jobs: Vec<mpsc::Sender<Job>> = Vec::with_capacity(size);
+        let mut workers: JoinSet<()> = JoinSet::new();
+        for index in 0..size {
+            let (jobs_tx, mut jobs_rx) = mpsc::channel::<Job>(1);
+            jobs.push(jobs_tx);
+            let events: mpsc::UnboundedSender<Event> = events_tx.clone();
+            let run: Arc<F> = Arc::clone(&run);
+            let mut session: Option<Session> = sessions.next();
+            workers.spawn(async move {
+                while let Some(job) = jobs_rx.recv().await {
+                    session = run(index, session.take(), job).await;
+                    if events.send(Event::Done).is_err() {
+                        break;
+                    }
+                    if let Some(think) = think {
+                        tokio::time::sleep(think.draw()).await;
+                    }
+                    if events.send(Event::Ready(index)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Pool {
+            jobs,
+            events,
+            ready: (0..size).rev().collect(),
+            in_flight: 0,
+            workers,
+        }
+    }
+
+    /// Workers running a job or pausing before the next
+    pub fn busy(&self) -> usize {
+        self.jobs.len() - self.ready.len()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Hands `job` to the worker freed most recently, passing over any that
+    /// stopped, e.g. by panicking; the job comes back if no worker took it
+    pub fn dispatch(&mut self, mut job: Job) -> Result<(), Job> {
+        while let Some(index) = self.ready.pop() {
+            // A ready worker is waiting on its empty channel, so only one
+            // that stopped turns the job away
+            match self.jobs[index].try_send(job) {
+                Ok(()) => {
+                    self.in_flight += 1;
+                    return Ok(());
+                }
+                Err(e) => job = e.into_inner(),
+            }
+        }
+        Err(job)
+    }
+
+    /// Waits for a worker to finish a job or become ready, failing once
+    /// every worker has stopped
+    pub async fn wait(&mut self) -> Result<(), String> {
+        match self.events.recv().await {
+            Some(event) => {
+                self.handle(event);
+                Ok(())
+            }
+            None => Err("every worker stopped".to_string()),
+        }
+    }
+
+    /// Takes note of whatever workers have reported, without waiting
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            self.handle(event);
+        }
+    }
+
+    /// Waits for the jobs in flight to finish, then stops the workers
+    pub async fn finish(mut self) {
+        // Idle workers stop once they have no more jobs coming
+        self.jobs.clear();
+        while self.in_flight > 0 {
+            match self.events.recv().await {
+                Some(event) => self.handle(event),
+                // Every worker stopped, which only a panic would leave a job behind
+                None => break,
+            }
+        }
+        // Workers still pausing for their think time needn't finish it, and
+        // their collectors close once they are gone
+        self.workers.shutdown().await;
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Done => self.in_flight -= 1,
+            Event::Ready(index) => self.ready.push(index),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use clap::ValueEnum;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::stages::Stage;
+
+/// Ready-made load profiles for `run --preset`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    /// Steady baseline, a sudden jump to a multiple of it, then back to the
+    /// baseline to watch the service recover
+    Spike,
+}
+
+/// How long each phase lasts when no --duration is given
+pub const DEFAULT_PHASE: Duration = Duration::from_secs(60);
+
+/// How long the load takes to jump between phases, at most; short phases
+/// jump in a tenth of their length
+const JUMP: Duration = Duration::from_secs(1);
+
+/// A named part of a preset, made of consecutive stages
+pub struct Phase {
+    pub name: &'static str,
+    pub stages: Range<usize>,
+}
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Spike => "spike",
+        }
+    }
+
+    /// Builds the stages for a baseline target, the multiple of it the spike
+    /// reaches, and the length of each phase
+    pub fn stages(&self, baseline: f64, factor: f64, phase: Duration) -> Vec<Stage> {
+        let stage = |duration: Duration, target: f64| Stage { duration, target };
+        match self {
+            Preset::Spike => {
+                let jump: Duration = JUMP.min(phase / 10);
+                let hold: Duration = phase - jump;
+                vec![
+                    stage(jump, baseline),
+                    stage(hold, baseline),
+                    stage(jump, baseline * factor),
+                    stage(hold, baseline * factor),
+                    stage(jump, baseline),
+                    stage(hold, baseline),
+                ]
+            }
+        }
+    }
+
+    /// The phases results are grouped into, each including the jump into it
+    pub fn phases(&self) -> Vec<Phase> {
+        match self {
+            Preset::Spike => vec![
+                Phase {
+                    name: "baseline",
+                    stages: 0..2,
+                },
+                Phase {
+                    name: "spike",
+                    stages: 2..4,
+                },
+                Phase {
+                    name: "recovery",
+                    stages: 4..6,
+                },
+            ],
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use reqwest::{Client, Url, Version};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Barrier;
+use tokio::task::JoinSet;
+
+use crate::connections::ConnectionCounter;
+use crate::targets::Target;
+
+/// Connections opened before measuring, with --prewarm
+pub struct Prewarmed {
+    pub opened: usize,
+    /// Requests that couldn't connect, with the first error
+    pub failed: usize,
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+/// Root of every host the targets point at, once each; templated hosts
+/// aren't known until a request renders them, so they are left out
+pub fn origins(targets: &[Target]) -> Vec<Url> {
+    let mut origins: Vec<Url> = Vec::new();
+    for target in targets {
+        let Ok(url) = Url::parse(&target.url) else {
+            continue;
+        };
+        let Ok(origin) = Url::parse(&url.origin().ascii_serialization()) else {
+            continue;
+        };
+        if !origins.contains(&origin) {
+            origins.push(origin);
+        }
+    }
+    origins
+}
+
+/// Most rounds of requests sent to open the connections
+const ROUNDS: usize = 10;
+
+/// Sends `connections` HEAD requests at once to every origin through each
+/// client, so their pools hold that many open connections when measuring
+/// starts. A request that finds no idle connection opens one, but one that
+/// comes back before the others are sent frees its connection for them, so
+/// rounds are sent until every connection is open. HTTP/2 shares one
+/// connection per host, so a single round does.
+pub async fn prewarm(
+    clients: &[(Arc<Client>, usize)],
+    origins: &[Url],
+    counter: &ConnectionCounter,
+) -> Prewarmed {
+    let started: Instant = Instant::now();
+    let before: usize = counter.opened();
+    let wanted: usize = clients
+        .iter()
+        .map(|(_, connections)| connections * origins.len())
+        .sum();
+    let mut failed: usize = 0;
+    let mut error: Option<String> = None;
+    for _ in 0..ROUNDS {
+        // Every request of the round is sent together, so each finds the
+        // connections taken by the others
+        let barrier: Arc<Barrier> = Arc::new(Barrier::new(wanted));
+        let mut requests: JoinSet<Result<Version, reqwest::Error>> = JoinSet::new();
+        for (client, connections) in clients {
+            for origin in origins {
+                for _ in 0..*connections {
+                    let request: reqwest::RequestBuilder = client.head(origin.clone());
+                    let barrier: Arc<Barrier> = Arc::clone(&barrier);
+                    requests.spawn(async move {
+                        barrier.wait().await;
+                        request.send().await.map(|response| response.version())
+                    });
+                }
+            }
+        }
+        let mut multiplexed: bool = false;
+        while let Some(result) = requests.join_next().await {
+            match result {
+                Ok(Ok(version)) => multiplexed |= version >= Version::HTTP_2,
+                Ok(Err(e)) => {
+                    failed += 1;
+                    error.get_or_insert_with(|| e.to_string());
+                }
+                Err(_) => {}
+            }
+        }
+        if failed > 0 || multiplexed || counter.opened() - before >= wanted {
+            break;
+        }
+    }
+
+    Prewarmed {
+        opened: counter.opened() - before,
+        failed,
+        error,
+        elapsed: started.elapsed(),
+    }
+}
@@ -0,0 +1,217 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use crate::metrics::header;
+use crate::{Report, parse_http_url};
+
+/// Job the results are grouped under on the Pushgateway
+const JOB: &str = "loadster";
+
+/// Longest wait for the Pushgateway to take the results
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushes the final results of the run to a Prometheus Pushgateway, with
+/// --pushgateway, replacing those of the last run of the same test and
+/// target. Returns where they were pushed.
+pub async fn push(gateway: &reqwest::Url, report: &Report, passed: bool) -> Result<String, String> {
+    let url: String = group_url(gateway, report);
+    let response: reqwest::Response = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?
+        .put(&url)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )
+        .body(render(report, passed))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status: reqwest::StatusCode = response.status();
+    if !status.is_success() {
+        let body: String = response.text().await.unwrap_or_default();
+        return Err(format!("{} {}", status, body.trim()).trim_end().to_string());
+    }
+    Ok(url)
+}
+
+/// The group of the run on the Pushgateway, labeled with the test's --tag
+/// (when given) and target. Label values are base64-encoded, as URLs and
+/// tags may hold slashes.
+fn group_url(gateway: &reqwest::Url, report: &Report) -> String {
+    let mut url: String = format!(
+        "{}/metrics/job/{}",
+        gateway.as_str().trim_end_matches('/'),
+        JOB
+    );
+    if let Some(tag) = &report.tag {
+        label(&mut url, "test", tag);
+    }
+    label(&mut url, "target", &report.url);
+    url
+}
+
+fn label(url: &mut String, name: &str, value: &str) {
+    // An empty value needs the "=" the Pushgateway takes for it
+    let encoded: String = if value.is_empty() {
+        "=".to_string()
+    } else {
+        URL_SAFE_NO_PAD.encode(value)
+    };
+    let _ = write!(url, "/{}@base64/{}", name, encoded);
+}
+
+/// The results in Prometheus' text exposition format, as gauges since each
+/// push stands for a whole run
+fn render(report: &Report, passed: bool) -> String {
+    let mut text: String = String::new();
+    gauge(
+        &mut text,
+        "loadster_run_requests",
+        "Requests completed in the run.",
+        report.total_requests as f64,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_failed_requests",
+        "Requests that failed.",
+        report.failed as f64,
+    );
+    let error_rate: f64 = if report.total_requests == 0 {
+        0.0
+    } else {
+        report.failed as f64 / report.total_requests as f64
+    };
+    gauge(
+        &mut text,
+        "loadster_run_error_rate",
+        "Fraction of the requests that failed.",
+        error_rate,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_requests_per_second",
+        "Requests completed per second.",
+        report.requests_per_sec,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_duration_seconds",
+        "How long the run measured for.",
+        report.total_duration_secs,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_concurrency",
+        "Concurrent workers of the run.",
+        report.concurrency as f64,
+    );
+
+    header(
+        &mut text,
+        "loadster_run_latency_seconds",
+        "gauge",
+        "Latency percentiles of the run.",
+    );
+    let latency = &report.latency;
+    let mut percentiles: Vec<(f64, f64)> = latency
+        .percentiles
+        .iter()
+        .map(|stat| (stat.percentile, stat.ms))
+        .collect();
+    if percentiles.is_empty() {
+        percentiles = vec![
+            (50.0, latency.p50_ms),
+            (95.0, latency.p95_ms),
+            (99.0, latency.p99_ms),
+        ];
+    }
+    for (percentile, ms) in percentiles {
+        let _ = writeln!(
+            text,
+            "loadster_run_latency_seconds{{quantile=\"{}\"}} {}",
+            percentile / 100.0,
+            ms / 1000.0
+        );
+    }
+    gauge(
+        &mut text,
+        "loadster_run_latency_avg_seconds",
+        "Average latency of the run.",
+        latency.avg_ms / 1000.0,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_latency_max_seconds",
+        "Slowest request of the run.",
+        latency.max_ms / 1000.0,
+    );
+
+    header(
+        &mut text,
+        "loadster_run_responses",
+        "gauge",
+        "Responses received, by status code.",
+    );
+    for (status, count) in &report.status_codes {
+        let _ = writeln!(
+            text,
+            "loadster_run_responses{{status=\"{}\"}} {}",
+            status, count
+        );
+    }
+    header(
+        &mut text,
+        "loadster_run_errors",
+        "gauge",
+        "Failed requests, by kind of failure.",
+    );
+    // Named like the errors of --metrics-addr and the request log
+    let errors: [(&str, usize); 9] = [
+        ("timed_out", report.timed_out),
+        ("tls_error", report.tls_errors),
+        ("dns_error", report.dns_errors),
+        ("connection_refused", report.connection_refused),
+        ("connection_reset", report.connection_reset),
+        ("body_error", report.body_errors),
+        ("other_error", report.other_errors),
+        ("unexpected_status", report.unexpected_status),
+        ("assertion_failure", report.assertion_failures),
+    ];
+    for (error, count) in errors {
+        let _ = writeln!(text, "loadster_run_errors{{error=\"{}\"}} {}", error, count);
+    }
+
+    gauge(
+        &mut text,
+        "loadster_run_received_bytes",
+        "Response body bytes received on the wire.",
+        report.bytes_received as f64,
+    );
+    gauge(
+        &mut text,
+        "loadster_run_passed",
+        "1 when the run wasn't aborted and passed its thresholds and baseline.",
+        f64::from(u8::from(passed)),
+    );
+    gauge(
+        &mut text,
+        "loadster_run_timestamp_seconds",
+        "When the run ended, in seconds since the Unix epoch.",
+        report.date.timestamp() as f64,
+    );
+    text
+}
+
+fn gauge(text: &mut String, name: &str, help: &str, value: f64) {
+    header(text, name, "gauge", help);
+    let _ = writeln!(text, "{} {}", name, value);
+}
+
+/// Checks a --pushgateway URL is http:// or https:// with a host
+pub fn parse_pushgateway(s: &str) -> Result<reqwest::Url, String> {
+    parse_http_url(s, "Pushgateway")
+}
@@ -0,0 +1,163 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How request starts are spread out at a given rate
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Arrival {
+    /// Evenly spaced, one every 1/rate seconds
+    Uniform,
+    /// Exponentially distributed gaps averaging 1/rate seconds, like
+    /// independent users arriving (a Poisson process)
+    Poisson,
+}
+
+impl Arrival {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arrival::Uniform => "uniform",
+            Arrival::Poisson => "poisson",
+        }
+    }
+
+    /// Draws the gap before the next request as a multiple of the mean gap
+    fn draw(&self) -> f64 {
+        match self {
+            Arrival::Uniform => 1.0,
+            // 1 - u is in (0, 1], so the logarithm is finite
+            Arrival::Poisson => -(1.0 - rand::rng().random::<f64>()).ln(),
+        }
+    }
+}
+
+/// Token-bucket pacer that spaces request starts at a fixed rate.
+///
+/// Tokens accrue at `rate` per second. The bucket holds up to 10ms worth of
+/// tokens so timer granularity doesn't drag the achieved rate below target,
+/// but a stall never turns into a large catch-up burst. With Poisson arrivals
+/// the gap between starts is drawn afresh for every request.
+///
+/// Alongside the paced starts it keeps the schedule a perfectly kept rate
+/// would follow, so the time a request spent waiting for its turn (e.g.
+/// behind slow responses holding every slot) can be added back to its
+/// latency, as wrk2 does to correct for coordinated omission.
+pub struct RateLimiter {
+    rate: f64,
+    arrival: Arrival,
+    /// Gap before the next request, as a multiple of the mean gap
+    draw: f64,
+    capacity: Duration,
+    last: Option<Instant>,
+    /// When the next request was due had every request started on time
+    due: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, arrival: Arrival) -> Self {
+        let mut limiter: RateLimiter = RateLimiter {
+            rate,
+            arrival,
+            draw: arrival.draw(),
+            capacity: Duration::ZERO,
+            last: None,
+            due: None,
+        };
+        limiter.set_rate(rate);
+        limiter
+    }
+
+    /// Changes the rate used for subsequent requests
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+        self.capacity = Duration::from_secs_f64(1.0 / rate).max(Duration::from_millis(10));
+    }
+
+    /// Gap between the last request and the next one
+    fn gap(&self) -> Duration {
+        Duration::from_secs_f64(self.draw / self.rate)
+    }
+
+    /// Time remaining until the next request is allowed to start
+    pub fn wait_time(&self) -> Duration {
+        match self.last {
+            Some(last) => (last + self.gap()).saturating_duration_since(Instant::now()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Waits until the next request is allowed to start, and returns how far
+    /// behind the ideal schedule it is starting
+    pub async fn acquire(&mut self) -> Duration {
+        let now: Instant = Instant::now();
+        let gap: Duration = self.gap();
+        let mut slot: Instant = match self.last {
+            Some(last) => last + gap,
+            None => now,
+        };
+
+        if slot > now {
+            tokio::time::sleep_until(slot).await;
+        } else if now - slot > self.capacity {
+            slot = now - self.capacity;
+        }
+        self.last = Some(slot);
+
+        let due: Instant = self.due.unwrap_or(slot);
+        self.due = Some(due + gap);
+        self.draw = self.arrival.draw();
+        Instant::now().saturating_duration_since(due)
+    }
+}
+
+/// Releases requests in bursts of `size` at once, one burst every
+/// `interval`, instead of spacing them out.
+///
+/// A burst that can't start on time (e.g. because the last one still holds
+/// every slot) starts as soon as it can, and the schedule continues from
+/// there rather than catching up with bursts back to back.
+pub struct BurstPacer {
+    size: usize,
+    interval: Duration,
+    /// When the current burst starts
+    next: Option<Instant>,
+    /// Requests already released from the current burst
+    released: usize,
+}
+
+impl BurstPacer {
+    pub fn new(size: usize, interval: Duration) -> Self {
+        BurstPacer {
+            size: size.max(1),
+            interval,
+            next: None,
+            released: 0,
+        }
+    }
+
+    /// Waits until the next request's burst is due
+    pub async fn acquire(&mut self) {
+        let now: Instant = Instant::now();
+        let start: Instant = match self.next {
+            Some(start) if self.released == self.size => {
+                self.released = 0;
+                (start + self.interval).max(now)
+            }
+            Some(start) => start,
+            None => now,
+        };
+        self.next = Some(start);
+        tokio::time::sleep_until(start).await;
+        self.released += 1;
+    }
+}
+
+/// Parses a positive requests-per-second value
+pub fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("expected a positive number, got \"{}\"", s)),
+    }
+}
@@ -0,0 +1,36 @@
+use reqwest::redirect::{Action, Attempt, Policy};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Redirects followed per request when --max-redirects isn't given
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// True for statuses that send the client elsewhere with a Location header
+pub fn is_redirect(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Counts redirects the client follows, which never show up as responses
+#[derive(Clone, Default)]
+pub struct RedirectCounter {
+    followed: Arc<AtomicUsize>,
+}
+
+impl RedirectCounter {
+    pub fn followed(&self) -> usize {
+        self.followed.load(Ordering::Relaxed)
+    }
+
+    /// Follows up to `max` redirects per request, counting each one
+    pub fn policy(&self, max: usize) -> Policy {
+        let followed: Arc<AtomicUsize> = Arc::clone(&self.followed);
+        Policy::custom(move |attempt: Attempt| -> Action {
+            // `previous` includes the original URL
+            if attempt.previous().len() > max {
+                return attempt.error(format!("more than {} redirects", max));
+            }
+            followed.fetch_add(1, Ordering::Relaxed);
+            attempt.follow()
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use prost::Message;
+use prost_reflect::DescriptorPool;
+use prost_reflect::prost_types::FileDescriptorProto;
+use std::collections::HashSet;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::tokio_stream;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status, Streaming};
+use tonic_prost::ProstCodec;
+use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1::{ServerReflectionRequest, ServerReflectionResponse};
+
+/// gRPC server reflection services, newest first; servers that predate v1
+/// only offer v1alpha, whose messages are the same on the wire
+const REFLECTION_PATHS: [&str; 2] = [
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// Asks the server for the file defining `service` and every file it imports
+pub async fn load(
+    channel: &Channel,
+    metadata: &MetadataMap,
+    service: &str,
+) -> Result<DescriptorPool, String> {
+    for path in REFLECTION_PATHS {
+        match fetch_files(channel, metadata, path, service).await {
+            Ok(files) => {
+                let mut pool: DescriptorPool = DescriptorPool::new();
+                pool.add_file_descriptor_protos(files)
+                    .map_err(|e| format!("invalid descriptor from reflection: {}", e))?;
+                return Ok(pool);
+            }
+            Err(status) if status.code() == Code::Unimplemented => continue,
+            Err(status) => {
+                return Err(format!(
+                    "reflection failed: {} ({})",
+                    status.message(),
+                    crate::grpc::code_name(status.code())
+                ));
+            }
+        }
+    }
+    Err("the server doesn't support reflection".to_string())
+}
+
+/// Follows imports until every file needed for `service` has been fetched
+async fn fetch_files(
+    channel: &Channel,
+    metadata: &MetadataMap,
+    path: &'static str,
+    service: &str,
+) -> Result<Vec<FileDescriptorProto>, Status> {
+    let mut files: Vec<FileDescriptorProto> = Vec::new();
+    let mut requested: HashSet<String> = HashSet::new();
+    let mut pending: Vec<MessageRequest> =
+        vec![MessageRequest::FileContainingSymbol(service.to_string())];
+
+    while let Some(message) = pending.pop() {
+        for file in ask(channel, metadata, path, message).await? {
+            if !files.iter().any(|known| known.name == file.name) {
+                files.push(file);
+            }
+        }
+        for file in &files {
+            for dependency in &file.dependency {
+                let known: bool = files
+                    .iter()
+                    .any(|file| file.name.as_deref() == Some(dependency.as_str()));
+                if !known && requested.insert(dependency.clone()) {
+                    pending.push(MessageRequest::FileByFilename(dependency.clone()));
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Sends one reflection request and decodes the files in the answer
+async fn ask(
+    channel: &Channel,
+    metadata: &MetadataMap,
+    path: &'static str,
+    message: MessageRequest,
+) -> Result<Vec<FileDescriptorProto>, Status> {
+    let mut client: tonic::client::Grpc<Channel> = tonic::client::Grpc::new(channel.clone());
+    client
+        .ready()
+        .await
+        .map_err(|e| Status::unavailable(e.to_string()))?;
+
+    let mut request: Request<tokio_stream::Once<ServerReflectionRequest>> =
+        Request::new(tokio_stream::once(ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(message),
+        }));
+    *request.metadata_mut() = metadata.clone();
+    let codec: ProstCodec<ServerReflectionRequest, ServerReflectionResponse> =
+        ProstCodec::default();
+    let mut responses: Streaming<ServerReflectionResponse> = client
+        .streaming(request, PathAndQuery::from_static(path), codec)
+        .await?
+        .into_inner();
+    let response: ServerReflectionResponse = responses
+        .message()
+        .await?
+        .ok_or_else(|| Status::internal("no reflection response"))?;
+
+    match response.message_response {
+        Some(MessageResponse::FileDescriptorResponse(files)) => files
+            .file_descriptor_proto
+            .iter()
+            .map(|bytes| {
+                FileDescriptorProto::decode(bytes.as_slice())
+                    .map_err(|e| Status::internal(format!("invalid file descriptor: {}", e)))
+            })
+            .collect(),
+        Some(MessageResponse::ErrorResponse(error)) => Err(Status::new(
+            Code::from(error.error_code),
+            error.error_message,
+        )),
+        _ => Err(Status::internal("unexpected reflection response")),
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+
+use crate::parquet;
+use crate::{Outcome, Sample};
+
+/// Columns of the CSV export, in the order of `Entry`'s fields
+const CSV_HEADER: [&str; 6] = [
+    "timestamp",
+    "worker",
+    "status",
+    "latency_ms",
+    "bytes",
+    "error",
+];
+
+/// One line of the log, or row of the CSV and Parquet exports
+#[derive(Serialize)]
+pub struct Entry {
+    /// When the request was sent
+    pub timestamp: DateTime<Utc>,
+    pub worker: usize,
+    pub status: Option<u16>,
+    pub latency_ms: f64,
+    /// Response body bytes on the wire
+    pub bytes: Option<u64>,
+    pub error: Option<&'static str>,
+}
+
+impl Entry {
+    /// The request `worker` sent
+    pub fn of(sample: &Sample, worker: usize) -> Entry {
+        // Samples are logged a moment after they finish, so the wall clock
+        // is wound back to when they were sent
+        let ago: std::time::Duration =
+            Instant::now().saturating_duration_since(sample.finished) + sample.duration;
+        Entry {
+            timestamp: Utc::now() - chrono::Duration::from_std(ago).unwrap_or_default(),
+            worker,
+            status: sample.peer.as_ref().map(|peer| peer.status),
+            // Whole microseconds, as more only reads as noise
+            latency_ms: sample.duration.as_micros() as f64 / 1000.0,
+            bytes: sample
+                .peer
+                .as_ref()
+                .and_then(|peer| peer.body.as_ref())
+                .map(|body| body.received),
+            error: category(&sample.outcome),
+        }
+    }
+}
+
+enum Writer {
+    Json(BufWriter<File>),
+    Csv(Box<csv::Writer<File>>),
+    Parquet(Box<parquet::Writer>),
+}
+
+/// Every measured request written while the run goes on, as a line of JSON
+/// with --request-log, a CSV row with --csv, or a Parquet row with
+/// --parquet
+pub struct RequestLog {
+    pub path: String,
+    writer: Writer,
+    /// The first write that failed; the rest of the log is skipped
+    error: Option<io::Error>,
+}
+
+impl RequestLog {
+    pub fn create(path: &str) -> io::Result<RequestLog> {
+        Ok(RequestLog {
+            path: path.to_string(),
+            writer: Writer::Json(BufWriter::new(File::create(path)?)),
+            error: None,
+        })
+    }
+
+    /// Starts a CSV export, with the header written up front so the columns
+    /// are there even when no request finished
+    pub fn create_csv(path: &str) -> io::Result<RequestLog> {
+        let mut writer: csv::Writer<File> = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(path)?;
+        writer.write_record(CSV_HEADER)?;
+        Ok(RequestLog {
+            path: path.to_string(),
+            writer: Writer::Csv(Box::new(writer)),
+            error: None,
+        })
+    }
+
+    /// Starts a Parquet export, written a row group at a time
+    pub fn create_parquet(path: &str) -> io::Result<RequestLog> {
+        Ok(RequestLog {
+            path: path.to_string(),
+            writer: Writer::Parquet(Box::new(parquet::Writer::create(path)?)),
+            error: None,
+        })
+    }
+
+    /// Writes the request `worker` sent
+    pub fn write(&mut self, sample: &Sample, worker: usize) {
+        if self.error.is_some() {
+            return;
+        }
+        let entry: Entry = Entry::of(sample, worker);
+        let result: io::Result<()> = match &mut self.writer {
+            Writer::Json(writer) => serde_json::to_writer(&mut *writer, &entry)
+                .map_err(io::Error::from)
+                .and_then(|_| writer.write_all(b"\n")),
+            Writer::Csv(writer) => writer.serialize(&entry).map_err(io::Error::from),
+            Writer::Parquet(writer) => writer.write(&entry),
+        };
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    /// Flushes what is left, returning the first error writing the log
+    pub fn finish(self) -> io::Result<()> {
+        match (self.error, self.writer) {
+            (Some(e), _) => Err(e),
+            (None, Writer::Json(mut writer)) => writer.flush(),
+            (None, Writer::Csv(mut writer)) => writer.flush(),
+            (None, Writer::Parquet(writer)) => writer.finish(),
+        }
+    }
+}
+
+/// Names the kind of failure like the report's error counts, or `None` for
+/// a successful request
+pub fn category(outcome: &Outcome) -> Option<&'static str> {
+    match outcome {
+        Outcome::Response(_) => None,
+        Outcome::Timeout => Some("timed_out"),
+        Outcome::TlsError => Some("tls_error"),
+        Outcome::DnsError => Some("dns_error"),
+        Outcome::ConnectionRefused => Some("connection_refused"),
+        Outcome::ConnectionReset => Some("connection_reset"),
+        Outcome::BodyError => Some("body_error"),
+        Outcome::UnexpectedStatus(_) => Some("unexpected_status"),
+        Outcome::AssertionFailed(_) => Some("assertion_failure"),
+        Outcome::Error => Some("other_error"),
+        Outcome::ExtractFailed => Some("extract_failed"),
+        Outcome::GraphqlError => Some("graphql_error"),
+    }
+}
@@ -0,0 +1,138 @@
+use reqwest::dns::{Addrs, Name, Resolving};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::connections;
+
+/// DNS override in curl's `--resolve` format, pinning a host to addresses
+#[derive(Clone, Debug)]
+pub struct Resolve {
+    pub host: String,
+    pub addrs: Vec<SocketAddr>,
+}
+
+/// Parses "HOST:PORT:ADDR[,ADDR...]", e.g. "example.com:443:10.0.0.5";
+/// IPv6 addresses may be wrapped in brackets
+pub fn parse_resolve(s: &str) -> Result<Resolve, String> {
+    let invalid = || format!("expected HOST:PORT:ADDR, got \"{}\"", s);
+    let (host, rest) = s.split_once(':').ok_or_else(invalid)?;
+    let (port, addrs) = rest.split_once(':').ok_or_else(invalid)?;
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port: u16 = port
+        .parse()
+        .map_err(|_| format!("invalid port \"{}\" in \"{}\"", port, s))?;
+
+    let addrs: Vec<SocketAddr> = addrs
+        .split(',')
+        .map(|addr| {
+            let addr: &str = addr.trim();
+            let ip: &str = addr
+                .strip_prefix('[')
+                .and_then(|a| a.strip_suffix(']'))
+                .unwrap_or(addr);
+            ip.parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, port))
+                .map_err(|_| format!("invalid IP address \"{}\" in \"{}\"", addr, s))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Resolve {
+        host: host.to_ascii_lowercase(),
+        addrs,
+    })
+}
+
+/// Combines overrides by host, since addresses are pinned per hostname
+/// regardless of the port
+pub fn by_host(entries: &[Resolve]) -> Vec<Resolve> {
+    let mut merged: Vec<Resolve> = Vec::new();
+    for entry in entries {
+        match merged.iter_mut().find(|m| m.host == entry.host) {
+            Some(existing) => {
+                for addr in &entry.addrs {
+                    if !existing.addrs.contains(addr) {
+                        existing.addrs.push(*addr);
+                    }
+                }
+            }
+            None => merged.push(entry.clone()),
+        }
+    }
+    merged
+}
+
+/// Hostname lookup that failed, kept distinct from connection errors
+#[derive(Debug)]
+pub struct DnsError {
+    host: String,
+    error: std::io::Error,
+}
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to resolve {}: {}", self.host, self.error)
+    }
+}
+
+impl std::error::Error for DnsError {}
+
+/// System DNS resolver with `--resolve` overrides, whose failures are
+/// reported as `DnsError`.
+///
+/// New connections use the first address that works, so when spreading,
+/// each lookup starts the list at the next address in turn.
+pub struct Resolver {
+    overrides: HashMap<String, Vec<SocketAddr>>,
+    spread: bool,
+    next: AtomicUsize,
+}
+
+impl Resolver {
+    pub fn new(overrides: &[Resolve], spread: bool) -> Self {
+        Resolver {
+            overrides: overrides
+                .iter()
+                .map(|entry| (entry.host.clone(), entry.addrs.clone()))
+                .collect(),
+            spread,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl reqwest::dns::Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host: String = name.as_str().to_ascii_lowercase();
+        let pinned: Option<Vec<SocketAddr>> = self.overrides.get(&host).cloned();
+        let offset: Option<usize> = self
+            .spread
+            .then(|| self.next.fetch_add(1, Ordering::Relaxed));
+
+        Box::pin(async move {
+            let mut addrs: Vec<SocketAddr> = match pinned {
+                Some(addrs) => addrs,
+                None => {
+                    let start: Instant = Instant::now();
+                    let lookup: std::io::Result<Vec<SocketAddr>> =
+                        tokio::net::lookup_host((host.as_str(), 0))
+                            .await
+                            .map(|addrs| addrs.collect());
+                    connections::record_lookup(start.elapsed());
+                    lookup.map_err(|error| DnsError { host, error })?
+                }
+            };
+            if let Some(offset) = offset
+                && !addrs.is_empty()
+            {
+                let len: usize = addrs.len();
+                addrs.rotate_left(offset % len);
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
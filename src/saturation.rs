@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::BucketReport;
+
+/// Windows a staged or shaped run is split into when --bucket isn't given
+const WINDOWS: u32 = 20;
+
+/// A window is saturated once its p95 reaches this multiple of the baseline
+const LATENCY_FACTOR: f64 = 2.0;
+
+/// ...or more than this share of its requests fail
+const MAX_ERROR_RATE: f64 = 0.01;
+
+/// Windows with fewer requests say too little about latency to count
+const MIN_REQUESTS: usize = 5;
+
+/// Estimate of how much load the service took before latency inflected
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaturationReport {
+    /// Length of the windows the run was measured in
+    pub window_secs: f64,
+    /// Lowest p95 of any window, taken as the unloaded latency
+    pub baseline_p95_ms: f64,
+    /// Most requests per second any window completed
+    pub peak_rps: f64,
+    /// Most requests per second completed before the first saturated window;
+    /// only set when the service saturated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saturation_rps: Option<f64>,
+    /// Seconds into the run the first saturated window starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saturated_at_secs: Option<f64>,
+}
+
+/// Window length for a load profile of `total` length, between 1s and 1m
+pub fn window(total: Duration) -> Duration {
+    (total / WINDOWS).clamp(Duration::from_secs(1), Duration::from_secs(60))
+}
+
+/// Finds the first window whose p95 reached `LATENCY_FACTOR` times the
+/// baseline or whose error rate went over `MAX_ERROR_RATE`, and the highest
+/// throughput the windows before it held
+pub fn analyze(windows: &[BucketReport], size: Duration) -> Option<SaturationReport> {
+    let measured: Vec<&BucketReport> = windows
+        .iter()
+        .filter(|window| window.requests >= MIN_REQUESTS)
+        // A sliver of a window cut short by the end of the run would skew
+        // the throughput
+        .filter(|window| window.duration_secs >= size.as_secs_f64() / 2.0)
+        .collect();
+    let baseline_p95_ms: f64 = measured
+        .iter()
+        .map(|window| window.latency.p95_ms)
+        .min_by(f64::total_cmp)?;
+    let peak_rps: f64 = measured
+        .iter()
+        .map(|window| window.requests_per_sec)
+        .fold(0.0, f64::max);
+
+    let saturated = |window: &&BucketReport| {
+        window.latency.p95_ms >= baseline_p95_ms * LATENCY_FACTOR
+            || window.failed as f64 / window.requests as f64 > MAX_ERROR_RATE
+    };
+    let first: Option<usize> = measured.iter().position(saturated);
+    let (saturation_rps, saturated_at_secs) = match first {
+        Some(index) => (
+            Some(
+                measured[..index]
+                    .iter()
+                    .map(|window| window.requests_per_sec)
+                    .fold(0.0, f64::max),
+            ),
+            Some(measured[index].start_secs),
+        ),
+        None => (None, None),
+    };
+
+    Some(SaturationReport {
+        window_secs: size.as_secs_f64(),
+        baseline_p95_ms,
+        peak_rps,
+        saturation_rps,
+        saturated_at_secs,
+    })
+}
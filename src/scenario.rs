@@ -0,0 +1,430 @@
+use bytes::Bytes;
+use reqwest::Method;
+use reqwest::header::{CONTENT_ENCODING, HeaderName, HeaderValue};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::assertions::{self, BodyMatcher};
+use crate::auth::{self, Auth};
+use crate::compression::{self, Counter};
+use crate::template::{Context, Template};
+use crate::trace::Trace;
+use crate::upload::{self, UploadClock};
+use crate::{BodySize, Outcome, Peer, RequestOptions, Sample, Session, parse_method};
+
+/// What a step's request left behind besides its outcome
+#[derive(Default)]
+struct Exchange {
+    peer: Option<Peer>,
+    trace: Option<Trace>,
+}
+
+/// Step definition as written in a scenario file
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StepConfig {
+    name: Option<String>,
+    method: Option<String>,
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+    #[serde(default)]
+    extract: BTreeMap<String, ExtractConfig>,
+}
+
+/// Where an extracted variable comes from; exactly one field must be set
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractConfig {
+    /// JSONPath into the response body, e.g. "$.data.items[0].id"
+    json: Option<String>,
+    /// Response header name
+    header: Option<String>,
+}
+
+enum Extractor {
+    Json(Vec<PathSegment>),
+    Header(HeaderName),
+}
+
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A single request in a multi-step scenario
+struct Step {
+    name: String,
+    method: Method,
+    url: Template,
+    headers: Vec<(HeaderName, Template)>,
+    body: Option<Template>,
+    extract: Vec<(String, Extractor)>,
+}
+
+/// Ordered requests that each iteration executes in sequence.
+///
+/// Values extracted from a response are available to later steps of the same
+/// iteration (and, with sessions, to later iterations of the same user) as
+/// `{{name}}` placeholders in the URL, header values, and body, alongside the
+/// built-in placeholders such as `{{uuid}}`.
+pub struct Scenario {
+    steps: Vec<Step>,
+    first_url: String,
+}
+
+impl Scenario {
+    /// Builds the scenario, resolving step URLs that start with "/" against `base_url`
+    pub fn new(configs: &[StepConfig], base_url: Option<&str>) -> Result<Scenario, String> {
+        let mut steps: Vec<Step> = Vec::new();
+        let mut first_url: Option<String> = None;
+
+        for (index, config) in configs.iter().enumerate() {
+            let name: String = config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("step {}", index + 1));
+
+            let method: Method = match (&config.method, &config.body) {
+                (Some(method), _) => parse_method(method)?,
+                (None, Some(_)) => Method::POST,
+                (None, None) => Method::GET,
+            };
+
+            let url_text: String = match (config.url.starts_with('/'), base_url) {
+                (true, Some(base)) => format!("{}{}", base.trim_end_matches('/'), config.url),
+                (true, None) => {
+                    return Err(format!(
+                        "{}: relative URL \"{}\" needs a top-level url",
+                        name, config.url
+                    ));
+                }
+                (false, _) => config.url.clone(),
+            };
+            let template =
+                |text: &str| Template::parse(text).map_err(|e| format!("{}: {}", name, e));
+
+            let mut headers: Vec<(HeaderName, Template)> = Vec::new();
+            for (header, value) in &config.headers {
+                let header: HeaderName = HeaderName::from_bytes(header.as_bytes())
+                    .map_err(|e| format!("{}: invalid header name \"{}\": {}", name, header, e))?;
+                headers.push((header, template(value)?));
+            }
+
+            let mut extract: Vec<(String, Extractor)> = Vec::new();
+            for (variable, source) in &config.extract {
+                let extractor: Extractor = match (&source.json, &source.header) {
+                    (Some(path), None) => Extractor::Json(
+                        parse_json_path(path).map_err(|e| format!("{}: {}", name, e))?,
+                    ),
+                    (None, Some(header)) => {
+                        Extractor::Header(HeaderName::from_bytes(header.as_bytes()).map_err(
+                            |e| format!("{}: invalid header name \"{}\": {}", name, header, e),
+                        )?)
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{}: extract \"{}\" needs exactly one of json or header",
+                            name, variable
+                        ));
+                    }
+                };
+                extract.push((variable.clone(), extractor));
+            }
+
+            let url: Template = template(&url_text)?;
+            first_url.get_or_insert(url_text);
+            let body: Option<Template> = config.body.as_deref().map(template).transpose()?;
+            steps.push(Step {
+                name,
+                method,
+                url,
+                headers,
+                body,
+                extract,
+            });
+        }
+
+        let Some(first_url) = first_url else {
+            return Err("scenario has no steps".to_string());
+        };
+        Ok(Scenario { steps, first_url })
+    }
+
+    pub fn step_names(&self) -> Vec<String> {
+        self.steps.iter().map(|step| step.name.clone()).collect()
+    }
+
+    pub fn first_url(&self) -> &str {
+        &self.first_url
+    }
+
+    /// Executes every step in order, stopping at the first failed step.
+    ///
+    /// `seq` is the iteration number used for `{{seq}}` placeholders, and
+    /// `row` holds the iteration's CSV values, if any, which replace the
+    /// session's variables of the same name.
+    pub async fn run_iteration(
+        &self,
+        session: &mut Session,
+        stage: Option<usize>,
+        seq: u64,
+        row: Option<&HashMap<String, String>>,
+        auth: Option<&Auth>,
+        options: &RequestOptions,
+    ) -> Vec<Sample> {
+        if let Some(row) = row {
+            session
+                .variables
+                .extend(row.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        let mut samples: Vec<Sample> = Vec::with_capacity(self.steps.len());
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let req_start: Instant = Instant::now();
+            let mut exchange: Exchange = Exchange::default();
+            let outcome: Outcome = self
+                .run_step(session, step, seq, auth, options, &mut exchange)
+                .await;
+            let duration: Duration = req_start.elapsed();
+            let Exchange { peer, trace } = exchange;
+            if let Some(trace) = trace {
+                trace.finish(Some(&step.name), &outcome, peer.as_ref(), duration);
+            }
+
+            let failed: bool = !matches!(outcome, Outcome::Response(_));
+            samples.push(Sample {
+                outcome,
+                duration,
+                stage,
+                step: Some(index),
+                target: None,
+                peer,
+                finished: Instant::now(),
+            });
+            if failed {
+                break;
+            }
+        }
+
+        samples
+    }
+
+    async fn run_step(
+        &self,
+        session: &mut Session,
+        step: &Step,
+        seq: u64,
+        auth: Option<&Auth>,
+        options: &RequestOptions,
+        exchange: &mut Exchange,
+    ) -> Outcome {
+        let Session { client, variables } = session;
+        let context: Context = Context {
+            seq,
+            variables: Some(variables),
+        };
+        let mut request: reqwest::RequestBuilder =
+            client.request(step.method.clone(), step.url.render(&context).as_ref());
+        for (name, value) in &step.headers {
+            match HeaderValue::from_str(&value.render(&context)) {
+                Ok(value) => request = request.header(name, value),
+                Err(_) => return Outcome::Error,
+            }
+        }
+        let upload_clock: UploadClock = UploadClock::default();
+        let mut body_bytes: Option<u64> = None;
+        if let Some(body) = &step.body {
+            let rendered: String = body.render(&context).into_owned();
+            let body: Bytes = match options.compress_body {
+                Some(encoding) => {
+                    request = request.header(CONTENT_ENCODING, encoding.content_encoding());
+                    Bytes::from(encoding.encode(rendered.as_bytes()))
+                }
+                None => Bytes::from(rendered),
+            };
+            body_bytes = Some(body.len() as u64);
+            request = match options.upload_rate {
+                Some(rate) => request.body(upload::paced(body, rate, upload_clock.clone())),
+                None => request.body(body),
+            };
+        }
+
+        let send_start: Instant = Instant::now();
+        let request: reqwest::Request = match auth::build(request, auth).await {
+            Ok(request) => request,
+            Err(e) => return Outcome::from_error(&e),
+        };
+        exchange.trace = Trace::start(&request, options.verbose, &options.default_headers);
+        let response: reqwest::Response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(e) => return Outcome::from_error(&e),
+        };
+        if let Some(trace) = &mut exchange.trace {
+            trace.responded(&response);
+        }
+        let status: u16 = response.status().as_u16();
+        // Variables aren't extracted from responses that failed the status check
+        let expected: bool = options.expected_status.accepts(status);
+        let peer: &mut Peer = exchange
+            .peer
+            .insert(Peer::of(&response, send_start.elapsed()));
+        peer.sent = body_bytes;
+        peer.upload_ack = upload_clock.since_finished();
+        let body_start: Instant = Instant::now();
+
+        for (variable, extractor) in step.extract.iter().filter(|_| expected) {
+            if let Extractor::Header(name) = extractor {
+                match response.headers().get(name).and_then(|v| v.to_str().ok()) {
+                    Some(value) => {
+                        variables.insert(variable.clone(), value.to_string());
+                    }
+                    None => return Outcome::ExtractFailed,
+                }
+            }
+        }
+
+        let extracts_json: bool = expected
+            && step
+                .extract
+                .iter()
+                .any(|(_, extractor)| matches!(extractor, Extractor::Json(_)));
+        if expected
+            && let Some(index) =
+                assertions::failed_header_check(&options.assertions, response.headers())
+        {
+            return Outcome::AssertionFailed(index);
+        }
+        let checks_body: bool = expected && options.checks_body();
+        if !extracts_json && !checks_body {
+            if options.decompress {
+                match compression::read_body(response, Counter::default()).await {
+                    Ok((received, decoded)) => {
+                        peer.body = Some(BodySize {
+                            received,
+                            decoded: decoded.0,
+                            transfer: body_start.elapsed(),
+                        })
+                    }
+                    Err(e) => return Outcome::from(e),
+                }
+            } else {
+                match compression::drain(response).await {
+                    Ok(received) => {
+                        peer.body = Some(BodySize {
+                            received,
+                            decoded: received,
+                            transfer: body_start.elapsed(),
+                        })
+                    }
+                    Err(e) => return Outcome::from_body_error(&e),
+                }
+            }
+        } else if !extracts_json && !options.buffer_bodies {
+            let matcher: BodyMatcher = BodyMatcher::new(Arc::clone(&options.assertions));
+            match compression::read_body(response, matcher).await {
+                Ok((received, mut matcher)) => {
+                    peer.body = Some(BodySize {
+                        received,
+                        decoded: matcher.size,
+                        transfer: body_start.elapsed(),
+                    });
+                    if let Some(index) = matcher.failed() {
+                        return Outcome::AssertionFailed(index);
+                    }
+                }
+                Err(e) => return Outcome::from(e),
+            }
+        } else {
+            let decoded: Vec<u8> = match compression::read_body(response, Vec::new()).await {
+                Ok((received, decoded)) => {
+                    peer.body = Some(BodySize {
+                        received,
+                        decoded: decoded.len() as u64,
+                        transfer: body_start.elapsed(),
+                    });
+                    decoded
+                }
+                Err(e) => return Outcome::from(e),
+            };
+            if let Some(index) = assertions::failed_body_check(&options.assertions, &decoded) {
+                return Outcome::AssertionFailed(index);
+            }
+            if extracts_json {
+                let json: Value = match serde_json::from_slice(&decoded) {
+                    Ok(json) => json,
+                    Err(_) => return Outcome::ExtractFailed,
+                };
+
+                for (variable, extractor) in &step.extract {
+                    if let Extractor::Json(path) = extractor {
+                        match lookup(&json, path) {
+                            Some(Value::String(value)) => {
+                                variables.insert(variable.clone(), value.clone());
+                            }
+                            Some(value) => {
+                                variables.insert(variable.clone(), value.to_string());
+                            }
+                            None => return Outcome::ExtractFailed,
+                        }
+                    }
+                }
+            }
+        }
+
+        if !expected {
+            return Outcome::UnexpectedStatus(status);
+        }
+        if let Some(body) = &peer.body
+            && let Some(index) = assertions::failed_size_check(&options.assertions, body.decoded)
+        {
+            return Outcome::AssertionFailed(index);
+        }
+        Outcome::Response(status)
+    }
+}
+
+/// Parses the supported JSONPath subset: `$`, `.key`, `['key']` and `[index]`
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let invalid = || format!("unsupported JSONPath \"{}\"", path);
+    let mut rest: &str = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments: Vec<PathSegment> = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end: usize = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(PathSegment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end: usize = after.find(']').ok_or_else(invalid)?;
+            let inner: &str = after[..end].trim();
+            let quoted: Option<&str> = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            match quoted {
+                Some(key) => segments.push(PathSegment::Key(key.to_string())),
+                None => segments.push(PathSegment::Index(inner.parse().map_err(|_| invalid())?)),
+            }
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(segments)
+}
+
+fn lookup<'a>(json: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(json, |value, segment| match segment {
+        PathSegment::Key(key) => value.get(key),
+        PathSegment::Index(index) => value.get(index),
+    })
+}
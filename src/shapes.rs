@@ -0,0 +1,151 @@
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::parse_duration;
+use crate::stages::LoadShape;
+
+/// Kind of periodic load shape
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    /// Swings smoothly from `min` up to `max` and back once per period
+    Sine,
+    /// Ramps linearly from `min` to `max` over each period, then drops back
+    Sawtooth,
+    /// Starts at `min` and rises by `step` every period, holding at `max`
+    Step,
+}
+
+/// A load shape given with --shape, e.g. "sine:period=60s,min=10,max=100"
+#[derive(Clone, Debug)]
+pub struct ShapeSpec {
+    kind: Kind,
+    period: Duration,
+    min: f64,
+    max: f64,
+    step: f64,
+}
+
+/// Parses "KIND:period=DURATION,min=N,max=N" where KIND is sine, sawtooth or
+/// step; step also takes step=N, the rise per period (default max - min)
+pub fn parse_shape(s: &str) -> Result<ShapeSpec, String> {
+    let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+    let kind: Kind = match kind.trim() {
+        "sine" => Kind::Sine,
+        "sawtooth" => Kind::Sawtooth,
+        "step" => Kind::Step,
+        other => {
+            return Err(format!(
+                "unknown shape \"{}\" (use sine, sawtooth or step)",
+                other
+            ));
+        }
+    };
+
+    let mut period: Option<Duration> = None;
+    let mut min: Option<f64> = None;
+    let mut max: Option<f64> = None;
+    let mut step: Option<f64> = None;
+    for param in params.split(',').filter(|param| !param.trim().is_empty()) {
+        let (name, value) = param
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE in shape, got \"{}\"", param))?;
+        match name.trim() {
+            "period" => period = Some(parse_duration(value.trim())?),
+            "min" => min = Some(parse_target(value)?),
+            "max" => max = Some(parse_target(value)?),
+            "step" if kind == Kind::Step => step = Some(parse_target(value)?),
+            other => return Err(format!("unknown shape parameter \"{}\"", other)),
+        }
+    }
+
+    let period: Duration = period
+        .filter(|period| !period.is_zero())
+        .ok_or_else(|| "shape needs a period, e.g. period=60s".to_string())?;
+    let max: f64 = max.ok_or_else(|| "shape needs a max".to_string())?;
+    let min: f64 = min.unwrap_or(0.0);
+    if min > max {
+        return Err(format!("shape min {} is above its max {}", min, max));
+    }
+    Ok(ShapeSpec {
+        kind,
+        period,
+        min,
+        max,
+        step: step.unwrap_or(max - min),
+    })
+}
+
+fn parse_target(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(target) if target >= 0.0 && target.is_finite() => Ok(target),
+        _ => Err(format!("invalid shape target \"{}\"", s.trim())),
+    }
+}
+
+/// A periodic load shape that runs for the length of the test
+pub struct Shape {
+    spec: ShapeSpec,
+    duration: Duration,
+}
+
+impl Shape {
+    pub fn new(spec: ShapeSpec, duration: Duration) -> Self {
+        Shape { spec, duration }
+    }
+}
+
+impl LoadShape for Shape {
+    /// The phase index is the number of whole periods elapsed
+    fn at(&self, elapsed: Duration) -> Option<(usize, f64)> {
+        if elapsed >= self.duration {
+            return None;
+        }
+        let ShapeSpec {
+            kind,
+            period,
+            min,
+            max,
+            step,
+        } = self.spec;
+        let periods: f64 = elapsed.as_secs_f64() / period.as_secs_f64();
+        let index: usize = periods.floor() as usize;
+        let progress: f64 = periods.fract();
+        let target: f64 = match kind {
+            Kind::Sine => min + (max - min) * (1.0 - (2.0 * PI * progress).cos()) / 2.0,
+            Kind::Sawtooth => min + (max - min) * progress,
+            Kind::Step => (min + step * index as f64).min(max),
+        };
+        Some((index, target))
+    }
+
+    fn peak(&self) -> f64 {
+        self.spec.max
+    }
+
+    fn total_duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn describe(&self) -> String {
+        let ShapeSpec {
+            kind,
+            period,
+            min,
+            max,
+            step,
+        } = self.spec;
+        let kind: String = match kind {
+            Kind::Sine => "sine".to_string(),
+            Kind::Sawtooth => "sawtooth".to_string(),
+            Kind::Step => format!("step +{}", step),
+        };
+        format!(
+            "{} between {} and {} every {:.2}s for {:.2}s",
+            kind,
+            min,
+            max,
+            period.as_secs_f64(),
+            self.duration.as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,246 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Request;
+use reqwest::header::{AUTHORIZATION, HeaderName, HeaderValue};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Region and service to sign for, e.g. "us-east-1/execute-api"
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub region: String,
+    pub service: String,
+}
+
+/// Parses "REGION/SERVICE", e.g. "eu-west-1/s3"
+pub fn parse_scope(s: &str) -> Result<Scope, String> {
+    match s.split_once('/') {
+        Some((region, service)) if !region.is_empty() && !service.is_empty() => Ok(Scope {
+            region: region.to_string(),
+            service: service.to_string(),
+        }),
+        _ => Err(format!("expected REGION/SERVICE, got \"{}\"", s)),
+    }
+}
+
+/// AWS access key, secret and optional session token
+pub struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Reads credentials from the AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY
+    /// environment variables, falling back to a profile in the shared
+    /// credentials file (`profile`, then AWS_PROFILE, then "default")
+    pub fn load(profile: Option<&str>) -> Result<Credentials, String> {
+        if profile.is_none()
+            && let (Ok(access_key_id), Ok(secret_access_key)) = (
+                std::env::var("AWS_ACCESS_KEY_ID"),
+                std::env::var("AWS_SECRET_ACCESS_KEY"),
+            )
+        {
+            return Ok(Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            });
+        }
+
+        let profile: String = profile
+            .map(str::to_string)
+            .or_else(|| std::env::var("AWS_PROFILE").ok())
+            .unwrap_or_else(|| "default".to_string());
+        let path: PathBuf = match std::env::var("AWS_SHARED_CREDENTIALS_FILE") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => {
+                let home: String = std::env::var("HOME")
+                    .map_err(|_| "no AWS credentials in the environment".to_string())?;
+                PathBuf::from(home).join(".aws").join("credentials")
+            }
+        };
+        let contents: String = fs::read_to_string(&path).map_err(|e| {
+            format!(
+                "no AWS credentials in the environment and failed to read {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        let mut in_profile: bool = false;
+        for line in contents.lines() {
+            let line: &str = line.trim();
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_profile = section.trim() == profile;
+            } else if in_profile && let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim(), value.trim());
+            }
+        }
+
+        match (
+            values.get("aws_access_key_id"),
+            values.get("aws_secret_access_key"),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+                access_key_id: access_key_id.to_string(),
+                secret_access_key: secret_access_key.to_string(),
+                session_token: values.get("aws_session_token").map(|t| t.to_string()),
+            }),
+            _ => Err(format!(
+                "profile \"{}\" not found or incomplete in {}",
+                profile,
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Signs requests with AWS Signature Version 4.
+///
+/// The signature covers the method, path, query, host, timestamp and body
+/// hash, so every request is signed just before it is sent.
+pub struct SigV4 {
+    scope: Scope,
+    credentials: Credentials,
+}
+
+impl SigV4 {
+    pub fn new(scope: Scope, credentials: Credentials) -> Self {
+        SigV4 { scope, credentials }
+    }
+
+    pub fn sign(&self, request: &mut Request) {
+        let now: DateTime<Utc> = Utc::now();
+        let amz_date: String = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date: String = now.format("%Y%m%d").to_string();
+
+        let payload_hash: String = match request.body().map(|body| body.as_bytes()) {
+            None => hex(&Sha256::digest(b"")),
+            Some(Some(bytes)) => hex(&Sha256::digest(bytes)),
+            Some(None) => "UNSIGNED-PAYLOAD".to_string(),
+        };
+
+        let url: &reqwest::Url = request.url();
+        let host: String = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        // S3 paths are encoded once; every other service encodes them twice
+        let path: String = if self.scope.service == "s3" {
+            url.path().to_string()
+        } else {
+            uri_encode(url.path(), false)
+        };
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| (uri_encode(&key, true), uri_encode(&value, true)))
+            .collect();
+        query.sort();
+        let query: String = query
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<String>>()
+            .join("&");
+
+        let mut headers: Vec<(&'static str, &str)> = vec![
+            ("host", &host),
+            ("x-amz-content-sha256", &payload_hash),
+            ("x-amz-date", &amz_date),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token));
+        }
+        let mut canonical_headers: String = String::new();
+        for (name, value) in &headers {
+            let _ = writeln!(canonical_headers, "{}:{}", name, value.trim());
+        }
+        let signed_headers: String = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<&str>>()
+            .join(";");
+
+        let canonical_request: String = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method(),
+            path,
+            query,
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+        let credential_scope: String = format!(
+            "{}/{}/{}/aws4_request",
+            date, self.scope.region, self.scope.service
+        );
+        let string_to_sign: String = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            credential_scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret: String = format!("AWS4{}", self.credentials.secret_access_key);
+        let key: Vec<u8> = hmac(secret.as_bytes(), date.as_bytes());
+        let key: Vec<u8> = hmac(&key, self.scope.region.as_bytes());
+        let key: Vec<u8> = hmac(&key, self.scope.service.as_bytes());
+        let key: Vec<u8> = hmac(&key, b"aws4_request");
+        let signature: String = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        let authorization: String = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, self.credentials.access_key_id, credential_scope, signed_headers, signature
+        );
+
+        let request_headers: &mut reqwest::header::HeaderMap = request.headers_mut();
+        for (name, value) in headers.iter().skip(1) {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                request_headers.insert(HeaderName::from_static(name), value);
+            }
+        }
+        if let Ok(mut value) = HeaderValue::from_str(&authorization) {
+            value.set_sensitive(true);
+            request_headers.insert(AUTHORIZATION, value);
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac: Hmac<Sha256> = Hmac::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut output: String = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(output, "{:02x}", byte);
+    }
+    output
+}
+
+/// Percent-encodes everything except unreserved characters (and '/' unless
+/// `encode_slash` is set)
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut output: String = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                output.push(byte as char)
+            }
+            b'/' if !encode_slash => output.push('/'),
+            _ => {
+                let _ = write!(output, "%{:02X}", byte);
+            }
+        }
+    }
+    output
+}
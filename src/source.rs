@@ -0,0 +1,58 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Most source addresses a single CIDR block may expand to; each address
+/// gets its own connection pool
+const MAX_ADDRESSES: u128 = 1024;
+
+/// Local addresses from one `--local-address` value
+#[derive(Clone, Debug)]
+pub struct SourceAddresses(pub Vec<IpAddr>);
+
+/// Parses an IP address or a CIDR block such as "10.0.0.0/28"; IPv4 blocks
+/// larger than /31 leave out the network and broadcast addresses
+pub fn parse_source(s: &str) -> Result<SourceAddresses, String> {
+    let s: &str = s.trim();
+    let Some((ip, prefix)) = s.split_once('/') else {
+        let ip: IpAddr = s
+            .parse()
+            .map_err(|_| format!("invalid IP address \"{}\"", s))?;
+        return Ok(SourceAddresses(vec![ip]));
+    };
+
+    let ip: IpAddr = ip
+        .parse()
+        .map_err(|_| format!("invalid IP address in \"{}\"", s))?;
+    let bits: u32 = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix: u32 = prefix
+        .parse()
+        .ok()
+        .filter(|prefix| *prefix <= bits)
+        .ok_or_else(|| format!("invalid prefix length in \"{}\"", s))?;
+
+    let size: u128 = 1u128.checked_shl(bits - prefix).unwrap_or(u128::MAX);
+    if size > MAX_ADDRESSES {
+        return Err(format!(
+            "\"{}\" has too many addresses (at most {})",
+            s, MAX_ADDRESSES
+        ));
+    }
+
+    let start: u128 = match ip {
+        IpAddr::V4(ip) => u32::from(ip) as u128 & !(size - 1),
+        IpAddr::V6(ip) => u128::from(ip) & !(size - 1),
+    };
+    let (first, last) = if ip.is_ipv4() && size > 2 {
+        (start + 1, start + size - 2)
+    } else {
+        (start, start + size - 1)
+    };
+
+    Ok(SourceAddresses(
+        (first..=last)
+            .map(|n| match ip {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(n as u32)),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(n)),
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,338 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use reqwest::header::{ACCEPT, CACHE_CONTROL, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::grpc::print_latency;
+use crate::latencies::Latencies;
+use crate::{LatencyStats, parse_duration, parse_header};
+
+/// Reconnect delay used until the server sets one with a `retry:` field
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// Hold Server-Sent Events streams open and time the events
+#[derive(clap::Args, Debug)]
+pub struct SseCommand {
+    /// Event stream URL (must include http:// or https://)
+    #[arg(value_name = "URL")]
+    url: String,
+
+    /// Number of event streams to hold open at once
+    #[arg(short = 'c', long, default_value = "10")]
+    connections: usize,
+
+    /// How long to hold the streams open, e.g. 30s, 5m
+    #[arg(short = 'z', long, value_name = "DURATION", value_parser = parse_duration, default_value = "30s")]
+    duration: Duration,
+
+    /// Wait this long before reconnecting a stream that ended, until the
+    /// server sets a delay with a retry: field (default: 3s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    reconnect_delay: Option<Duration>,
+
+    /// Add a header to every request, e.g. -H "Authorization: Bearer abc" (repeatable)
+    #[arg(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Time allowed to establish a connection, e.g. 2s (default: no timeout)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    connect_timeout: Option<Duration>,
+
+    /// Skip TLS certificate verification (for self-signed staging certificates)
+    #[arg(short = 'k', long)]
+    insecure: bool,
+
+    /// Output file path for JSON report (optional)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    output: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SseReport {
+    url: String,
+    date: DateTime<Utc>,
+    connections: usize,
+    total_duration_secs: f64,
+    events_received: usize,
+    events_per_sec: f64,
+    connection_attempts: usize,
+    /// Attempts that got no event stream: errors, non-2xx statuses, or
+    /// responses that weren't text/event-stream
+    failed_attempts: usize,
+    /// Connections opened again after a stream ended or an attempt failed
+    reconnects: usize,
+    /// Streams the server ended cleanly
+    streams_closed: usize,
+    /// Streams cut off by a network or protocol error
+    stream_errors: usize,
+    /// Time from sending the request to the first event of each stream
+    time_to_first_event: LatencyStats,
+    /// Time between consecutive events of a stream
+    inter_arrival: LatencyStats,
+}
+
+/// Splits an event stream into events as its bytes arrive
+#[derive(Default)]
+struct EventParser {
+    /// Bytes of a line that hasn't ended yet
+    line: Vec<u8>,
+    /// Whether the event being read has any data yet
+    has_data: bool,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl EventParser {
+    /// Reads a chunk, returning the number of events it completed
+    fn feed(&mut self, chunk: &[u8]) -> usize {
+        let mut events: usize = 0;
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            if self.line.last() == Some(&b'\r') {
+                self.line.pop();
+            }
+            let line: Vec<u8> = std::mem::take(&mut self.line);
+            if line.is_empty() {
+                // A blank line dispatches the event, if it had data
+                if std::mem::take(&mut self.has_data) {
+                    events += 1;
+                }
+                continue;
+            }
+            self.field(&String::from_utf8_lossy(&line));
+        }
+        events
+    }
+
+    fn field(&mut self, line: &str) {
+        let (name, value) = match line.split_once(':') {
+            // Lines starting with a colon are comments
+            Some(("", _)) => return,
+            Some((name, value)) => (name, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match name {
+            "data" => self.has_data = true,
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(millis) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What happened on one stream over the whole run
+#[derive(Default)]
+struct StreamStats {
+    attempts: usize,
+    failed_attempts: usize,
+    streams_closed: usize,
+    stream_errors: usize,
+    events: usize,
+    first_events: Latencies,
+    inter_arrivals: Latencies,
+}
+
+/// True for responses that carry an event stream
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response.status().is_success()
+        && response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim_start().starts_with("text/event-stream"))
+}
+
+/// Keeps one event stream open until `deadline`, reconnecting whenever it
+/// ends and resuming from the last event ID like a browser would
+async fn hold_stream(
+    client: Client,
+    url: String,
+    deadline: Instant,
+    reconnect_delay: Duration,
+) -> StreamStats {
+    let mut stats: StreamStats = StreamStats::default();
+    let mut last_event_id: Option<String> = None;
+    let mut delay: Duration = reconnect_delay;
+
+    while Instant::now() < deadline {
+        stats.attempts += 1;
+        let mut request: reqwest::RequestBuilder = client
+            .get(&url)
+            .header(ACCEPT, "text/event-stream")
+            .header(CACHE_CONTROL, "no-cache");
+        if let Some(id) = &last_event_id {
+            request = request.header("Last-Event-ID", id.as_str());
+        }
+
+        let start: Instant = Instant::now();
+        let response: Option<reqwest::Response> =
+            match tokio::time::timeout_at(deadline, request.send()).await {
+                Err(_) => break,
+                Ok(Ok(response)) if is_event_stream(&response) => Some(response),
+                Ok(_) => None,
+            };
+
+        match response {
+            Some(mut response) => {
+                print!(".");
+                let mut parser: EventParser = EventParser::default();
+                let mut last_event: Option<Instant> = None;
+                loop {
+                    match tokio::time::timeout_at(deadline, response.chunk()).await {
+                        // Still open when the run ends
+                        Err(_) => return stats,
+                        Ok(Ok(Some(chunk))) => {
+                            let now: Instant = Instant::now();
+                            for _ in 0..parser.feed(&chunk) {
+                                match last_event {
+                                    None => stats.first_events.record(now - start),
+                                    Some(previous) => stats.inter_arrivals.record(now - previous),
+                                }
+                                last_event = Some(now);
+                                stats.events += 1;
+                            }
+                        }
+                        Ok(Ok(None)) => {
+                            stats.streams_closed += 1;
+                            break;
+                        }
+                        Ok(Err(_)) => {
+                            stats.stream_errors += 1;
+                            break;
+                        }
+                    }
+                }
+                if parser.last_event_id.is_some() {
+                    last_event_id = parser.last_event_id;
+                }
+                if let Some(retry) = parser.retry {
+                    delay = retry;
+                }
+            }
+            None => {
+                print!("F");
+                stats.failed_attempts += 1;
+            }
+        }
+
+        tokio::time::sleep_until(deadline.min(Instant::now() + delay)).await;
+    }
+    stats
+}
+
+/// Runs the event stream test
+pub async fn run(command: SseCommand) {
+    let mut headers: HeaderMap = HeaderMap::new();
+    for (name, value) in &command.headers {
+        headers.append(name.clone(), value.clone());
+    }
+    let mut builder: reqwest::ClientBuilder = Client::builder().default_headers(headers);
+    if let Some(connect_timeout) = command.connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if command.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let client: Client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("✗ Failed to build HTTP client: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let reconnect_delay: Duration = command.reconnect_delay.unwrap_or(DEFAULT_RECONNECT_DELAY);
+
+    println!("Load testing: SSE {}", command.url);
+    println!("Connections: {}", command.connections);
+    println!("Duration: {:.2}s", command.duration.as_secs_f64());
+    println!(
+        "Reconnect delay: {:.2}s (until the server sets one)",
+        reconnect_delay.as_secs_f64()
+    );
+    println!();
+
+    let start: Instant = Instant::now();
+    let deadline: Instant = start + command.duration;
+    let mut tasks: JoinSet<StreamStats> = JoinSet::new();
+    for _ in 0..command.connections {
+        tasks.spawn(hold_stream(
+            client.clone(),
+            command.url.clone(),
+            deadline,
+            reconnect_delay,
+        ));
+    }
+
+    let mut totals: StreamStats = StreamStats::default();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(stats) = result {
+            totals.attempts += stats.attempts;
+            totals.failed_attempts += stats.failed_attempts;
+            totals.streams_closed += stats.streams_closed;
+            totals.stream_errors += stats.stream_errors;
+            totals.events += stats.events;
+            totals.first_events.merge(&stats.first_events);
+            totals.inter_arrivals.merge(&stats.inter_arrivals);
+        }
+    }
+
+    let total_duration: Duration = start.elapsed();
+    // Every stream's first attempt is a connection, the rest are reconnects
+    let reconnects: usize = totals.attempts.saturating_sub(command.connections);
+    let events_per_sec: f64 = totals.events as f64 / total_duration.as_secs_f64();
+    println!("\n\nResults:");
+    println!("========");
+    println!("Total time: {:.2}s", total_duration.as_secs_f64());
+    println!("Events received: {}", totals.events);
+    println!("Events/sec: {:.2}", events_per_sec);
+    println!("Connection attempts: {}", totals.attempts);
+    println!("Failed attempts: {}", totals.failed_attempts);
+    println!("Reconnects: {}", reconnects);
+    println!("Streams closed by server: {}", totals.streams_closed);
+    println!("Stream errors: {}", totals.stream_errors);
+
+    let time_to_first_event: Option<LatencyStats> =
+        LatencyStats::from_latencies(&mut totals.first_events);
+    if let Some(latency) = &time_to_first_event {
+        print_latency("Time to first event", latency);
+    }
+    let inter_arrival: Option<LatencyStats> =
+        LatencyStats::from_latencies(&mut totals.inter_arrivals);
+    if let Some(latency) = &inter_arrival {
+        print_latency("Event inter-arrival", latency);
+    }
+
+    let report: SseReport = SseReport {
+        url: command.url.clone(),
+        date: Utc::now(),
+        connections: command.connections,
+        total_duration_secs: total_duration.as_secs_f64(),
+        events_received: totals.events,
+        events_per_sec,
+        connection_attempts: totals.attempts,
+        failed_attempts: totals.failed_attempts,
+        reconnects,
+        streams_closed: totals.streams_closed,
+        stream_errors: totals.stream_errors,
+        time_to_first_event: time_to_first_event.unwrap_or_default(),
+        inter_arrival: inter_arrival.unwrap_or_default(),
+    };
+
+    if let Some(output_path) = &command.output {
+        match fs::write(output_path, serde_json::to_string_pretty(&report).unwrap()) {
+            Ok(_) => println!("\n✓ Report saved to: {}", output_path),
+            Err(e) => eprintln!("\n✗ Failed to save report: {}", e),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::parse_duration;
+
+/// What the stage targets control
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StageUnit {
+    /// Number of requests in flight
+    Concurrency,
+    /// Requests per second
+    Rate,
+}
+
+/// Target concurrency or request rate over the course of a run, which the
+/// scheduler follows
+pub trait LoadShape: Send + Sync {
+    /// Returns the current phase index and the target at `elapsed`, or
+    /// `None` once the shape has finished
+    fn at(&self, elapsed: Duration) -> Option<(usize, f64)>;
+
+    /// Highest target the shape reaches
+    fn peak(&self) -> f64;
+
+    fn total_duration(&self) -> Duration;
+
+    /// One-line description for the test summary
+    fn describe(&self) -> String;
+}
+
+/// One phase of a staged load profile
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub duration: Duration,
+    pub target: f64,
+}
+
+/// Parses a "DURATION:TARGET" stage such as "30s:10"
+pub fn parse_stage(s: &str) -> Result<Stage, String> {
+    let (duration, target) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"DURATION:TARGET\", got \"{}\"", s))?;
+    let duration: Duration = parse_duration(duration)?;
+    let target: f64 = match target.trim().parse::<f64>() {
+        Ok(target) if target >= 0.0 && target.is_finite() => target,
+        _ => return Err(format!("invalid stage target \"{}\"", target)),
+    };
+    Ok(Stage { duration, target })
+}
+
+/// Load shape built from consecutive stages.
+///
+/// Like k6 stages, each stage ramps linearly from the previous stage's target
+/// (zero for the first stage) to its own target over its duration.
+pub struct Schedule {
+    stages: Vec<Stage>,
+}
+
+impl Schedule {
+    pub fn new(stages: Vec<Stage>) -> Self {
+        Schedule { stages }
+    }
+}
+
+impl LoadShape for Schedule {
+    /// Returns the active stage index and the interpolated target at `elapsed`,
+    /// or `None` once every stage has finished
+    fn at(&self, elapsed: Duration) -> Option<(usize, f64)> {
+        let mut stage_start: Duration = Duration::ZERO;
+        let mut from: f64 = 0.0;
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            let stage_end: Duration = stage_start + stage.duration;
+            if elapsed < stage_end {
+                let progress: f64 =
+                    (elapsed - stage_start).as_secs_f64() / stage.duration.as_secs_f64();
+                return Some((index, from + (stage.target - from) * progress));
+            }
+            stage_start = stage_end;
+            from = stage.target;
+        }
+
+        None
+    }
+
+    fn peak(&self) -> f64 {
+        self.stages
+            .iter()
+            .map(|stage| stage.target)
+            .fold(0.0, f64::max)
+    }
+
+    fn total_duration(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} over {:.2}s",
+            self.stages.len(),
+            self.total_duration().as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt::Write as _;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::Sample;
+use crate::request_log::category;
+
+/// Most bytes sent in one packet, which fits an Ethernet frame's payload
+const MAX_PACKET: usize = 1432;
+
+/// Longest a metric waits in the buffer, so a slow run still shows up live
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends every request's timing and counters to StatsD, or to a Datadog
+/// agent as DogStatsD with tags, with --statsd. Lines are sent over UDP a
+/// packet at a time; ones that don't arrive are lost, as with any StatsD
+/// client.
+pub struct Statsd {
+    socket: UdpSocket,
+    dogstatsd: bool,
+    /// "|#" and the tags of every metric, with --dogstatsd
+    tags: String,
+    buffer: String,
+    flushed: Instant,
+}
+
+impl Statsd {
+    /// Connects to `addr`, a HOST:PORT, tagging every metric with `tags`
+    /// when `dogstatsd`
+    pub fn connect(addr: &str, dogstatsd: bool, tags: &[String]) -> io::Result<Statsd> {
+        let addr: SocketAddr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found"))?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            SocketAddr::from(([0, 0, 0, 0], 0))
+        } else {
+            SocketAddr::from(([0u16; 8], 0))
+        };
+        let socket: UdpSocket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        let tags: String = if dogstatsd && !tags.is_empty() {
+            format!("|#{}", tags.join(","))
+        } else {
+            String::new()
+        };
+        Ok(Statsd {
+            socket,
+            dogstatsd,
+            tags,
+            buffer: String::new(),
+            flushed: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, sample: &Sample) {
+        self.count("loadster.requests", 1, None);
+        let ms: f64 = sample.duration.as_secs_f64() * 1000.0;
+        self.metric("loadster.request.duration", &format!("{:.3}|ms", ms), None);
+        if let Some(peer) = &sample.peer {
+            self.count(
+                "loadster.responses",
+                1,
+                Some(("status", &peer.status.to_string())),
+            );
+            if let Some(body) = &peer.body {
+                self.count("loadster.received_bytes", body.received, None);
+            }
+        }
+        if let Some(error) = category(&sample.outcome) {
+            self.count("loadster.errors", 1, Some(("error", error)));
+        }
+        if self.flushed.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn count(&mut self, name: &str, value: u64, label: Option<(&str, &str)>) {
+        self.metric(name, &format!("{}|c", value), label);
+    }
+
+    /// Buffers a line of `value` for `name`; the `label` is a tag with
+    /// DogStatsD, and the last part of the name otherwise, as in
+    /// "loadster.responses.200"
+    fn metric(&mut self, name: &str, value: &str, label: Option<(&str, &str)>) {
+        let mut line: String = name.to_string();
+        if let Some((_, part)) = label
+            && !self.dogstatsd
+        {
+            let _ = write!(line, ".{}", part);
+        }
+        let _ = write!(line, ":{}{}", value, self.tags);
+        if let Some((key, part)) = label
+            && self.dogstatsd
+        {
+            line.push_str(if self.tags.is_empty() { "|#" } else { "," });
+            let _ = write!(line, "{}:{}", key, part);
+        }
+        if !self.buffer.is_empty() && self.buffer.len() + 1 + line.len() > MAX_PACKET {
+            self.flush();
+        }
+        if !self.buffer.is_empty() {
+            self.buffer.push('\n');
+        }
+        self.buffer.push_str(&line);
+    }
+
+    /// Sends what is buffered, e.g. once the run is over
+    pub fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            // Nothing listening is no reason to hold up the run
+            let _ = self.socket.send(self.buffer.as_bytes());
+            self.buffer.clear();
+        }
+        self.flushed = Instant::now();
+    }
+}
+
+/// Checks a --statsd address is HOST:PORT
+pub fn parse_statsd(s: &str) -> Result<String, String> {
+    match s.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(s.to_string()),
+        _ => Err(format!("expected HOST:PORT, got \"{}\"", s)),
+    }
+}
+
+/// Checks a --statsd-tag, e.g. "env:staging", has none of the characters
+/// that separate DogStatsD's fields
+pub fn parse_statsd_tag(s: &str) -> Result<String, String> {
+    let tag: &str = s.trim();
+    if tag.is_empty() || tag.contains(['|', ',', '#', '\n']) {
+        return Err(format!(
+            "invalid tag \"{}\" (expected KEY:VALUE without '|', ',' or '#')",
+            s
+        ));
+    }
+    Ok(tag.to_string())
+}
@@ -0,0 +1,208 @@
+use chrono::SecondsFormat;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::io::{self, BufWriter, Write};
+use std::process::{Child, ChildStdin, Command, Output, Stdio};
+
+use crate::request_log::Entry;
+use crate::{Report, Sample};
+
+/// Tables the runs go in, created on first use. Every run's full JSON
+/// report is kept in `report`, for json_extract() to reach what the other
+/// columns leave out.
+const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    date TEXT NOT NULL,
+    url TEXT NOT NULL,
+    tag TEXT,
+    concurrency INTEGER NOT NULL,
+    duration_secs REAL,
+    total_requests INTEGER NOT NULL,
+    successful INTEGER NOT NULL,
+    failed INTEGER NOT NULL,
+    requests_per_sec REAL,
+    avg_ms REAL,
+    p50_ms REAL,
+    p95_ms REAL,
+    p99_ms REAL,
+    max_ms REAL,
+    bytes_received INTEGER NOT NULL,
+    thresholds_passed INTEGER,
+    aborted INTEGER NOT NULL,
+    report TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS samples (
+    run_id INTEGER NOT NULL REFERENCES runs (id),
+    timestamp TEXT NOT NULL,
+    worker INTEGER NOT NULL,
+    status INTEGER,
+    latency_ms REAL,
+    bytes INTEGER,
+    error TEXT
+);
+CREATE INDEX IF NOT EXISTS samples_run_id ON samples (run_id);
+";
+
+/// Appends the run to a SQLite database with --sqlite, through the sqlite3
+/// command. Samples wait in a temporary table, so the database is only
+/// locked for the moment the run is added at the end.
+pub struct Store {
+    path: String,
+    sqlite: Child,
+    input: BufWriter<ChildStdin>,
+    /// Keep every request too, with --sqlite-samples
+    samples: bool,
+    /// Set once sqlite3 stopped taking statements; it says why when it exits
+    broken: bool,
+}
+
+impl Store {
+    pub fn open(path: &str, samples: bool) -> Result<Store, String> {
+        // A database that can't be opened is found out before the run
+        // rather than after it
+        let checked: Output = Command::new("sqlite3")
+            .args(["-batch", path, "PRAGMA schema_version;"])
+            .output()
+            .map_err(sqlite3_error)?;
+        if !checked.status.success() {
+            return Err(String::from_utf8_lossy(&checked.stderr).trim().to_string());
+        }
+        let mut sqlite: Child = Command::new("sqlite3")
+            .args(["-batch", "-bail", path])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(sqlite3_error)?;
+        let Some(input) = sqlite.stdin.take() else {
+            return Err("sqlite3 has no input".to_string());
+        };
+        let mut store: Store = Store {
+            path: path.to_string(),
+            sqlite,
+            input: BufWriter::new(input),
+            samples,
+            broken: false,
+        };
+        // Writing only the temporary table takes no lock on the database
+        store.send(
+            "BEGIN;
+CREATE TEMP TABLE pending (timestamp TEXT, worker INTEGER, status INTEGER, latency_ms REAL, bytes INTEGER, error TEXT);
+",
+        );
+        Ok(store)
+    }
+
+    /// Keeps the request `worker` sent, with --sqlite-samples
+    pub fn record(&mut self, sample: &Sample, worker: usize) {
+        if !self.samples || self.broken {
+            return;
+        }
+        let entry: Entry = Entry::of(sample, worker);
+        let statement: String = format!(
+            "INSERT INTO pending VALUES ({}, {}, {}, {}, {}, {});\n",
+            text(entry.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)),
+            entry.worker,
+            nullable(entry.status),
+            real(entry.latency_ms),
+            nullable(entry.bytes),
+            entry.error.map_or("NULL".to_string(), text)
+        );
+        self.send(&statement);
+    }
+
+    /// Adds the run with its samples, returning its id
+    pub fn finish(mut self, report: &Report) -> Result<i64, String> {
+        let json: String = serde_json::to_string(report).map_err(|e| e.to_string())?;
+        let latency = &report.latency;
+        let statements: String = format!(
+            "{SCHEMA}INSERT INTO runs (date, url, tag, concurrency, duration_secs, total_requests, \
+successful, failed, requests_per_sec, avg_ms, p50_ms, p95_ms, p99_ms, max_ms, bytes_received, \
+thresholds_passed, aborted, report) VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});
+INSERT INTO samples SELECT runs.id, pending.* FROM pending, (SELECT max(id) AS id FROM runs) AS runs;
+COMMIT;
+SELECT max(id) FROM runs;
+",
+            text(report.date.to_rfc3339_opts(SecondsFormat::Micros, true)),
+            text(&report.url),
+            report.tag.as_ref().map_or("NULL".to_string(), text),
+            report.concurrency,
+            real(report.total_duration_secs),
+            report.total_requests,
+            report.successful,
+            report.failed,
+            real(report.requests_per_sec),
+            real(latency.avg_ms),
+            real(latency.p50_ms),
+            real(latency.p95_ms),
+            real(latency.p99_ms),
+            real(latency.max_ms),
+            report.bytes_received,
+            nullable(report.thresholds_passed.map(u8::from)),
+            u8::from(report.aborted.is_some()),
+            text(json)
+        );
+        self.send(&statements);
+        if !self.broken {
+            self.broken = self.input.flush().is_err();
+        }
+        drop(self.input);
+        let output: Output = self.sqlite.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            let stderr: String = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(format!("sqlite3 failed on {}: {}", self.path, stderr));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .map_err(|_| format!("sqlite3 didn't add the run to {}", self.path))
+    }
+
+    fn send(&mut self, statements: &str) {
+        if !self.broken {
+            self.broken = self.input.write_all(statements.as_bytes()).is_err();
+        }
+    }
+}
+
+/// Runs a query against the database without changing it, returning a
+/// row for each object sqlite3 prints
+pub fn query<T: DeserializeOwned>(path: &str, sql: &str) -> Result<Vec<T>, String> {
+    let output: Output = Command::new("sqlite3")
+        .args(["-batch", "-readonly", "-json", path, sql])
+        .output()
+        .map_err(sqlite3_error)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    // Nothing at all is printed when no row matched
+    let rows: &str = std::str::from_utf8(&output.stdout)
+        .map_err(|e| e.to_string())?
+        .trim();
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(rows).map_err(|e| e.to_string())
+}
+
+fn sqlite3_error(e: io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::NotFound => "the sqlite3 command wasn't found".to_string(),
+        _ => format!("couldn't run sqlite3: {}", e),
+    }
+}
+
+/// A SQL string literal
+pub fn text(value: impl AsRef<str>) -> String {
+    format!("'{}'", value.as_ref().replace('\'', "''"))
+}
+
+/// A number SQL can read; NaN and infinities aren't, so they are left NULL
+fn real(value: f64) -> String {
+    nullable(Some(value).filter(|value| value.is_finite()))
+}
+
+fn nullable(value: Option<impl Display>) -> String {
+    value.map_or("NULL".to_string(), |value| value.to_string())
+}
@@ -0,0 +1,175 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::io::Read;
+
+/// A URL to test and its share of the traffic
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub url: String,
+    pub weight: u32,
+}
+
+/// Target as written in a scenario file: a plain URL or `{ url, weight }`
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum TargetConfig {
+    Url(String),
+    Weighted { url: String, weight: u32 },
+}
+
+impl TargetConfig {
+    pub fn to_target(&self) -> Result<Target, String> {
+        match self {
+            TargetConfig::Url(url) => Ok(Target {
+                url: url.clone(),
+                weight: 1,
+            }),
+            TargetConfig::Weighted { url, weight } => {
+                if *weight == 0 {
+                    return Err(format!("weight for {} must be at least 1", url));
+                }
+                Ok(Target {
+                    url: url.clone(),
+                    weight: *weight,
+                })
+            }
+        }
+    }
+}
+
+/// Parses "URL" or "URL@WEIGHT", e.g. "https://example.com/api/list@80"
+pub fn parse_target(s: &str) -> Result<Target, String> {
+    if let Some((url, weight)) = s.rsplit_once('@')
+        && !weight.is_empty()
+        && weight.chars().all(|c| c.is_ascii_digit())
+    {
+        let weight: u32 = weight
+            .parse()
+            .map_err(|_| format!("invalid weight \"{}\"", weight))?;
+        if weight == 0 {
+            return Err(format!("weight for {} must be at least 1", url));
+        }
+        return Ok(Target {
+            url: url.to_string(),
+            weight,
+        });
+    }
+
+    Ok(Target {
+        url: s.to_string(),
+        weight: 1,
+    })
+}
+
+/// Reads one URL per line from a file, or from stdin when `path` is "-".
+///
+/// Blank lines and lines starting with '#' are skipped. Lines starting with
+/// '/' are paths appended to `base_url`.
+pub fn load_url_file(path: &str, base_url: Option<&str>) -> Result<Vec<Target>, String> {
+    let contents: String = if path == "-" {
+        let mut contents: String = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| format!("failed to read URLs from stdin: {}", e))?;
+        contents
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?
+    };
+
+    let mut targets: Vec<Target> = Vec::new();
+    for line in contents.lines() {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let url: String = match (line.starts_with('/'), base_url) {
+            (true, Some(base)) => format!("{}{}", base.trim_end_matches('/'), line),
+            (true, None) => return Err(format!("path \"{}\" needs a base URL", line)),
+            (false, _) => line.to_string(),
+        };
+        targets.push(Target { url, weight: 1 });
+    }
+
+    if targets.is_empty() {
+        return Err(format!("no URLs found in {}", path));
+    }
+    Ok(targets)
+}
+
+/// Order in which URLs are requested
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlOrder {
+    /// Cycle through the URLs in order, following their weights
+    Sequential,
+    /// Pick each URL at random, following their weights
+    Random,
+}
+
+/// Chooses the target for each request.
+///
+/// Sequential order uses smooth weighted round-robin (as in nginx), so picks
+/// follow the weights exactly over every cycle while interleaving targets
+/// evenly. Equally weighted lists, such as replayed URL files, simply cycle.
+pub struct TargetPicker {
+    order: UrlOrder,
+    weights: Vec<i64>,
+    current: Vec<i64>,
+    cumulative: Vec<i64>,
+    total: i64,
+    uniform: bool,
+    next: usize,
+}
+
+impl TargetPicker {
+    pub fn new(targets: &[Target], order: UrlOrder) -> Self {
+        let weights: Vec<i64> = targets.iter().map(|t| i64::from(t.weight)).collect();
+        let cumulative: Vec<i64> = weights
+            .iter()
+            .scan(0, |sum, weight| {
+                *sum += weight;
+                Some(*sum)
+            })
+            .collect();
+
+        TargetPicker {
+            order,
+            total: weights.iter().sum(),
+            current: vec![0; weights.len()],
+            uniform: weights.iter().all(|weight| *weight == weights[0]),
+            cumulative,
+            weights,
+            next: 0,
+        }
+    }
+
+    /// Returns the index of the next target to request
+    pub fn next(&mut self) -> usize {
+        match (self.order, self.uniform) {
+            (UrlOrder::Sequential, true) => {
+                let index: usize = self.next;
+                self.next = (self.next + 1) % self.weights.len();
+                index
+            }
+            (UrlOrder::Sequential, false) => {
+                let mut best: usize = 0;
+                for index in 0..self.weights.len() {
+                    self.current[index] += self.weights[index];
+                    if self.current[index] > self.current[best] {
+                        best = index;
+                    }
+                }
+                self.current[best] -= self.total;
+                best
+            }
+            (UrlOrder::Random, true) => rand::rng().random_range(0..self.weights.len()),
+            (UrlOrder::Random, false) => {
+                let point: i64 = rand::rng().random_range(0..self.total);
+                self.cumulative.partition_point(|sum| *sum <= point)
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use rand::Rng;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::fake::Fake;
+
+/// One piece of a parsed template
+#[derive(Clone, Debug)]
+enum Part {
+    Literal(String),
+    /// Scenario variable; left as written when it isn't set
+    Variable(String),
+    Uuid,
+    Seq,
+    RandInt(i64, i64),
+    Timestamp,
+    TimestampMs,
+    Fake(Fake),
+}
+
+/// Values that change from one request to the next
+pub struct Context<'a> {
+    pub seq: u64,
+    pub variables: Option<&'a HashMap<String, String>>,
+}
+
+/// Text with `{{...}}` placeholders, parsed once and expanded per request.
+///
+/// Built-in placeholders are `{{uuid}}`, `{{seq}}`, `{{rand_int MIN MAX}}`,
+/// `{{timestamp}}`, `{{timestamp_ms}}` and `{{fake.NAME}}`; any other name is
+/// looked up in the scenario variables.
+#[derive(Clone, Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, String> {
+        let mut parts: Vec<Part> = Vec::new();
+        let mut rest: &str = source;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(parse_placeholder(rest[start + 2..start + end].trim())?);
+            rest = &rest[start + end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Template { parts })
+    }
+
+    /// True when the template has no placeholders and always renders the same
+    pub fn is_static(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, Part::Literal(_)))
+    }
+
+    /// Expands the template; static templates are returned without allocating
+    pub fn render(&self, context: &Context) -> Cow<'_, str> {
+        match self.parts.as_slice() {
+            [] => Cow::Borrowed(""),
+            [Part::Literal(text)] => Cow::Borrowed(text),
+            parts => {
+                let mut output: String = String::with_capacity(64);
+                for part in parts {
+                    render_part(part, context, &mut output);
+                }
+                Cow::Owned(output)
+            }
+        }
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Part, String> {
+    let mut words = placeholder.split_whitespace();
+    let name: &str = words.next().unwrap_or_default();
+    let arguments: Vec<&str> = words.collect();
+
+    let part: Part = match (name, arguments.as_slice()) {
+        ("uuid", []) => Part::Uuid,
+        ("seq", []) => Part::Seq,
+        ("timestamp", []) => Part::Timestamp,
+        ("timestamp_ms", []) => Part::TimestampMs,
+        ("rand_int", [min, max]) => {
+            let invalid = || format!("invalid placeholder \"{{{{{}}}}}\"", placeholder);
+            let min: i64 = min.parse().map_err(|_| invalid())?;
+            let max: i64 = max.parse().map_err(|_| invalid())?;
+            if min > max {
+                return Err(invalid());
+            }
+            Part::RandInt(min, max)
+        }
+        ("rand_int", _) => {
+            return Err(format!(
+                "\"{{{{{}}}}}\" expects two arguments: rand_int MIN MAX",
+                placeholder
+            ));
+        }
+        (name, []) if name.starts_with("fake.") => {
+            Part::Fake(Fake::from_name(&name["fake.".len()..]).ok_or_else(|| {
+                format!(
+                    "unknown fake data \"{{{{{}}}}}\" (use fake.first_name, fake.last_name, \
+                     fake.name, fake.username, fake.email or fake.ipv4)",
+                    name
+                )
+            })?)
+        }
+        (name, []) if !name.is_empty() => Part::Variable(name.to_string()),
+        _ => return Err(format!("invalid placeholder \"{{{{{}}}}}\"", placeholder)),
+    };
+    Ok(part)
+}
+
+fn render_part(part: &Part, context: &Context, output: &mut String) {
+    match part {
+        Part::Literal(text) => output.push_str(text),
+        Part::Variable(name) => match context.variables.and_then(|vars| vars.get(name)) {
+            Some(value) => output.push_str(value),
+            None => {
+                let _ = write!(output, "{{{{{}}}}}", name);
+            }
+        },
+        Part::Uuid => write_uuid(output),
+        Part::Seq => {
+            let _ = write!(output, "{}", context.seq);
+        }
+        Part::RandInt(min, max) => {
+            let _ = write!(output, "{}", rand::rng().random_range(*min..=*max));
+        }
+        Part::Timestamp => {
+            let _ = write!(output, "{}", unix_time().as_secs());
+        }
+        Part::TimestampMs => {
+            let _ = write!(output, "{}", unix_time().as_millis());
+        }
+        Part::Fake(fake) => fake.write(output),
+    }
+}
+
+fn unix_time() -> std::time::Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Writes a random (version 4) UUID
+fn write_uuid(output: &mut String) {
+    let mut bytes: [u8; 16] = rand::rng().random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    for (index, byte) in bytes.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            output.push('-');
+        }
+        let _ = write!(output, "{:02x}", byte);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{LatencyStats, Report, parse_duration};
+
+/// Names of the metrics, for error messages
+pub const METRICS: &str = "avg, min, max, p50, p95, p99, error_rate or rps";
+
+/// Metric a threshold or --max-regression is checked against
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Avg,
+    Min,
+    Max,
+    P50,
+    P95,
+    P99,
+    ErrorRate,
+    Rps,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A pass/fail condition on the final results, e.g. "p95<250ms"
+#[derive(Clone, Debug)]
+pub struct Threshold {
+    pub expression: String,
+    metric: Metric,
+    op: Op,
+    value: f64,
+}
+
+/// Parses "METRIC<VALUE" where the operator is one of <, <=, >, >=.
+///
+/// Latency metrics (avg, min, max, p50, p95, p99) take a duration and are
+/// compared in milliseconds, error_rate takes a percentage or a fraction, and
+/// rps takes a number.
+pub fn parse_threshold(s: &str) -> Result<Threshold, String> {
+    let expression: String = s.split_whitespace().collect();
+    let position: usize = expression
+        .find(['<', '>'])
+        .ok_or_else(|| format!("expected \"METRIC<VALUE\", got \"{}\"", s))?;
+    let (name, rest) = expression.split_at(position);
+    let (op, value) = match rest.split_at(if rest[1..].starts_with('=') { 2 } else { 1 }) {
+        ("<", value) => (Op::Lt, value),
+        ("<=", value) => (Op::Le, value),
+        (">", value) => (Op::Gt, value),
+        (">=", value) => (Op::Ge, value),
+        _ => unreachable!("operator starts with < or >"),
+    };
+
+    let metric: Metric = Metric::parse(name)
+        .ok_or_else(|| format!("unknown threshold metric \"{}\" (use {})", name, METRICS))?;
+
+    let value: f64 = match metric {
+        Metric::ErrorRate => match value.strip_suffix('%') {
+            Some(percent) => parse_number(percent)? / 100.0,
+            None => parse_number(value)?,
+        },
+        Metric::Rps => parse_number(value)?,
+        // Bare latency values are milliseconds
+        _ => match value.parse::<f64>() {
+            Ok(ms) => ms,
+            Err(_) => parse_duration(value)?.as_secs_f64() * 1000.0,
+        },
+    };
+
+    Ok(Threshold {
+        expression,
+        metric,
+        op,
+        value,
+    })
+}
+
+fn parse_number(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .map_err(|_| format!("invalid threshold value \"{}\"", s))
+}
+
+impl Metric {
+    pub fn parse(name: &str) -> Option<Metric> {
+        match name {
+            "avg" => Some(Metric::Avg),
+            "min" => Some(Metric::Min),
+            "max" => Some(Metric::Max),
+            "p50" => Some(Metric::P50),
+            "p95" => Some(Metric::P95),
+            "p99" => Some(Metric::P99),
+            "error_rate" => Some(Metric::ErrorRate),
+            "rps" => Some(Metric::Rps),
+            _ => None,
+        }
+    }
+
+    /// The metric's value in a report
+    pub fn of(self, report: &Report) -> f64 {
+        let error_rate: f64 = if report.total_requests == 0 {
+            0.0
+        } else {
+            report.failed as f64 / report.total_requests as f64
+        };
+        self.value(&report.latency, error_rate, report.requests_per_sec)
+    }
+
+    fn value(self, latency: &LatencyStats, error_rate: f64, rps: f64) -> f64 {
+        match self {
+            Metric::Avg => latency.avg_ms,
+            Metric::Min => latency.min_ms,
+            Metric::Max => latency.max_ms,
+            Metric::P50 => latency.p50_ms,
+            Metric::P95 => latency.p95_ms,
+            Metric::P99 => latency.p99_ms,
+            Metric::ErrorRate => error_rate,
+            Metric::Rps => rps,
+        }
+    }
+
+    /// Formats a value in the metric's natural unit
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Metric::ErrorRate => format!("{:.2}%", value * 100.0),
+            Metric::Rps => format!("{:.2} req/s", value),
+            _ => format!("{:.2}ms", value),
+        }
+    }
+}
+
+impl Threshold {
+    /// Returns the observed value and whether the threshold held
+    pub fn evaluate(&self, report: &Report) -> (f64, bool) {
+        let actual: f64 = self.metric.of(report);
+        (actual, self.holds(actual))
+    }
+
+    /// Like `evaluate`, for results that aren't a whole report
+    pub fn check(&self, latency: &LatencyStats, error_rate: f64, rps: f64) -> (f64, bool) {
+        let actual: f64 = self.metric.value(latency, error_rate, rps);
+        (actual, self.holds(actual))
+    }
+
+    fn holds(&self, actual: f64) -> bool {
+        match self.op {
+            Op::Lt => actual < self.value,
+            Op::Le => actual <= self.value,
+            Op::Gt => actual > self.value,
+            Op::Ge => actual >= self.value,
+        }
+    }
+
+    /// Formats an observed value in the metric's natural unit
+    pub fn format_value(&self, value: f64) -> String {
+        self.metric.format(value)
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::hdr::IntervalLog;
+use crate::{LatencyStats, Sample, Tally, TimeseriesPoint};
+
+/// Seconds a second stays open after a later one has seen a request, for
+/// requests that reach the aggregator out of order
+const LATENESS: usize = 2;
+
+/// Requests by the second they finished in.
+///
+/// Each second is closed into its point, and its line of the --hdr-out log,
+/// shortly after it ends, so only the latencies of the last few seconds are
+/// kept however long the run goes on.
+pub struct Timeseries {
+    size: Duration,
+    /// When measuring started
+    pub start: Instant,
+    points: Vec<TimeseriesPoint>,
+    /// Seconds not closed yet, the first of them right after the last point
+    open: VecDeque<Tally>,
+    hdr: Option<IntervalLog>,
+}
+
+impl Timeseries {
+    /// Starts counting seconds of `size` from `start`, also logging each
+    /// one's latencies for --hdr-out with `hdr`
+    pub fn new(size: Duration, start: Instant, hdr: bool) -> Timeseries {
+        Timeseries {
+            size,
+            start,
+            points: Vec::new(),
+            open: VecDeque::new(),
+            hdr: hdr.then(|| IntervalLog::new(size)),
+        }
+    }
+
+    /// Adds a request to the second it finished in, closing the seconds
+    /// that are over
+    pub fn add(&mut self, sample: &Sample) {
+        let offset: Duration = sample.finished.saturating_duration_since(self.start);
+        let second: usize = (offset.as_nanos() / self.size.as_nanos().max(1)) as usize;
+        // A request later than LATENESS counts in the oldest open second
+        let index: usize = second.saturating_sub(self.points.len());
+        if self.open.len() <= index {
+            self.open.resize_with(index + 1, Tally::default);
+        }
+        self.open[index].add(&sample.outcome, sample.duration);
+        while self.open.len() > LATENESS + 1
+            && let Some(tally) = self.open.pop_front()
+        {
+            self.close(tally);
+        }
+    }
+
+    /// Returns a point for every second of a run that began at `started`
+    /// and lasted `total`, keeping empty ones so gaps in the run show, and
+    /// the --hdr-out log
+    pub fn finish(
+        mut self,
+        started: SystemTime,
+        total: Duration,
+    ) -> (Vec<TimeseriesPoint>, Option<Result<Vec<u8>, String>>) {
+        let count: usize = total.as_nanos().div_ceil(self.size.as_nanos().max(1)) as usize;
+        while let Some(tally) = self.open.pop_front() {
+            self.close(tally);
+        }
+        while self.points.len() < count {
+            self.close(Tally::default());
+        }
+        let hdr: Option<Result<Vec<u8>, String>> = self.hdr.map(|log| log.finish(started));
+        (self.points, hdr)
+    }
+
+    fn close(&mut self, mut tally: Tally) {
+        // Logged before the percentiles sort the latencies
+        if let Some(hdr) = &mut self.hdr {
+            hdr.write(&tally.latencies);
+        }
+        let latency: LatencyStats =
+            LatencyStats::from_latencies(&mut tally.latencies).unwrap_or_default();
+        self.points.push(TimeseriesPoint {
+            second: self.points.len(),
+            requests: tally.completed(),
+            errors: tally.failed,
+            p50_ms: latency.p50_ms,
+            p95_ms: latency.p95_ms,
+            p99_ms: latency.p99_ms,
+        });
+    }
+}
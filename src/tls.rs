@@ -0,0 +1,287 @@
+use clap::ValueEnum;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+    Tls13ClientSessionValue,
+};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, NamedGroup, ProtocolVersion,
+    RootCertStore, SignatureScheme, SupportedProtocolVersion,
+};
+use serde::Deserialize;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::connections;
+
+/// TLS sessions kept for resumption, as many as reqwest keeps by default
+const SESSION_CACHE_SIZE: usize = 256;
+
+/// TLS protocol version
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum TlsVersion {
+    #[value(name = "1.2")]
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[value(name = "1.3")]
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsVersion {
+    pub fn name(self) -> &'static str {
+        match self {
+            TlsVersion::Tls12 => "TLSv1.2",
+            TlsVersion::Tls13 => "TLSv1.3",
+        }
+    }
+}
+
+/// True if the error came from the TLS layer, e.g. a failed handshake
+pub fn is_tls_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    // I/O errors hide the error they wrap from `source()`, sometimes nested
+    error.is::<rustls::Error>()
+        || error
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.get_ref())
+            .is_some_and(|inner| is_tls_error(inner))
+}
+
+/// A client certificate and its private key, with --cert and --key
+pub struct Identity {
+    certificates: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl Identity {
+    /// Reads the certificate chain and the key from PEM
+    pub fn from_pem(cert: &[u8], key: &[u8]) -> Result<Identity, String> {
+        let certificates: Vec<CertificateDer<'static>> = CertificateDer::pem_slice_iter(cert)
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        if certificates.is_empty() {
+            return Err("no certificate found".to_string());
+        }
+        let key: PrivateKeyDer<'static> =
+            PrivateKeyDer::from_pem_slice(key).map_err(|e| e.to_string())?;
+        Ok(Identity { certificates, key })
+    }
+}
+
+/// Reads every certificate from a PEM bundle
+pub fn certificates_from_pem(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, String> {
+    CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// TLS settings of the load test's connections
+pub struct Settings<'a> {
+    /// Trusted on top of the system's root certificates, with --cacert
+    pub ca_certificates: &'a [CertificateDer<'static>],
+    pub identity: Option<&'a Identity>,
+    pub insecure: bool,
+    pub min: Option<TlsVersion>,
+    pub max: Option<TlsVersion>,
+    /// Protocols offered through ALPN, e.g. "h2" and "http/1.1"
+    pub alpn: &'a [&'a str],
+}
+
+/// Builds the TLS configuration of the load test's HTTP client, as reqwest
+/// would from the same settings.
+///
+/// Its session cache marks when each handshake starts, as rustls looks for
+/// a session to resume right before sending the ClientHello, so the
+/// handshake can be timed apart from the TCP connect.
+pub fn client_config(settings: &Settings) -> Result<ClientConfig, String> {
+    let provider: Arc<CryptoProvider> = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_protocol_versions(&versions(settings.min, settings.max))
+        .map_err(|e| e.to_string())?;
+    let builder = if settings.insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AnyCertificate {
+                algorithms: provider.signature_verification_algorithms,
+            }))
+    } else {
+        let mut roots: RootCertStore = RootCertStore::empty();
+        // Native stores often hold a few certificates rustls can't parse,
+        // which reqwest skips too
+        roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+        for certificate in settings.ca_certificates {
+            roots.add(certificate.clone()).map_err(|e| e.to_string())?;
+        }
+        builder.with_root_certificates(roots)
+    };
+    let mut config: ClientConfig = match settings.identity {
+        Some(identity) => builder
+            .with_client_auth_cert(identity.certificates.clone(), identity.key.clone_key())
+            .map_err(|e| e.to_string())?,
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = settings
+        .alpn
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+    config.resumption = Resumption::store(Arc::new(TimedSessions(ClientSessionMemoryCache::new(
+        SESSION_CACHE_SIZE,
+    ))));
+    Ok(config)
+}
+
+/// The TLS versions within the given bounds
+fn versions(
+    min: Option<TlsVersion>,
+    max: Option<TlsVersion>,
+) -> Vec<&'static SupportedProtocolVersion> {
+    [
+        (TlsVersion::Tls12, &rustls::version::TLS12),
+        (TlsVersion::Tls13, &rustls::version::TLS13),
+    ]
+    .into_iter()
+    .filter(|(version, _)| min.is_none_or(|min| *version >= min))
+    .filter(|(version, _)| max.is_none_or(|max| *version <= max))
+    .map(|(_, supported)| supported)
+    .collect()
+}
+
+/// Connects to `address` and returns the TLS version the server for `host`
+/// picks within the given bounds.
+///
+/// Only the handshake is performed, so the server certificate isn't verified.
+pub fn probe_version(
+    host: &str,
+    address: SocketAddr,
+    min: Option<TlsVersion>,
+    max: Option<TlsVersion>,
+    timeout: Duration,
+) -> Result<&'static str, String> {
+    let versions: Vec<&'static SupportedProtocolVersion> = versions(min, max);
+
+    let provider: Arc<CryptoProvider> = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier: Arc<AnyCertificate> = Arc::new(AnyCertificate {
+        algorithms: provider.signature_verification_algorithms,
+    });
+    let config: ClientConfig = ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&versions)
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth();
+
+    let name: ServerName<'static> =
+        ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+    let mut connection: ClientConnection =
+        ClientConnection::new(Arc::new(config), name).map_err(|e| e.to_string())?;
+
+    let mut socket: TcpStream =
+        TcpStream::connect_timeout(&address, timeout).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    while connection.is_handshaking() {
+        connection
+            .complete_io(&mut socket)
+            .map_err(|e| e.to_string())?;
+    }
+
+    match connection.protocol_version() {
+        Some(ProtocolVersion::TLSv1_2) => Ok(TlsVersion::Tls12.name()),
+        Some(ProtocolVersion::TLSv1_3) => Ok(TlsVersion::Tls13.name()),
+        _ => Err("unknown TLS version".to_string()),
+    }
+}
+
+/// Accepts any server certificate, for the probe, which only looks at the
+/// version, and for --insecure
+#[derive(Debug)]
+struct AnyCertificate {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Session cache that records when each handshake starts: rustls asks it
+/// for a session to resume, and for the key exchange the server chose last
+/// time, as it writes the ClientHello
+#[derive(Debug)]
+struct TimedSessions(ClientSessionMemoryCache);
+
+impl ClientSessionStore for TimedSessions {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.0.set_kx_hint(server_name, group);
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        connections::record_handshake_start();
+        self.0.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.0.set_tls12_session(server_name, value);
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        connections::record_handshake_start();
+        self.0.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.0.remove_tls12_session(server_name);
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.0.insert_tls13_ticket(server_name, value);
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        connections::record_handshake_start();
+        self.0.take_tls13_ticket(server_name)
+    }
+}
@@ -0,0 +1,93 @@
+use reqwest::header::{AUTHORIZATION, HeaderMap, PROXY_AUTHORIZATION};
+use reqwest::{Method, Request, Response};
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use crate::{Outcome, Peer, format_bytes, redact_url};
+
+/// One request as -v logs it to stderr; -vv adds the headers both ways
+pub struct Trace {
+    method: Method,
+    url: String,
+    /// Headers sent and received, with -vv
+    sent: Option<HeaderMap>,
+    received: Option<HeaderMap>,
+}
+
+impl Trace {
+    /// Starts tracing `request` as it is about to be sent, unless `verbose`
+    /// is 0. The client adds `defaults` the request doesn't set itself.
+    pub fn start(request: &Request, verbose: u8, defaults: &HeaderMap) -> Option<Trace> {
+        (verbose > 0).then(|| Trace {
+            method: request.method().clone(),
+            url: redact_url(request.url()),
+            sent: (verbose > 1).then(|| {
+                let mut sent: HeaderMap = request.headers().clone();
+                for (name, value) in defaults {
+                    if !request.headers().contains_key(name) {
+                        sent.append(name, value.clone());
+                    }
+                }
+                sent
+            }),
+            received: None,
+        })
+    }
+
+    /// Notes the response headers, with -vv
+    pub fn responded(&mut self, response: &Response) {
+        if self.sent.is_some() {
+            self.received = Some(response.headers().clone());
+        }
+    }
+
+    /// Logs the request with how it ended, all at once so lines of requests
+    /// running side by side don't mix
+    pub fn finish(
+        self,
+        step: Option<&str>,
+        outcome: &Outcome,
+        peer: Option<&Peer>,
+        latency: Duration,
+    ) {
+        let mut line: String = String::new();
+        if let Some(step) = step {
+            let _ = write!(line, "[{}] ", step);
+        }
+        let _ = write!(line, "{} {} → {}", self.method, self.url, describe(outcome));
+        if let Some(body) = peer.and_then(|peer| peer.body.as_ref()) {
+            let _ = write!(line, ", {}", format_bytes(body.received));
+        }
+        let _ = writeln!(line, " in {:.2}ms", latency.as_secs_f64() * 1000.0);
+        for (prefix, headers) in [("> ", &self.sent), ("< ", &self.received)] {
+            for (name, value) in headers.iter().flatten() {
+                // Credentials stay out of logs that get pasted into tickets
+                let value: String = if name == AUTHORIZATION || name == PROXY_AUTHORIZATION {
+                    "[redacted]".to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                let _ = writeln!(line, "  {}{}: {}", prefix, name, value);
+            }
+        }
+        eprint!("{}", line);
+    }
+}
+
+/// How a request ended, for the log
+fn describe(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Response(status) => status.to_string(),
+        Outcome::UnexpectedStatus(status) => format!("{} (unexpected status)", status),
+        Outcome::AssertionFailed(index) => format!("failed assertion {}", index + 1),
+        Outcome::Timeout => "timed out".to_string(),
+        Outcome::TlsError => "TLS error".to_string(),
+        Outcome::DnsError => "DNS error".to_string(),
+        Outcome::ConnectionRefused => "connection refused".to_string(),
+        Outcome::ConnectionReset => "connection reset".to_string(),
+        Outcome::BodyError => "body read error".to_string(),
+        Outcome::Error => "error".to_string(),
+        Outcome::ExtractFailed => "extraction failed".to_string(),
+        Outcome::GraphqlError => "GraphQL error".to_string(),
+    }
+}
@@ -0,0 +1,254 @@
+use serde::Deserialize;
+use std::fmt;
+
+use crate::{color, dashboard, parse_percent, store};
+
+/// Fewest earlier runs a run is judged against
+const MIN_BASELINE: usize = 3;
+
+/// Chart p95 latency and throughput across saved runs
+#[derive(clap::Args, Debug)]
+pub struct TrendCommand {
+    /// URL or --tag of the runs to follow
+    #[arg(value_name = "URL_OR_TAG")]
+    key: String,
+
+    /// SQLite database the runs were saved to with --sqlite
+    #[arg(long, value_name = "FILE", default_value = "loadster.db")]
+    sqlite: String,
+
+    /// Most recent runs to show
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    last: usize,
+
+    /// Runs before each one that it is judged against
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    baseline: usize,
+
+    /// Standard deviations from the baseline's mean a run must be off by to
+    /// count as a regression
+    #[arg(long, value_name = "N", default_value_t = 3.0)]
+    sigma: f64,
+
+    /// Smallest change from the baseline's mean that counts as a
+    /// regression, however steady the baseline, e.g. 5%
+    #[arg(long, value_name = "PERCENT", default_value = "5%", value_parser = parse_percent)]
+    min_change: f64,
+}
+
+/// A saved run, as far as the trend goes
+#[derive(Deserialize)]
+struct Run {
+    id: i64,
+    date: String,
+    p95_ms: Option<f64>,
+    requests_per_sec: Option<f64>,
+}
+
+/// How far a run is from the mean of its baseline
+struct Shift {
+    /// Relative to the mean, e.g. 0.25 for a quarter above it
+    change: f64,
+    /// In standard deviations of the baseline
+    sigmas: f64,
+}
+
+impl fmt::Display for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:+.1}%", self.change * 100.0)?;
+        // Against a baseline that never moved there is no deviation to count
+        if self.sigmas.is_finite() {
+            write!(f, " ({:+.1}σ)", self.sigmas)?;
+        }
+        Ok(())
+    }
+}
+
+/// What got worse in a run
+#[derive(Default)]
+struct Regression {
+    p95: Option<Shift>,
+    rps: Option<Shift>,
+}
+
+impl Regression {
+    fn found(&self) -> bool {
+        self.p95.is_some() || self.rps.is_some()
+    }
+
+    fn describe(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(shift) = &self.p95 {
+            parts.push(format!("p95 {}", shift));
+        }
+        if let Some(shift) = &self.rps {
+            parts.push(format!("req/s {}", shift));
+        }
+        parts.join(", ")
+    }
+}
+
+pub fn run(command: TrendCommand) {
+    if command.baseline < MIN_BASELINE {
+        eprintln!("✗ --baseline must be at least {}", MIN_BASELINE);
+        std::process::exit(1);
+    }
+    color::init(false);
+    // Enough earlier runs are read for the first one shown to have a baseline
+    let sql: String = format!(
+        "SELECT id, date, p95_ms, requests_per_sec FROM runs WHERE url = {key} OR tag = {key} \
+         ORDER BY id DESC LIMIT {}",
+        command.last + command.baseline,
+        key = store::text(&command.key)
+    );
+    let mut runs: Vec<Run> = match store::query(&command.sqlite, &sql) {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("✗ Failed to read runs from {}: {}", command.sqlite, e);
+            std::process::exit(1);
+        }
+    };
+    runs.reverse();
+    if runs.is_empty() {
+        eprintln!(
+            "✗ No runs of {} in {}; save some with --sqlite",
+            command.key, command.sqlite
+        );
+        std::process::exit(1);
+    }
+
+    let regressions: Vec<Regression> = (0..runs.len())
+        .map(|index| judge(&runs, index, &command))
+        .collect();
+    let shown: usize = runs.len().saturating_sub(command.last);
+    let (runs, regressions) = (&runs[shown..], &regressions[shown..]);
+
+    println!(
+        "Trend of {} runs of {} (each judged against the {} before it):",
+        runs.len(),
+        command.key,
+        command.baseline
+    );
+    let p95s: Vec<f64> = runs.iter().map(|run| run.p95_ms.unwrap_or(0.0)).collect();
+    let rates: Vec<f64> = runs
+        .iter()
+        .map(|run| run.requests_per_sec.unwrap_or(0.0))
+        .collect();
+    println!(
+        "\np95 latency (min {:.2}ms, max {:.2}ms):",
+        p95s.iter().copied().fold(f64::INFINITY, f64::min),
+        p95s.iter().copied().fold(0.0, f64::max)
+    );
+    println!("  {}", dashboard::sparkline(&p95s));
+    println!(
+        "Requests/sec (min {:.2}, max {:.2}):",
+        rates.iter().copied().fold(f64::INFINITY, f64::min),
+        rates.iter().copied().fold(0.0, f64::max)
+    );
+    println!("  {}", dashboard::sparkline(&rates));
+
+    println!(
+        "\n  {:>5}  {:<19}  {:>10}  {:>10}",
+        "Run", "Date", "p95", "Req/s"
+    );
+    for (run, regression) in runs.iter().zip(regressions) {
+        let mut line: String = format!(
+            "  {:>5}  {:<19}  {:>10}  {:>10}",
+            run.id,
+            run.date.get(..19).unwrap_or(&run.date).replace('T', " "),
+            run.p95_ms
+                .map_or("-".to_string(), |p95| format!("{:.2}ms", p95)),
+            run.requests_per_sec
+                .map_or("-".to_string(), |rate| format!("{:.2}", rate))
+        );
+        if regression.found() {
+            line = color::alert(format!("{}  ⚠ {}", line, regression.describe()));
+        }
+        println!("{}", line);
+    }
+
+    let (Some(latest), Some(regression)) = (runs.last(), regressions.last()) else {
+        return;
+    };
+    if regression.found() {
+        println!(
+            "\n{}",
+            color::alert(format!(
+                "✗ Run {} regressed against the runs before it: {}",
+                latest.id,
+                regression.describe()
+            ))
+        );
+        std::process::exit(1);
+    }
+    if shown + runs.len() > MIN_BASELINE {
+        println!(
+            "\n{} Run {} is in line with the runs before it",
+            color::green("✓"),
+            latest.id
+        );
+    } else {
+        println!(
+            "\nRun {} has too few runs before it to judge (at least {})",
+            latest.id, MIN_BASELINE
+        );
+    }
+}
+
+/// Compares the run at `index` with the runs just before it: higher p95 or
+/// lower throughput is a regression when it is both `--sigma` standard
+/// deviations and `--min-change` away from their mean
+fn judge(runs: &[Run], index: usize, command: &TrendCommand) -> Regression {
+    let earlier: &[Run] = &runs[index.saturating_sub(command.baseline)..index];
+    let run: &Run = &runs[index];
+    let mut regression: Regression = Regression::default();
+    let p95s: Vec<f64> = earlier.iter().filter_map(|run| run.p95_ms).collect();
+    if let Some(p95) = run.p95_ms
+        && let Some(shift) = shift(p95, &p95s)
+        && shift.change > command.min_change
+        && shift.sigmas > command.sigma
+    {
+        regression.p95 = Some(shift);
+    }
+    let rates: Vec<f64> = earlier
+        .iter()
+        .filter_map(|run| run.requests_per_sec)
+        .collect();
+    if let Some(rate) = run.requests_per_sec
+        && let Some(shift) = shift(rate, &rates)
+        && shift.change < -command.min_change
+        && shift.sigmas < -command.sigma
+    {
+        regression.rps = Some(shift);
+    }
+    regression
+}
+
+/// How far `value` is from `baseline`, which needs `MIN_BASELINE` values
+fn shift(value: f64, baseline: &[f64]) -> Option<Shift> {
+    if baseline.len() < MIN_BASELINE {
+        return None;
+    }
+    let count: f64 = baseline.len() as f64;
+    let mean: f64 = baseline.iter().sum::<f64>() / count;
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance: f64 = baseline
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (count - 1.0);
+    let deviation: f64 = variance.sqrt();
+    // A baseline that never moved makes any change beyond --min-change stand
+    // out
+    let sigmas: f64 = if deviation > 0.0 {
+        (value - mean) / deviation
+    } else {
+        (value - mean).signum() * f64::INFINITY
+    };
+    Some(Shift {
+        change: (value - mean) / mean,
+        sigmas,
+    })
+}
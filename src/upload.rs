@@ -0,0 +1,63 @@
+use bytes::Bytes;
+use futures_util::stream;
+use std::io;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::parse_size;
+
+/// Each chunk of a paced upload carries this long's worth of the rate
+const CHUNK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Parses an upload rate such as "1MB/s", "256KB/s" or "500" (bytes per
+/// second); units are powers of 1024
+pub fn parse_upload_rate(s: &str) -> Result<f64, String> {
+    let size: &str = s.trim();
+    let size: &str = size.strip_suffix("/s").unwrap_or(size);
+    match parse_size(size) {
+        Ok(bytes) if bytes > 0 => Ok(bytes as f64),
+        _ => Err(format!(
+            "invalid upload rate \"{}\" (expected bytes per second, e.g. 1MB/s)",
+            s
+        )),
+    }
+}
+
+/// Records when a paced body has been handed to the connection in full
+#[derive(Clone, Default)]
+pub struct UploadClock(Arc<OnceLock<Instant>>);
+
+impl UploadClock {
+    /// Time since the last byte went out, or `None` if the upload never
+    /// finished, e.g. because the server answered early
+    pub fn since_finished(&self) -> Option<Duration> {
+        self.0.get().map(Instant::elapsed)
+    }
+}
+
+/// Streams `body` at `rate` bytes per second instead of in one burst, the
+/// way a client on a slow link uploads it. Each chunk goes out once the rate
+/// allows all of its bytes, so the whole body takes `len / rate` seconds.
+pub fn paced(body: Bytes, rate: f64, clock: UploadClock) -> reqwest::Body {
+    let chunk_size: usize = ((rate * CHUNK_INTERVAL.as_secs_f64()) as usize).max(1);
+    let chunks = stream::unfold((0, None), move |(sent, start): (usize, Option<Instant>)| {
+        let body: Bytes = body.clone();
+        let clock: UploadClock = clock.clone();
+        async move {
+            // The clock starts when the connection asks for the first chunk
+            let start: Instant = start.unwrap_or_else(Instant::now);
+            if sent >= body.len() {
+                let _ = clock.0.set(Instant::now());
+                return None;
+            }
+            let end: usize = (sent + chunk_size).min(body.len());
+            let due: Instant = start + Duration::from_secs_f64(end as f64 / rate);
+            tokio::time::sleep_until(due.into()).await;
+            Some((
+                Ok::<Bytes, io::Error>(body.slice(sent..end)),
+                (end, Some(start)),
+            ))
+        }
+    });
+    reqwest::Body::wrap_stream(chunks)
+}
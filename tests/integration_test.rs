@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
@@ -168,3 +170,5418 @@ fn test_output_file_path_validation() {
     // Should handle invalid path gracefully
     cmd.assert().code(predicate::ne(2)); // Not an argument parsing error
 }
+
+#[test]
+fn test_data_conflicts_with_data_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "-d", "{}", "--data-file", "body.json"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_missing_data_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://example.com",
+        "--data-file",
+        "/invalid/path/that/does/not/exist/body.json",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read data file"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_post_with_body() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "-n",
+        "5",
+        "-c",
+        "2",
+        "-d",
+        "{\"hello\":\"world\"}",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful:"));
+}
+
+#[test]
+fn test_invalid_header_format() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "-H", "MissingColon"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected \"Name: value\""));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_custom_headers() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/headers",
+        "-n",
+        "5",
+        "-c",
+        "2",
+        "-H",
+        "X-Api-Key: secret",
+        "--header",
+        "Accept: application/json",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful:"));
+}
+
+#[test]
+fn test_invalid_timeout() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "--timeout", "10parsecs"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid duration"));
+}
+
+#[test]
+fn test_duration_out_of_range() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--timeout",
+        "99999999999999999999999h",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("out of range"))
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_timeout_counted_as_failure() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/delay/3",
+        "-n",
+        "2",
+        "-c",
+        "2",
+        "--timeout",
+        "500ms",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Timed out: 2"));
+}
+
+#[test]
+fn test_duration_conflicts_with_requests() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "-n", "10", "-z", "5s"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_duration_mode() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/get",
+        "-z",
+        "3s",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duration: 3.00s"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert!(json["total_requests"].as_u64().unwrap() > 0);
+    assert!(json["total_duration_secs"].as_f64().unwrap() >= 3.0);
+}
+
+#[test]
+fn test_invalid_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "--rate", "0"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected a positive number"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_rate_limited_run() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://httpbin.org/get", "-n", "10", "-c", "5", "-q", "5"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("(target: 5.00)"));
+}
+
+#[test]
+fn test_invalid_stage_format() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "--stage", "30s"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected \"DURATION:TARGET\""));
+}
+
+#[test]
+fn test_stage_conflicts_with_duration() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "--stage", "10s:5", "-z", "10s"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_stage_unit_requires_stages() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "--stage-unit", "rate"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--stage <DURATION:TARGET>"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_staged_run_reports_stages() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/get",
+        "--stage",
+        "2s:2",
+        "--stage",
+        "2s:0",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Stages:"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["stages"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_run_missing_config_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", "/invalid/path/that/does/not/exist.yaml"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load scenario"));
+}
+
+#[test]
+fn test_run_config_unknown_field() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(&config_path, "url: http://example.com\nconcurency: 5\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown field `concurency`"));
+}
+
+#[test]
+fn test_run_config_requires_url() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.toml");
+    fs::write(&config_path, "requests = 5\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("does not set one"));
+}
+
+#[test]
+fn test_run_config_threshold_breach_exits_non_zero() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 3\nconcurrency: 1\nthresholds:\n  - error_rate<1%\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("✗ error_rate<1%"));
+}
+
+#[test]
+fn test_run_cli_flags_override_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 50\nconcurrency: 5\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap(), "-n", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Total requests: 2"))
+        .stdout(predicate::str::contains("Concurrency: 5"));
+}
+
+#[test]
+fn test_invalid_method() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com", "-m", "NOT A METHOD"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid HTTP method"));
+}
+
+#[test]
+fn test_run_steps_invalid_json_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "steps:\n  - url: http://127.0.0.1:1/login\n    extract:\n      token:\n        json: token\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unsupported JSONPath"));
+}
+
+#[test]
+fn test_run_steps_relative_url_needs_base() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(&config_path, "steps:\n  - url: /login\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("needs a top-level url"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_run_steps_with_extraction() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(
+        &config_path,
+        r#"url: https://httpbin.org
+requests: 3
+concurrency: 1
+steps:
+  - name: login
+    url: /uuid
+    extract:
+      token:
+        json: $.uuid
+  - name: profile
+    url: /bearer
+    headers:
+      Authorization: Bearer {{token}}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--config",
+        config_path.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "2 step scenario: login -> profile",
+    ));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let steps = json["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1]["requests"], 3);
+}
+
+#[test]
+fn test_zero_url_weight() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://example.com/a@0"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("must be at least 1"));
+}
+
+#[test]
+fn test_weighted_urls_report_breakdown() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    // Nothing listens on port 1, so requests fail fast without network access
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1/list@3",
+        "http://127.0.0.1:1/detail@1",
+        "-n",
+        "8",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("URLs:"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let targets = json["targets"].as_array().unwrap();
+    assert_eq!(targets[0]["url"], "http://127.0.0.1:1/list");
+    assert_eq!(targets[0]["requests"], 6);
+    assert_eq!(targets[1]["requests"], 2);
+}
+
+#[test]
+fn test_url_file_replaces_url_argument() {
+    let temp_dir = TempDir::new().unwrap();
+    let urls_path = temp_dir.path().join("urls.txt");
+    fs::write(
+        &urls_path,
+        "# sampled paths\nhttp://127.0.0.1:1/a\n\nhttp://127.0.0.1:1/b\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["--url-file", urls_path.to_str().unwrap(), "-n", "4"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2 URLs from"));
+}
+
+#[test]
+fn test_url_file_from_stdin_with_base_url() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--url-file",
+        "-",
+        "--url-order",
+        "random",
+        "-n",
+        "4",
+    ])
+    .write_stdin("/a\n/b\n/c\n");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("3 URLs from - (random)"));
+}
+
+#[test]
+fn test_url_file_relative_path_needs_base_url() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["--url-file", "-"]).write_stdin("/a\n");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("needs a base URL"));
+}
+
+#[test]
+fn test_invalid_template_placeholder() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1/items?id={{rand_int 10}}", "-n", "1"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid template"));
+}
+
+#[test]
+fn test_run_steps_invalid_template() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        r#"url: http://127.0.0.1:1
+steps:
+  - name: create
+    url: /items
+    body: '{"n":{{rand_int 9 1}}}'
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("create: invalid placeholder"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_templated_request() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/anything/{{seq}}?n={{rand_int 1 100}}",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "-H",
+        "X-Request-Id: {{uuid}}",
+        "-d",
+        r#"{"sent_at":{{timestamp}}}"#,
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+}
+
+#[test]
+fn test_data_order_requires_data_csv() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--data-order", "random"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--data-csv"));
+}
+
+#[test]
+fn test_missing_data_csv() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--data-csv", "/nonexistent/users.csv"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load CSV data"));
+}
+
+#[test]
+fn test_sequential_data_csv_ends_after_last_row() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("users.csv");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(&csv_path, "user_id,token\n1,abc\n2,def\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1/users/{{user_id}}",
+        "--data-csv",
+        csv_path.to_str().unwrap(),
+        "--data-order",
+        "sequential",
+        "-n",
+        "10",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Data: 2 rows from"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["total_requests"], 2);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_data_csv_request() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("users.csv");
+    fs::write(&csv_path, "user,password\nalice,secret1\nbob,secret2\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/basic-auth/{{user}}/{{password}}",
+        "--data-csv",
+        csv_path.to_str().unwrap(),
+        "--data-order",
+        "random",
+        "-n",
+        "4",
+    ]);
+
+    cmd.assert().success();
+}
+
+#[test]
+fn test_unknown_fake_data() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-d", r#"{"phone":"{{fake.phone}}"}"#]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown fake data"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_fake_data_request() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "-n",
+        "3",
+        "-H",
+        "Content-Type: application/json",
+        "-H",
+        "X-Forwarded-For: {{fake.ipv4}}",
+        "-d",
+        r#"{"name":"{{fake.name}}","email":"{{fake.email}}"}"#,
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+}
+
+#[test]
+fn test_invalid_basic_auth() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--basic-auth", "admin"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected USER:PASSWORD"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_basic_auth_request() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/basic-auth/admin/secret",
+        "-n",
+        "3",
+        "--basic-auth",
+        "admin:secret",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+}
+
+#[test]
+fn test_bearer_conflicts_with_basic_auth() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--bearer",
+        "abc123",
+        "--basic-auth",
+        "admin:secret",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_missing_bearer_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--bearer-file", "/nonexistent/token"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load bearer token"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_bearer_file_request() {
+    let temp_dir = TempDir::new().unwrap();
+    let token_path = temp_dir.path().join("token");
+    fs::write(&token_path, "abc123\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/bearer",
+        "-n",
+        "3",
+        "--bearer-file",
+        token_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+}
+
+#[test]
+fn test_oauth2_requires_client_credentials() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--oauth2-token-url",
+        "http://127.0.0.1:1/token",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--oauth2-client-id"));
+}
+
+#[test]
+fn test_oauth2_token_request_failure() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--oauth2-token-url",
+        "http://127.0.0.1:1/token",
+        "--oauth2-client-id",
+        "loadtest",
+        "--oauth2-client-secret",
+        "secret",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to get OAuth2 token"));
+}
+
+#[test]
+fn test_oauth2_token_with_huge_lifetime() {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                let body = r#"{"access_token":"abc123","expires_in":1e300}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0 || stream.write_all(response.as_bytes()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "3",
+        "--oauth2-token-url",
+        &format!("{}token", url),
+        "--oauth2-client-id",
+        "loadtest",
+        "--oauth2-client-secret",
+        "secret",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+}
+
+#[test]
+fn test_invalid_aws_sigv4_scope() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--aws-sigv4", "us-east-1"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected REGION/SERVICE"));
+}
+
+#[test]
+fn test_aws_sigv4_missing_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    let credentials_path = temp_dir.path().join("credentials");
+    fs::write(
+        &credentials_path,
+        "[default]\naws_access_key_id = AKIDEXAMPLE\naws_secret_access_key = secret\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--aws-sigv4",
+        "us-east-1/execute-api",
+        "--aws-profile",
+        "staging",
+    ])
+    .env("AWS_SHARED_CREDENTIALS_FILE", &credentials_path);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("profile \"staging\" not found"));
+}
+
+#[test]
+fn test_aws_sigv4_with_profile_credentials() {
+    let temp_dir = TempDir::new().unwrap();
+    let credentials_path = temp_dir.path().join("credentials");
+    fs::write(
+        &credentials_path,
+        "[staging]\naws_access_key_id = AKIDEXAMPLE\naws_secret_access_key = secret\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "2",
+        "--aws-sigv4",
+        "us-east-1/execute-api",
+        "--aws-profile",
+        "staging",
+    ])
+    .env("AWS_SHARED_CREDENTIALS_FILE", &credentials_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Total requests: 2"));
+}
+
+#[test]
+fn test_cert_requires_key() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://127.0.0.1:1", "--cert", "client.pem"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--key"));
+}
+
+#[test]
+fn test_missing_client_certificate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://127.0.0.1:1",
+        "--cert",
+        "/nonexistent/client.pem",
+        "--key",
+        "/nonexistent/client.key",
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Failed to load client certificate",
+    ));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_tls_errors_reported() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://self-signed.badssl.com/", "-n", "2"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TLS errors: 2"));
+}
+
+#[test]
+fn test_cacert_conflicts_with_insecure() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://127.0.0.1:1", "--cacert", "ca.pem", "--insecure"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_missing_cacert() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://127.0.0.1:1", "--cacert", "/nonexistent/ca.pem"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load CA certificates"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_insecure_accepts_self_signed_certificate() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://self-signed.badssl.com/",
+        "-n",
+        "2",
+        "--insecure",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 2"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["tls"]["insecure"], true);
+}
+
+#[test]
+fn test_invalid_tls_version() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["https://127.0.0.1:1", "--tls-min-version", "1.1"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn test_tls_min_version_above_max() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://127.0.0.1:1",
+        "--tls-min-version",
+        "1.3",
+        "--tls-max-version",
+        "1.2",
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--tls-min-version is higher than --tls-max-version",
+    ));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_tls_max_version_records_negotiated_version() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/200",
+        "-n",
+        "2",
+        "--tls-max-version",
+        "1.2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TLS version: TLSv1.2"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["tls"]["negotiated_version"], "TLSv1.2");
+}
+
+#[test]
+fn test_http_version_flags_conflict() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--http1.1", "--http2"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_http1_records_protocol() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/200",
+        "-n",
+        "2",
+        "--http1.1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Protocol: HTTP/1.1"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["http_version"], "http1.1");
+    assert_eq!(json["protocols"]["HTTP/1.1"], 2);
+}
+
+#[test]
+fn test_missing_unix_socket() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://localhost/health",
+        "--unix-socket",
+        "/nonexistent/app.sock",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unix socket not found"));
+}
+
+#[test]
+fn test_invalid_proxy() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--proxy", "ftp://proxy:21"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid proxy URL"));
+}
+
+#[test]
+fn test_proxy_conflicts_with_unix_socket() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://localhost/health",
+        "--proxy",
+        "http://proxy:3128",
+        "--unix-socket",
+        "/var/run/app.sock",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_invalid_socks5_proxy() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--socks5", "localhost"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid SOCKS5 proxy"));
+}
+
+#[test]
+fn test_invalid_resolve() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://example.com",
+        "--resolve",
+        "example.com:80:not-an-ip",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid IP address"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_resolve_pins_host() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    // Resolve httpbin.org's address up front, then pin an unresolvable
+    // name's lookups to it
+    let addr = std::net::ToSocketAddrs::to_socket_addrs("httpbin.org:80")
+        .unwrap()
+        .find(|addr| addr.is_ipv4())
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://httpbin.invalid/status/200",
+        "-n",
+        "2",
+        "-H",
+        "Host: httpbin.org",
+        "--resolve",
+        &format!("httpbin.invalid:80:{}", addr.ip()),
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 2"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert!(json["resolve"]["httpbin.invalid"].is_array());
+}
+
+#[test]
+fn test_dns_errors_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://nonexistent.invalid/",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "--dns-per-request",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("DNS errors: 2"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["dns_errors"], 2);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_spread_ips_reports_addresses() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/200",
+        "-n",
+        "6",
+        "-c",
+        "3",
+        "--spread-ips",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Addresses:"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let requests: u64 = json["addresses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|address| address["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(requests, 6);
+}
+
+#[test]
+fn test_invalid_local_address() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--local-address", "eth0"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid IP address"));
+}
+
+#[test]
+fn test_unavailable_local_address() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    // TEST-NET-3, never assigned to a local interface
+    cmd.args(["http://127.0.0.1:1", "--local-address", "203.0.113.7"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot use local address"));
+}
+
+#[test]
+fn test_local_address_cidr_too_large() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--local-address", "10.0.0.0/8"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("too many addresses"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_disable_keepalive_opens_connection_per_request() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/200",
+        "-n",
+        "4",
+        "-c",
+        "1",
+        "--disable-keepalive",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["connections_opened"], 4);
+    assert_eq!(json["connections_reused"], 0);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_redirects_not_followed_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/redirect/2",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Redirect responses: 3"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["redirects"], 3);
+    assert!(json.get("redirects_followed").is_none());
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_follow_redirects_counts_followed() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/redirect/2",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--follow-redirects",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["redirects"], 0);
+    assert_eq!(json["redirects_followed"], 6);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_cookies_set_by_responses_are_counted() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/cookies/set?session=abc",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "--cookies",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Cookies: enabled"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["cookies_set"], 2);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_sessions_keep_cookies_per_user() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/cookies/set?session=abc",
+        "-n",
+        "6",
+        "-c",
+        "3",
+        "--sessions",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Sessions: one per concurrent worker",
+    ));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["sessions"], 3);
+    assert_eq!(json["connections_opened"], 3);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_compression_reports_wire_and_decompressed_bytes() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/gzip",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--compression",
+        "gzip",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Response bodies (gzip)"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let received = json["compression"]["bytes_received"].as_u64().unwrap();
+    let decompressed = json["compression"]["bytes_decompressed"].as_u64().unwrap();
+    assert_eq!(json["compression"]["accept_encoding"], "gzip");
+    assert!(received > 0);
+    assert!(decompressed > received);
+}
+
+#[test]
+fn test_invalid_compression() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--compression", "lzma"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'lzma'"));
+}
+
+#[test]
+fn test_compress_body_only_supports_gzip() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-d",
+        "payload",
+        "--compress-body",
+        "br",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("possible values: gzip"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_form_upload() {
+    let temp_dir = TempDir::new().unwrap();
+    let upload_path = temp_dir.path().join("upload.txt");
+    fs::write(&upload_path, "file contents").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "-F",
+        "title=hello",
+        "-F",
+        &format!("file=@{}", upload_path.to_str().unwrap()),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Load testing: POST"))
+        .stdout(predicate::str::contains("Successful: 2"));
+}
+
+#[test]
+fn test_form_missing_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-F", "file=@/nonexistent/upload.bin"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to open form file"));
+}
+
+#[test]
+fn test_form_conflicts_with_data() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-F", "a=b", "-d", "body"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_invalid_body_size() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--body-size", "64XB"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size unit"));
+}
+
+#[test]
+fn test_body_random_requires_body_size() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--body-random"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--body-size"));
+}
+
+#[test]
+fn test_upload_rate_requires_body() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--upload-rate", "1MB/s"]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--upload-rate needs a request body",
+    ));
+}
+
+#[test]
+fn test_invalid_upload_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-d", "x", "--upload-rate", "fast"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid upload rate"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_upload_rate_reports_time_to_ack() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "--body-size",
+        "8KB",
+        "--upload-rate",
+        "16KB/s",
+        "-n",
+        "2",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Upload rate: 16.00 KiB/s"))
+        .stdout(predicate::str::contains("after the last upload byte"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["upload"]["rate_bytes_per_sec"], 16384.0);
+    assert!(report["latency"]["min_ms"].as_f64().unwrap() >= 400.0);
+}
+
+#[test]
+fn test_graphql_missing_query_file() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--graphql", "/nonexistent/query.gql"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load GraphQL query"));
+}
+
+#[test]
+fn test_graphql_variables_must_be_an_object() {
+    let temp_dir = TempDir::new().unwrap();
+    let query_path = temp_dir.path().join("query.gql");
+    let variables_path = temp_dir.path().join("vars.json");
+    fs::write(&query_path, "{ viewer { id } }").unwrap();
+    fs::write(&variables_path, "[1, 2]").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--graphql",
+        query_path.to_str().unwrap(),
+        "--variables",
+        variables_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("must contain a JSON object"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_graphql_response_without_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let query_path = temp_dir.path().join("query.gql");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(&query_path, "{ viewer { id } }").unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--graphql",
+        query_path.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 3"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["graphql_errors"], 0);
+}
+
+const ECHO_PROTO: &str = r#"
+syntax = "proto3";
+package test;
+
+message EchoRequest {
+  string text = 1;
+}
+
+message EchoReply {
+  string text = 1;
+}
+
+service Echo {
+  rpc Say(EchoRequest) returns (EchoReply);
+  rpc Repeat(EchoRequest) returns (stream EchoReply);
+}
+"#;
+
+#[test]
+fn test_grpc_unreachable_server_reports_unavailable() {
+    let temp_dir = TempDir::new().unwrap();
+    let proto_path = temp_dir.path().join("echo.proto");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(&proto_path, ECHO_PROTO).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Say",
+        "--proto",
+        proto_path.to_str().unwrap(),
+        "-d",
+        r#"{"text":"hello"}"#,
+        "-n",
+        "3",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Failed: 3"))
+        .stdout(predicate::str::contains("UNAVAILABLE: 3"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["method"], "test.Echo/Say");
+    assert_eq!(json["status_codes"]["UNAVAILABLE"], 3);
+}
+
+#[test]
+fn test_grpc_server_streaming_reports_message_timing() {
+    let temp_dir = TempDir::new().unwrap();
+    let proto_path = temp_dir.path().join("echo.proto");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(&proto_path, ECHO_PROTO).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Repeat",
+        "--proto",
+        proto_path.to_str().unwrap(),
+        "-n",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Server streaming"))
+        .stdout(predicate::str::contains("Messages received: 0"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["streaming"]["messages_received"], 0);
+}
+
+#[test]
+fn test_grpc_message_array_needs_client_streaming() {
+    let temp_dir = TempDir::new().unwrap();
+    let proto_path = temp_dir.path().join("echo.proto");
+    fs::write(&proto_path, ECHO_PROTO).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Repeat",
+        "--proto",
+        proto_path.to_str().unwrap(),
+        "-d",
+        r#"[{"text":"a"},{"text":"b"}]"#,
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("takes a single message"));
+}
+
+#[test]
+fn test_grpc_unknown_method() {
+    let temp_dir = TempDir::new().unwrap();
+    let proto_path = temp_dir.path().join("echo.proto");
+    fs::write(&proto_path, ECHO_PROTO).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Shout",
+        "--proto",
+        proto_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("method \"Shout\" not found"));
+}
+
+#[test]
+fn test_grpc_invalid_request_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let proto_path = temp_dir.path().join("echo.proto");
+    fs::write(&proto_path, ECHO_PROTO).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Say",
+        "--proto",
+        proto_path.to_str().unwrap(),
+        "-d",
+        r#"{"volume":11}"#,
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Invalid request for test.EchoRequest",
+    ));
+}
+
+#[test]
+fn test_grpc_reflect_unreachable_server() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["grpc", "http://127.0.0.1:1", "test.Echo/Say", "--reflect"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("reflection failed"))
+        .stderr(predicate::str::contains("UNAVAILABLE"));
+}
+
+#[test]
+fn test_grpc_reflect_conflicts_with_proto() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "grpc",
+        "http://127.0.0.1:1",
+        "test.Echo/Say",
+        "--reflect",
+        "--proto",
+        "echo.proto",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_grpc_requires_service_definition() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["grpc", "http://127.0.0.1:1", "test.Echo/Say"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--proto"));
+}
+
+#[test]
+fn test_invalid_cookie() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--cookie", "session"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected NAME=VALUE"));
+}
+
+#[test]
+fn test_tcp_keepalive_interval_requires_tcp_keepalive() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--tcp-keepalive-interval", "10s"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--tcp-keepalive <DURATION>"));
+}
+
+#[test]
+fn test_max_idle_per_host_conflicts_with_disable_keepalive() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--max-idle-per-host",
+        "5",
+        "--disable-keepalive",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_sse_unreachable_server_counts_failed_attempts() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "sse",
+        "http://127.0.0.1:1/events",
+        "-c",
+        "2",
+        "-z",
+        "500ms",
+        "--reconnect-delay",
+        "100ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Events received: 0"))
+        .stdout(predicate::str::contains("Reconnects:"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let attempts = report["connection_attempts"].as_u64().unwrap();
+    assert!(attempts > 2);
+    assert_eq!(report["failed_attempts"].as_u64().unwrap(), attempts);
+    assert_eq!(report["reconnects"].as_u64().unwrap(), attempts - 2);
+}
+
+#[test]
+fn test_sse_invalid_reconnect_delay() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "sse",
+        "http://127.0.0.1:1/events",
+        "--reconnect-delay",
+        "soon",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--reconnect-delay"));
+}
+
+#[test]
+fn test_download_unreachable_server() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["download", "http://127.0.0.1:1/big.iso"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to get the object size"));
+}
+
+#[test]
+fn test_download_invalid_range_size() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "download",
+        "http://127.0.0.1:1/big.iso",
+        "--range-size",
+        "huge",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_download_in_ranges() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "download",
+        "https://httpbin.org/range/4096",
+        "--range-size",
+        "1KB",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("in 4 ranges"))
+        .stdout(predicate::str::contains("Latency per range"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["object_size"], 4096);
+    assert_eq!(report["successful"], 4);
+    assert_eq!(report["bytes_downloaded"], 4096);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_response_bodies_are_read() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/bytes/2048",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Received: 6.00 KiB of response bodies",
+        ))
+        .stdout(predicate::str::contains("Download: p50"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["bytes_received"], 6144);
+    assert!(report["throughput_bytes_per_sec"].as_f64().unwrap() > 0.0);
+    assert!(
+        report["phases"]["download"]["max_ms"].as_f64().unwrap()
+            <= report["latency"]["max_ms"].as_f64().unwrap()
+    );
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_request_phases_are_timed() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/get",
+        "-n",
+        "4",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Phases:"))
+        .stdout(predicate::str::contains("DNS lookup: p50"))
+        .stdout(predicate::str::contains("TCP connect: p50"))
+        .stdout(predicate::str::contains("TLS handshake: p50"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let phases: &serde_json::Value = &report["phases"];
+    assert!(phases["dns"]["max_ms"].as_f64().unwrap() > 0.0);
+    assert!(phases["connect"]["max_ms"].as_f64().unwrap() > 0.0);
+    assert!(phases["tls"]["max_ms"].as_f64().unwrap() > 0.0);
+}
+
+#[test]
+fn test_tls_handshake_timed_apart_from_connect() {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    // Answers every request on a TLS connection with an empty 200
+    let certificate = CertificateDer::from_pem_file("tests/fixtures/localhost.pem").unwrap();
+    let key = PrivateKeyDer::from_pem_file("tests/fixtures/localhost.key").unwrap();
+    let config = Arc::new(
+        rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![certificate], key)
+        .unwrap(),
+    );
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let config = Arc::clone(&config);
+            std::thread::spawn(move || {
+                let connection = rustls::ServerConnection::new(config).unwrap();
+                let mut stream = rustls::StreamOwned::new(connection, stream);
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "4",
+        "-c",
+        "1",
+        "--disable-keepalive",
+        "--cacert",
+        "tests/fixtures/localhost.pem",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 4"))
+        .stdout(predicate::str::contains("TCP connect: p50"))
+        .stdout(predicate::str::contains("(4 handshakes)"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let phases: &serde_json::Value = &report["phases"];
+    assert!(phases["connect"]["min_ms"].as_f64().unwrap() > 0.0);
+    assert!(phases["tls"]["min_ms"].as_f64().unwrap() > 0.0);
+
+    // The certificate is only trusted with --cacert or --insecure
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([url.as_str(), "-n", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TLS errors: 1"));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([url.as_str(), "-n", "1", "--insecure"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 1"));
+
+    // Plain HTTP connections have no handshake
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TLS handshake").not());
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_time_to_first_byte_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/drip?duration=1&numbytes=5",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Time to first byte:"));
+
+    // The body trickles in over a second, after the headers
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let ttfb: f64 = report["ttfb"]["p50_ms"].as_f64().unwrap();
+    assert!(ttfb > 0.0);
+    assert!(ttfb < report["latency"]["p50_ms"].as_f64().unwrap());
+    assert!(report["ttfb"]["p99_ms"].as_f64().is_some());
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_request_bodies_report_upload_throughput() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/post",
+        "--body-size",
+        "4KB",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Sent: 8.00 KiB of request bodies"))
+        .stdout(predicate::str::contains("Per request: min"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["bytes_sent"], 8192);
+    assert!(report["upload_throughput_bytes_per_sec"].as_f64().unwrap() > 0.0);
+    assert!(
+        report["upload_per_request"]["p50_bytes_per_sec"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+}
+
+#[test]
+fn test_upload_rates_per_request() {
+    use std::io::{Read, Write};
+
+    // Reads each whole request body before answering
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                loop {
+                    let Ok(read) = stream.read(&mut buffer) else {
+                        return;
+                    };
+                    if read == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_lowercase();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length: usize = text
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .map_or(0, |length| length.trim().parse().unwrap());
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-m",
+        "POST",
+        "--body-size",
+        "4KB",
+        "-n",
+        "20",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Per request: min"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["bytes_sent"], 20 * 4096);
+    let rates = &report["upload_per_request"];
+    let min = rates["min_bytes_per_sec"].as_f64().unwrap();
+    let p50 = rates["p50_bytes_per_sec"].as_f64().unwrap();
+    let max = rates["max_bytes_per_sec"].as_f64().unwrap();
+    let avg = rates["avg_bytes_per_sec"].as_f64().unwrap();
+    assert!(min > 0.0);
+    // The median comes from a histogram, to within 0.1%
+    assert!(min * 0.999 <= p50 && p50 <= max * 1.001);
+    assert!(min <= avg && avg <= max);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_status_code_distribution() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/200,429",
+        "-n",
+        "10",
+        "-c",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Status codes:"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let counts: &serde_json::Map<String, serde_json::Value> =
+        report["status_codes"].as_object().unwrap();
+    assert!(
+        counts
+            .keys()
+            .all(|status| status == "200" || status == "429")
+    );
+    let total: u64 = counts.values().map(|count| count.as_u64().unwrap()).sum();
+    assert_eq!(total, 10);
+}
+
+#[test]
+fn test_connection_refused_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("CCC"))
+        .stdout(predicate::str::contains("Connection refused: 3"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["failed"], 3);
+    assert_eq!(report["connection_refused"], 3);
+    assert_eq!(report["other_errors"], 0);
+}
+
+#[test]
+fn test_invalid_expect_status() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--expect-status", "200,abc"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid status code \"abc\""));
+}
+
+#[test]
+fn test_expect_status_conflicts_with_fail_on_non_2xx() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--expect-status",
+        "200",
+        "--fail-on-non-2xx",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_config_expect_status_conflicts_with_fail_on_non_2xx() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nfail_on_non_2xx: true\nexpect_status: [200, 201]\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "expect_status can't be combined with fail_on_non_2xx",
+    ));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_fail_on_non_2xx() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/500",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--fail-on-non-2xx",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Unexpected status: 3"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["successful"], 0);
+    assert_eq!(report["unexpected_status"], 3);
+    assert_eq!(report["status_codes"]["500"], 3);
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_expect_status_list() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/status/204",
+        "-n",
+        "2",
+        "--expect-status",
+        "200,204",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 2"));
+}
+
+#[test]
+fn test_invalid_assert_body_regex() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--assert-body-regex", "(unclosed"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid regular expression"));
+}
+
+#[test]
+fn test_config_invalid_assert_body_regex() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nassert_body_regex:\n  - \"[a-\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid regular expression"));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_body_assertions() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/get",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--assert-body-regex",
+        "\"url\":\\s*\"https://httpbin.org/get\"",
+        "--assert-body-contains",
+        "not in the body",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Assertion failures: 3"))
+        .stdout(predicate::str::contains(
+            "body contains \"not in the body\": 3 failed",
+        ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["successful"], 0);
+    assert_eq!(report["assertion_failures"], 3);
+    // Contains checks come before regex checks
+    assert_eq!(report["assertions"][0]["failures"], 3);
+    assert_eq!(report["assertions"][1]["failures"], 0);
+}
+
+#[test]
+fn test_invalid_assert_header() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--assert-header", "Bad Name: x"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid header name"));
+}
+
+#[test]
+fn test_assert_header_regex_needs_a_value() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--assert-header-regex",
+        "Content-Type",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected \"Name: regex\""));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_header_assertions() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/response-headers?Cache-Control=no-store",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "--assert-header",
+        "cache-control: no-store",
+        "--assert-header-regex",
+        "Content-Type: ^application/json",
+        "--assert-header",
+        "X-Not-Sent",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "header x-not-sent present: 2 failed",
+    ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["assertion_failures"], 2);
+    assert_eq!(report["assertions"][0]["failures"], 0);
+}
+
+#[test]
+fn test_assert_min_size_larger_than_max() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--assert-min-size",
+        "2KB",
+        "--assert-max-size",
+        "1KB",
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--assert-min-size can't be larger than --assert-max-size",
+    ));
+}
+
+#[test]
+#[ignore] // Ignored by default as it makes real HTTP requests
+fn test_size_assertions() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "https://httpbin.org/bytes/2048",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "--assert-min-size",
+        "1KB",
+        "--assert-max-size",
+        "1.5KB",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("size at most 1.50 KiB: 2 failed"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["assertion_failures"], 2);
+    assert_eq!(report["assertions"][0]["failures"], 0);
+}
+
+#[test]
+fn test_invalid_threshold() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--threshold", "p90<250ms"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown threshold metric \"p90\""));
+}
+
+#[test]
+fn test_threshold_breach_exits_non_zero() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--threshold",
+        "error_rate<1%",
+        "--threshold",
+        "p99<10s",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "✗ error_rate<1% (actual: 100.00%)",
+        ))
+        .stdout(predicate::str::contains("✓ p99<10s"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["thresholds_passed"], false);
+    assert_eq!(report["thresholds"][0]["threshold"], "error_rate<1%");
+    assert_eq!(report["thresholds"][0]["passed"], false);
+    assert_eq!(report["thresholds"][1]["passed"], true);
+}
+
+#[test]
+fn test_run_cli_threshold_overrides_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 2\nconcurrency: 1\nthresholds:\n  - error_rate<1%\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--config",
+        config_path.to_str().unwrap(),
+        "--threshold",
+        "error_rate<=100%",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("✓ error_rate<=100%"))
+        .stdout(predicate::str::contains("error_rate<1%").not());
+}
+
+#[test]
+fn test_invalid_abort_on_error_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--abort-on-error-rate", "150%"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("must be between 0% and 100%"));
+}
+
+#[test]
+fn test_abort_window_requires_error_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--abort-window", "20"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--abort-on-error-rate"));
+}
+
+#[test]
+fn test_abort_on_error_rate() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1000",
+        "-c",
+        "1",
+        "--abort-on-error-rate",
+        "50%",
+        "--abort-window",
+        "10",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "Aborted: error rate 100.00% over the last 10 requests went above 50.00%",
+    ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    // The aggregator judges each request after its worker is free again, so
+    // a few more may start before the run stops
+    let total: u64 = report["total_requests"].as_u64().unwrap();
+    assert!((10..20).contains(&total), "{} requests", total);
+    assert_eq!(report["aborted"]["window"], 10);
+    assert_eq!(report["aborted"]["error_rate"], 1.0);
+}
+
+#[test]
+fn test_checks_in_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "2",
+        "-c",
+        "1",
+        "--assert-body-contains",
+        "ok",
+        "--threshold",
+        "error_rate<=100%",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let checks = report["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0]["name"], "body contains \"ok\"");
+    assert_eq!(checks[0]["kind"], "assertion");
+    assert_eq!(checks[0]["passes"], 0);
+    assert_eq!(checks[0]["failures"], 0);
+    assert_eq!(checks[1]["name"], "error_rate<=100%");
+    assert_eq!(checks[1]["kind"], "threshold");
+    assert_eq!(checks[1]["passes"], 1);
+    assert_eq!(checks[1]["pass_rate"], 1.0);
+}
+
+#[test]
+fn test_baseline_regression() {
+    let temp_dir = TempDir::new().unwrap();
+    let baseline_path = temp_dir.path().join("baseline.json");
+    let output_path = temp_dir.path().join("report.json");
+
+    // A baseline in which none of the requests failed
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "3", "-o"])
+        .arg(&baseline_path);
+    cmd.assert().success();
+    let mut baseline: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&baseline_path).unwrap()).unwrap();
+    baseline["failed"] = serde_json::json!(0);
+    fs::write(&baseline_path, baseline.to_string()).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "3", "-c", "1", "--baseline"])
+        .arg(&baseline_path)
+        .arg("-o")
+        .arg(&output_path);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "✗ error_rate:1% (100.00% vs 0.00%, +100.00 pts)",
+        ))
+        .stdout(predicate::str::contains("Baseline ("));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["baseline"]["passed"], false);
+    let checks = report["baseline"]["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 5);
+    assert_eq!(checks[4]["max_regression"], "error_rate:1%");
+    assert_eq!(checks[4]["change"], 1.0);
+    assert_eq!(report["checks"][4]["kind"], "baseline");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "3", "--baseline"])
+        .arg(&baseline_path)
+        .args(["--max-regression", "error_rate:100%"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("✓ error_rate:100%"))
+        .stdout(predicate::str::contains("p95:10%").not());
+}
+
+#[test]
+fn test_baseline_unreadable() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--baseline",
+        "/nonexistent/baseline.json",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read baseline"))
+        .stdout(predicate::str::contains("Total requests").not());
+}
+
+/// A report saved before timeouts, TTFB and phases were reported
+const OLD_REPORT: &str = r#"{
+  "url": "http://localhost:8080",
+  "date": "2025-10-02T18:09:29.794111Z",
+  "total_requests": 100,
+  "concurrency": 10,
+  "total_duration_secs": 0.098184333,
+  "successful": 100,
+  "failed": 0,
+  "requests_per_sec": 1018.4924309665575,
+  "latency": {
+    "avg_ms": 39.737232999999996,
+    "p50_ms": 60.41875,
+    "p95_ms": 98.088458,
+    "p99_ms": 98.088458,
+    "min_ms": 0.732875,
+    "max_ms": 98.088458
+  }
+}"#;
+
+#[test]
+fn test_baseline_from_old_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let baseline_path = temp_dir.path().join("baseline.json");
+    fs::write(&baseline_path, OLD_REPORT).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "3", "--baseline"])
+        .arg(&baseline_path)
+        .args(["--max-regression", "error_rate:100%"]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "✓ error_rate:100% (100.00% vs 0.00%",
+    ));
+}
+
+#[test]
+fn test_invalid_max_regression() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--baseline",
+        "baseline.json",
+        "--max-regression",
+        "p95",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected \"METRIC:PERCENT\""));
+}
+
+#[test]
+fn test_warmup_conflicts_with_stages() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--stage",
+        "1s:1",
+        "--warmup-requests",
+        "5",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_warmup_requests_not_counted() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "4",
+        "-c",
+        "1",
+        "--warmup-requests",
+        "3",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Warm-up: 3 requests (not measured)",
+        ))
+        .stdout(predicate::str::contains("Total requests: 4\n"))
+        .stdout(predicate::str::contains(
+            "Warm-up requests (not counted): 3",
+        ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["total_requests"], 4);
+    assert_eq!(report["failed"], 4);
+    assert_eq!(report["warmup_requests"], 3);
+}
+
+#[test]
+fn test_run_config_warmup() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 2\nconcurrency: 1\nwarmup_requests: 2\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Total requests: 2\n"))
+        .stdout(predicate::str::contains(
+            "Warm-up requests (not counted): 2",
+        ));
+}
+
+#[test]
+fn test_rate_reports_corrected_latency() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "5",
+        "-c",
+        "1",
+        "-q",
+        "50",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Latency from scheduled start (corrected for coordinated omission):",
+    ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let corrected = report["corrected_latency"]["p50_ms"].as_f64().unwrap();
+    assert!(corrected >= report["latency"]["min_ms"].as_f64().unwrap());
+}
+
+#[test]
+fn test_unpaced_run_has_no_corrected_latency() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("coordinated omission").not());
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert!(report.get("corrected_latency").is_none());
+}
+
+#[test]
+fn test_arrival_requires_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "1", "--arrival", "poisson"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--arrival needs a request rate"));
+}
+
+#[test]
+fn test_poisson_arrival() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "10",
+        "-c",
+        "5",
+        "-q",
+        "100",
+        "--arrival",
+        "poisson",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Arrivals: poisson"))
+        .stdout(predicate::str::contains("Total requests: 10"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["arrival"], "poisson");
+}
+
+#[test]
+fn test_max_concurrency_requires_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--max-concurrency", "100"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--rate"));
+}
+
+#[test]
+fn test_max_concurrency_below_concurrency() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-q",
+        "10",
+        "-c",
+        "20",
+        "--max-concurrency",
+        "5",
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--max-concurrency can't be lower than --concurrency",
+    ));
+}
+
+#[test]
+fn test_max_concurrency_drops_requests() {
+    // Accepts connections into the backlog but never answers them
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "20",
+        "-q",
+        "100",
+        "-c",
+        "1",
+        "--max-concurrency",
+        "2",
+        "--timeout",
+        "1s",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Peak concurrency: 2 of 2"))
+        .stdout(predicate::str::contains("Dropped: 18 requests"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["total_requests"], 2);
+    assert_eq!(report["capacity"]["dropped"], 18);
+    assert_eq!(report["capacity"]["peak_concurrency"], 2);
+    drop(listener);
+}
+
+#[test]
+fn test_shape_requires_duration() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--shape", "sine:period=1s,max=5"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--duration"));
+}
+
+#[test]
+fn test_invalid_shape() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "1s",
+        "--shape",
+        "square:period=1s,max=5",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown shape \"square\""));
+}
+
+#[test]
+fn test_shape_needs_period() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "1s",
+        "--shape",
+        "sawtooth:max=5",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("shape needs a period"));
+}
+
+#[test]
+fn test_step_shape() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "1s",
+        "--stage-unit",
+        "rate",
+        "--shape",
+        "step:period=500ms,min=5,step=5,max=10",
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Shape: step +5 between 5 and 10 every 0.50s for 1.00s (rate targets)",
+    ));
+}
+
+#[test]
+fn test_run_config_shape() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nduration: 1s\nshape: sine:period=1s,min=1,max=2\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Shape: sine between 1 and 2 every 1.00s for 1.00s (concurrency targets)",
+    ));
+}
+
+#[test]
+fn test_run_requires_config_or_preset() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "http://127.0.0.1:1"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--config <FILE>"));
+}
+
+#[test]
+fn test_spike_preset_conflicts_with_stages() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--preset",
+        "spike",
+        "http://127.0.0.1:1",
+        "--stage",
+        "1s:5",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_spike_preset() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--preset",
+        "spike",
+        "http://127.0.0.1:1",
+        "-q",
+        "20",
+        "--spike-factor",
+        "3",
+        "-z",
+        "1500ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Stages: 6 over 1.50s (rate targets)",
+        ))
+        .stdout(predicate::str::contains("Phases (spike preset):"))
+        .stdout(predicate::str::contains("  recovery: "));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let phases = report["preset_phases"].as_array().unwrap();
+    assert_eq!(phases.len(), 3);
+    assert_eq!(phases[0]["name"], "baseline");
+    assert_eq!(phases[0]["p95_delta_ms"], 0.0);
+    assert_eq!(phases[1]["name"], "spike");
+    assert_eq!(report["stages"][3]["target"], 60.0);
+}
+
+#[test]
+fn test_bucket_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "1s",
+        "-q",
+        "20",
+        "--bucket",
+        "400ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Over time (0.40s buckets):"))
+        .stdout(predicate::str::contains("  0.00s-0.40s: "))
+        .stdout(predicate::str::contains("Drift: p95 "));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let buckets = report["buckets"].as_array().unwrap();
+    assert_eq!(buckets.len(), 3);
+    assert_eq!(buckets[1]["start_secs"], 0.4);
+    assert!(buckets[2]["duration_secs"].as_f64().unwrap() < 0.4);
+    let requests: u64 = buckets
+        .iter()
+        .map(|b| b["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(requests, report["total_requests"].as_u64().unwrap());
+}
+
+#[test]
+fn test_bucket_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    let output_path = temp_dir.path().join("report.json");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 5\nbucket: 1m\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--config",
+        config_path.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Over time (60.00s buckets):"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["buckets"][0]["requests"], 5);
+}
+
+#[test]
+fn test_autotune_growth_must_increase() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["autotune", "http://127.0.0.1:1", "--growth", "1"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("must be a number above 1"));
+}
+
+#[test]
+fn test_autotune_invalid_plateau() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["autotune", "http://127.0.0.1:1", "--plateau", "abc"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid percentage \"abc\""));
+}
+
+#[test]
+fn test_autotune_stops_on_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "autotune",
+        "http://127.0.0.1:1",
+        "--step-duration",
+        "200ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Stopped: error rate went over the limit",
+        ))
+        .stdout(predicate::str::contains("Knee: none"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["stop_reason"], "errors");
+    assert_eq!(report["steps"].as_array().unwrap().len(), 1);
+    assert!(report.get("knee").is_none());
+}
+
+#[test]
+fn test_autotune_finds_knee() {
+    use std::io::{Read, Write};
+
+    // Answers every request on a connection with an empty 200
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "autotune",
+        url.as_str(),
+        "--step-duration",
+        "200ms",
+        "--max",
+        "4",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Knee: concurrency "));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let steps = report["steps"].as_array().unwrap();
+    assert_eq!(steps[0]["concurrency"], 1);
+    assert!(steps.len() <= 3);
+    assert_eq!(report["knee"]["failed"], 0);
+}
+
+#[test]
+fn test_autotune_slo_requires_find_max_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["autotune", "http://127.0.0.1:1", "--slo", "p99<200ms"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--find-max-rate"));
+}
+
+#[test]
+fn test_find_max_rate_fails_at_min_rate() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "autotune",
+        "http://127.0.0.1:1",
+        "--find-max-rate",
+        "--slo",
+        "p99<200ms",
+        "--step-duration",
+        "200ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "Max rate: none; the SLO failed at --min-rate",
+    ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["slo"][0], "p99<200ms");
+    assert_eq!(report["rounds"].as_array().unwrap().len(), 1);
+    assert_eq!(report["rounds"][0]["passed"], false);
+    assert!(report.get("max_rate").is_none());
+}
+
+#[test]
+fn test_find_max_rate_up_to_max_rate() {
+    use std::io::{Read, Write};
+
+    // Answers every request on a connection with an empty 200
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "autotune",
+        url.as_str(),
+        "--find-max-rate",
+        "--slo",
+        "p99<1s",
+        "--min-rate",
+        "10",
+        "--max-rate",
+        "40",
+        "--step-duration",
+        "500ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Max rate: 40.00 req/s"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let rounds = report["rounds"].as_array().unwrap();
+    assert_eq!(rounds.len(), 3);
+    assert_eq!(rounds[1]["target_rps"], 20.0);
+    assert_eq!(report["max_rate"], 40.0);
+}
+
+#[test]
+fn test_saturation_for_staged_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--stage",
+        "1ms:20",
+        "--stage",
+        "2s:20",
+        "--stage-unit",
+        "rate",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    // Every request fails, so the service is saturated from the start
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Saturation (1.00s windows):"))
+        .stdout(predicate::str::contains(
+            "Saturation point: 0.00 req/s (p95 doubled or errors passed 1% at 0.00s)",
+        ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["saturation"]["window_secs"], 1.0);
+    assert_eq!(report["saturation"]["saturated_at_secs"], 0.0);
+    assert!(report.get("buckets").is_none());
+}
+
+#[test]
+fn test_no_saturation_without_stages() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "20",
+        "--bucket",
+        "1s",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Saturation").not());
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert!(report.get("saturation").is_none());
+}
+
+#[test]
+fn test_jitter_requires_think_time() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--jitter", "100ms"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--think-time <DURATION>"));
+}
+
+#[test]
+fn test_think_time_paces_workers() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-c",
+        "1",
+        "-z",
+        "1s",
+        "--think-time",
+        "200ms",
+        "--jitter",
+        "50ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Think time: 0.20s ± 0.05s between iterations",
+    ));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let requests = report["total_requests"].as_u64().unwrap();
+    assert!((4..=7).contains(&requests), "{} requests", requests);
+    assert_eq!(report["think_time_ms"], 200.0);
+    assert_eq!(report["jitter_ms"], 50.0);
+    // Pauses aren't counted as latency
+    assert!(report["latency"]["max_ms"].as_f64().unwrap() < 100.0);
+}
+
+#[test]
+fn test_think_time_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 3\nconcurrency: 1\nthink_time: 100ms\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Think time: 0.10s between iterations",
+        ))
+        .stdout(predicate::str::contains("Total requests: 3"));
+}
+
+#[test]
+fn test_burst_conflicts_with_rate() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--burst", "10", "-q", "10"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_burst_sends_requests_in_bursts() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "30",
+        "--burst",
+        "10",
+        "--burst-interval",
+        "300ms",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Concurrency: 10"))
+        .stdout(predicate::str::contains("Bursts: 10 requests every 0.30s"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["total_requests"], 30);
+    assert_eq!(report["burst"]["size"], 10);
+    // Three bursts, the last starting 600ms in
+    let duration = report["total_duration_secs"].as_f64().unwrap();
+    assert!((0.6..0.9).contains(&duration), "{}s", duration);
+}
+
+#[test]
+fn test_burst_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("scenario.yaml");
+    fs::write(
+        &config_path,
+        "url: http://127.0.0.1:1\nrequests: 4\nburst: 2\nburst_interval: 100ms\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["run", "--config", config_path.to_str().unwrap()]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Bursts: 2 requests every 0.10s"));
+}
+
+#[test]
+fn test_timeseries_in_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "2s",
+        "-q",
+        "20",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let timeseries = report["timeseries"].as_array().unwrap();
+    assert!((2..=3).contains(&timeseries.len()));
+    assert_eq!(timeseries[1]["second"], 1);
+    // Requests count in the second they finished, not when they were collected
+    let first = timeseries[0]["requests"].as_u64().unwrap();
+    assert!((18..=22).contains(&first), "{} requests", first);
+    assert_eq!(timeseries[0]["errors"], timeseries[0]["requests"]);
+    let requests: u64 = timeseries
+        .iter()
+        .map(|point| point["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(requests, report["total_requests"].as_u64().unwrap());
+}
+
+#[test]
+fn test_hdr_out() {
+    let temp_dir = TempDir::new().unwrap();
+    let hdr_path = temp_dir.path().join("latencies.hlog");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-z",
+        "2s",
+        "-q",
+        "20",
+        "--hdr-out",
+        hdr_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("✓ HDR histogram log saved to:"));
+
+    let log = fs::read_to_string(&hdr_path).unwrap();
+    assert!(log.contains("#[BaseTime: "));
+    assert!(log.contains("#[MaxValueDivisor: 1000000.000]"));
+    let intervals: Vec<&str> = log.lines().filter(|line| !line.starts_with('#')).collect();
+    assert!((2..=3).contains(&intervals.len()));
+    assert!(intervals[0].starts_with("0.000,1.000,"));
+    assert!(intervals[1].starts_with("1.000,1.000,"));
+    assert!(intervals.iter().all(|line| line.contains(",HISTF")));
+}
+
+#[test]
+fn test_percentiles() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "50",
+        "--percentiles",
+        "50,90,99.9",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("  p90: "))
+        .stdout(predicate::str::contains("  p99.9: "))
+        .stdout(predicate::str::contains("  p95: ").not());
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let percentiles = report["latency"]["percentiles"].as_array().unwrap();
+    assert_eq!(percentiles.len(), 3);
+    assert_eq!(percentiles[2]["percentile"], 99.9);
+    assert!(percentiles[2]["ms"].as_f64().unwrap() >= percentiles[1]["ms"].as_f64().unwrap());
+    // The default percentiles are still reported for existing consumers
+    assert!(report["latency"]["p95_ms"].is_number());
+}
+
+#[test]
+fn test_percentiles_out_of_range() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "1", "--percentiles", "50,101"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid percentile \"101\""));
+}
+
+#[test]
+fn test_latency_stats_past_exact_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    // More requests than are kept exactly, so the histogram reports them
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "6000",
+        "-c",
+        "20",
+        "--percentiles",
+        "50,99.9",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    let latency = &report["latency"];
+    let ms = |key: &str| latency[key].as_f64().unwrap();
+    assert!(ms("min_ms") > 0.0);
+    assert!(ms("min_ms") <= ms("p50_ms"));
+    assert!(ms("p50_ms") <= ms("p95_ms"));
+    assert!(ms("p95_ms") <= ms("p99_ms"));
+    assert!(ms("p99_ms") <= ms("max_ms"));
+    assert!(ms("min_ms") <= ms("avg_ms") && ms("avg_ms") <= ms("max_ms"));
+    assert_eq!(latency["percentiles"][0]["ms"], latency["p50_ms"]);
+    let requests: u64 = report["timeseries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| point["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(requests, 6000);
+}
+
+#[test]
+fn test_concurrency_held_steady() {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Answers every request after 20ms, noting the most it served at once
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (server_active, server_peak) = (Arc::clone(&active), Arc::clone(&peak));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (active, peak) = (Arc::clone(&server_active), Arc::clone(&server_peak));
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    if stream
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([url.as_str(), "-n", "60", "-c", "3"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Total requests: 60"))
+        .stdout(predicate::str::contains("Successful: 60"));
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_threads_flag() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "20",
+        "-c",
+        "4",
+        "--threads",
+        "2",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Total requests: 20"));
+}
+
+#[test]
+fn test_threads_with_subcommand() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "run",
+        "--preset",
+        "spike",
+        "http://127.0.0.1:1",
+        "-q",
+        "20",
+        "-z",
+        "300ms",
+        "--threads",
+        "1",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Phases (spike preset):"));
+}
+
+#[test]
+fn test_zero_threads() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "1", "--threads", "0"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--threads must be above zero"));
+}
+
+#[test]
+fn test_load_generator_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "20",
+        "--threads",
+        "2",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Load generator:"))
+        .stdout(predicate::str::contains("of 2 threads"));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let client = &json["client"];
+    assert_eq!(client["runtime_threads"], 2);
+    assert!(client["cores"].as_u64().unwrap() >= 1);
+    assert!(client.get("queue_depth_peak").is_some());
+}
+
+#[test]
+fn test_prewarm() {
+    use std::io::{Read, Write};
+
+    // Answers every request on a kept-alive connection
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([url.as_str(), "-n", "40", "-c", "4", "--prewarm"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Pre-warmed 4 connections"))
+        .stdout(predicate::str::contains("Connections: 0 new, 40 requests"));
+}
+
+#[test]
+fn test_prewarm_without_keepalive() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--prewarm",
+        "--disable-keepalive",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+/// Serves a 64KB body in two writes, splitting "marker-text" between them
+fn split_body_server() -> String {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                let mut first = vec![b'x'; 32 * 1024];
+                first.extend_from_slice(b"marker-");
+                let mut second = b"text".to_vec();
+                second.extend(vec![b'y'; 64 * 1024 - first.len() - 4]);
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    let head = "HTTP/1.1 200 OK\r\nContent-Length: 65536\r\n\r\n";
+                    if stream.write_all(head.as_bytes()).is_err()
+                        || stream.write_all(&first).is_err()
+                        || stream.flush().is_err()
+                    {
+                        break;
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                    if stream.write_all(&second).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    url
+}
+
+#[test]
+fn test_streamed_body_assertions() {
+    let url = split_body_server();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "10",
+        "--assert-body-contains",
+        "marker-text",
+        "--assert-body-regex",
+        "mar[k]er-te?xt",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 10"));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "10",
+        "--assert-body-contains",
+        "missing-text",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Assertion failures: 10"));
+}
+
+#[test]
+fn test_buffer_bodies() {
+    let url = split_body_server();
+
+    // Anchored to the whole body, which only a buffered body is checked as
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "5",
+        "--assert-body-regex",
+        "^x+marker-texty+$",
+        "--buffer-bodies",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Successful: 5"));
+}
+
+#[test]
+fn test_ui_without_terminal() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "5", "--ui"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("--ui needs a terminal"))
+        .stdout(predicate::str::contains("CCCCC"))
+        .stdout(predicate::str::contains("Total requests: 5"));
+}
+
+#[test]
+fn test_latency_distribution() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "30",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Latency distribution:"))
+        .stdout(predicate::str::contains("ms [").and(predicate::str::contains("] |")));
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let slices = json["latency_distribution"].as_array().unwrap();
+    assert!(!slices.is_empty() && slices.len() <= 10);
+    let requests: u64 = slices
+        .iter()
+        .map(|slice| slice["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(requests, 30);
+}
+
+#[test]
+fn test_rps_sparkline() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-z", "2500ms", "-c", "1", "-q", "20"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Requests/sec over time (1s per bar",
+        ))
+        .stdout(predicate::str::is_match("\n  [▁▂▃▄▅▆▇█]{2}\n").unwrap());
+}
+
+#[test]
+fn test_quiet() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "5", "--quiet"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Results:"))
+        .stdout(predicate::str::contains("Total requests: 5"))
+        .stdout(predicate::str::contains("Load testing").not())
+        .stdout(predicate::str::contains("CCCCC").not());
+}
+
+#[test]
+fn test_quiet_with_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "5",
+        "--quiet",
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    let content = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["total_requests"], 5);
+}
+
+#[test]
+fn test_verbose() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1/items", "-n", "2", "-v"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "GET http://127.0.0.1:1/items → connection refused in",
+        ))
+        .stderr(predicate::str::contains("  > ").not());
+}
+
+#[test]
+fn test_verbose_headers() {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nX-Served-By: test\r\nContent-Length: 2\r\n\r\nok")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([url.as_str(), "-n", "1", "-vv", "--bearer", "secret-token"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "GET {} → 200, 2 B in",
+            url
+        )))
+        .stderr(predicate::str::contains("  > authorization: [redacted]"))
+        .stderr(predicate::str::contains("  < x-served-by: test"))
+        .stderr(predicate::str::contains("secret-token").not());
+}
+
+#[test]
+fn test_no_color_when_piped() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "5",
+        "--threshold",
+        "error_rate<1%",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("  ✗ error_rate<1%"))
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_no_color() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "5", "--no-color"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Failed: 5"))
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_request_log() {
+    let temp_dir = TempDir::new().unwrap();
+    let log_path = temp_dir.path().join("requests.ndjson");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "6",
+        "-c",
+        "2",
+        "--request-log",
+        log_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Request log saved to"));
+
+    let content = fs::read_to_string(&log_path).unwrap();
+    let lines: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 6);
+    for line in &lines {
+        assert!(line["timestamp"].is_string());
+        assert!(line["status"].is_null());
+        assert!(line["latency_ms"].is_number());
+        assert!(line["bytes"].is_null());
+        assert_eq!(line["error"], "connection_refused");
+        assert!(line["worker"].as_u64().unwrap() < 2);
+    }
+}
+
+#[test]
+fn test_aggregator_keeps_up_with_small_queue() {
+    use std::io::{Read, Write};
+
+    // Answers every request on a connection with an empty 200
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let output_path = temp_dir.path().join("report.json");
+    let log_path = temp_dir.path().join("requests.ndjson");
+
+    // A queue of one makes every worker wait for the aggregator
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        url.as_str(),
+        "-n",
+        "2000",
+        "-c",
+        "64",
+        "--aggregator-capacity",
+        "1",
+        "--request-log",
+        log_path.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_eq!(report["total_requests"], 2000);
+    assert_eq!(report["successful"], 2000);
+    assert_eq!(report["status_codes"]["200"], 2000);
+    let seconds: u64 = report["timeseries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| point["requests"].as_u64().unwrap())
+        .sum();
+    assert_eq!(seconds, 2000);
+
+    // Every request is logged, each worker's in the order it sent them
+    let content = fs::read_to_string(&log_path).unwrap();
+    let mut sent: Vec<Vec<chrono::DateTime<chrono::Utc>>> = vec![Vec::new(); 64];
+    for line in content.lines() {
+        let line: serde_json::Value = serde_json::from_str(line).unwrap();
+        let worker = line["worker"].as_u64().unwrap() as usize;
+        sent[worker].push(line["timestamp"].as_str().unwrap().parse().unwrap());
+    }
+    assert_eq!(sent.iter().map(Vec::len).sum::<usize>(), 2000);
+    for timestamps in &sent {
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
+
+#[test]
+fn test_request_log_unwritable() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--request-log",
+        "/nonexistent/requests.ndjson",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to create request log"));
+}
+
+#[test]
+fn test_csv_export() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("requests.csv");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "4",
+        "--csv",
+        csv_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let content = fs::read_to_string(&csv_path).unwrap();
+    let mut lines = content.lines();
+    assert_eq!(
+        lines.next(),
+        Some("timestamp,worker,status,latency_ms,bytes,error")
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 4);
+    for row in &rows {
+        assert_eq!(row.len(), 6);
+        assert_eq!(row[2], "");
+        assert!(row[3].parse::<f64>().is_ok());
+        assert_eq!(row[5], "connection_refused");
+    }
+}
+
+#[test]
+fn test_parquet_export() {
+    let temp_dir = TempDir::new().unwrap();
+    let parquet_path = temp_dir.path().join("requests.parquet");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "10",
+        "--parquet",
+        parquet_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Request log saved to"));
+
+    // A Parquet file starts and ends with its magic, the footer's length
+    // just before the trailing one
+    let content = fs::read(&parquet_path).unwrap();
+    assert_eq!(&content[..4], b"PAR1");
+    assert_eq!(&content[content.len() - 4..], b"PAR1");
+    let footer_len = u32::from_le_bytes(
+        content[content.len() - 8..content.len() - 4]
+            .try_into()
+            .unwrap(),
+    );
+    assert!((footer_len as usize) < content.len() - 12);
+    let footer = &content[content.len() - 8 - footer_len as usize..content.len() - 8];
+    for column in [
+        "timestamp",
+        "worker",
+        "status",
+        "latency_ms",
+        "bytes",
+        "error",
+    ] {
+        assert!(
+            footer
+                .windows(column.len())
+                .any(|window| window == column.as_bytes())
+        );
+    }
+}
+
+#[test]
+fn test_parquet_reads_back() {
+    use std::io::{Read, Write};
+
+    // Answers every request on a connection with "ok"
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    if read == 0
+                        || stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let temp_dir = TempDir::new().unwrap();
+    let url_path = temp_dir.path().join("urls.txt");
+    let parquet_path = temp_dir.path().join("requests.parquet");
+    // Every other request is refused, so the optional columns alternate
+    // between values and nulls
+    fs::write(&url_path, format!("{}\nhttp://127.0.0.1:1/\n", url)).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "--url-file",
+        url_path.to_str().unwrap(),
+        "-n",
+        "10",
+        "-c",
+        "1",
+        "--parquet",
+        parquet_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let content = fs::read(&parquet_path).unwrap();
+    let footer_len = u32::from_le_bytes(
+        content[content.len() - 8..content.len() - 4]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    let footer_start = content.len() - 8 - footer_len;
+    let mut footer = Thrift::reader(&content[footer_start..content.len() - 8]);
+    let meta = footer.read_struct();
+    assert_eq!(footer.position, footer_len);
+
+    assert_eq!(meta[&1].int(), 1);
+    assert_eq!(meta[&3].int(), 10);
+    let schema = meta[&2].list();
+    assert_eq!(schema[0].fields()[&5].int(), 6);
+    let names: Vec<&[u8]> = schema[1..]
+        .iter()
+        .map(|element| element.fields()[&4].binary())
+        .collect();
+    let expected: [&[u8]; 6] = [
+        b"timestamp",
+        b"worker",
+        b"status",
+        b"latency_ms",
+        b"bytes",
+        b"error",
+    ];
+    assert_eq!(names, expected);
+
+    let groups = meta[&4].list();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].fields()[&3].int(), 10);
+    let chunks = groups[0].fields()[&1].list();
+    assert_eq!(chunks.len(), 6);
+
+    // The column chunks follow the leading magic one after another, each a
+    // page header and a zstd-compressed page, up to the footer
+    let mut offset = 4;
+    let mut columns: Vec<(Vec<bool>, Vec<u8>)> = Vec::new();
+    for (chunk, element) in chunks.iter().zip(&schema[1..]) {
+        let chunk = chunk.fields();
+        let column = chunk[&3].fields();
+        assert_eq!(chunk[&2].int(), offset as i64);
+        assert_eq!(column[&9].int(), offset as i64);
+        assert_eq!(column[&1].int(), element.fields()[&1].int());
+        assert_eq!(column[&4].int(), 6);
+        assert_eq!(column[&5].int(), 10);
+
+        let mut reader = Thrift::reader(&content[offset..]);
+        let header = reader.read_struct();
+        let header_len = reader.position;
+        let uncompressed = header[&2].int() as usize;
+        let compressed = header[&3].int() as usize;
+        assert_eq!(header[&1].int(), 0);
+        assert_eq!(header[&5].fields()[&1].int(), 10);
+        assert_eq!(column[&6].int(), (header_len + uncompressed) as i64);
+        assert_eq!(column[&7].int(), (header_len + compressed) as i64);
+        let page_start = offset + header_len;
+        let page =
+            zstd::bulk::decompress(&content[page_start..page_start + compressed], uncompressed)
+                .unwrap();
+        assert_eq!(page.len(), uncompressed);
+
+        // Optional columns start with their definition levels
+        let optional = element.fields()[&3].int() == 1;
+        let (defined, values) = if optional {
+            let levels_len = u32::from_le_bytes(page[..4].try_into().unwrap()) as usize;
+            let defined = rle_levels(&page[4..4 + levels_len], 10);
+            (defined, page[4 + levels_len..].to_vec())
+        } else {
+            (vec![true; 10], page)
+        };
+        columns.push((defined, values));
+        offset += header_len + compressed;
+    }
+    assert_eq!(offset, footer_start);
+
+    let (_, timestamps) = &columns[0];
+    let (_, workers) = &columns[1];
+    let (status_defined, statuses) = &columns[2];
+    let (_, latencies) = &columns[3];
+    let (bytes_defined, bytes) = &columns[4];
+    let (error_defined, errors) = &columns[5];
+    let answered: Vec<bool> = (0..10).map(|row| row % 2 == 0).collect();
+    assert_eq!(status_defined, &answered);
+    assert_eq!(bytes_defined, &answered);
+    let refused: Vec<bool> = answered.iter().map(|answered| !answered).collect();
+    assert_eq!(error_defined, &refused);
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as i64;
+    for timestamp in timestamps.chunks(8) {
+        let timestamp = i64::from_le_bytes(timestamp.try_into().unwrap());
+        assert!(timestamp <= now && timestamp > now - 60_000_000);
+    }
+    assert!(workers.chunks(4).all(|worker| worker == 0i32.to_le_bytes()));
+    assert_eq!(statuses.len(), 5 * 4);
+    assert!(
+        statuses
+            .chunks(4)
+            .all(|status| status == 200i32.to_le_bytes())
+    );
+    assert_eq!(latencies.len(), 10 * 8);
+    assert!(latencies.chunks(8).all(|latency| {
+        let latency = f64::from_le_bytes(latency.try_into().unwrap());
+        latency > 0.0 && latency < 60_000.0
+    }));
+    assert_eq!(bytes.len(), 5 * 8);
+    assert!(bytes.chunks(8).all(|bytes| bytes == 2i64.to_le_bytes()));
+    let error = b"connection_refused";
+    let mut expected_errors: Vec<u8> = Vec::new();
+    for _ in 0..5 {
+        expected_errors.extend_from_slice(&(error.len() as u32).to_le_bytes());
+        expected_errors.extend_from_slice(error);
+    }
+    assert_eq!(errors, &expected_errors);
+}
+
+/// Definition levels one bit wide, as RLE runs
+fn rle_levels(mut encoded: &[u8], rows: usize) -> Vec<bool> {
+    let mut defined: Vec<bool> = Vec::new();
+    while defined.len() < rows {
+        let mut reader = Thrift::reader(encoded);
+        let header = reader.varint();
+        assert_eq!(header & 1, 0, "expected an RLE run");
+        let value = encoded[reader.position];
+        defined.extend(std::iter::repeat_n(value == 1, (header >> 1) as usize));
+        encoded = &encoded[reader.position + 1..];
+    }
+    assert!(encoded.is_empty());
+    defined
+}
+
+/// A value read with Thrift's compact protocol, which Parquet's metadata is
+/// written in
+#[derive(Debug)]
+enum Thrift {
+    Int(i64),
+    Binary(Vec<u8>),
+    List(Vec<Thrift>),
+    Struct(std::collections::BTreeMap<i16, Thrift>),
+}
+
+struct ThriftReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Thrift {
+    fn reader(bytes: &[u8]) -> ThriftReader<'_> {
+        ThriftReader { bytes, position: 0 }
+    }
+
+    fn int(&self) -> i64 {
+        match self {
+            Thrift::Int(value) => *value,
+            other => panic!("expected an integer, got {:?}", other),
+        }
+    }
+
+    fn binary(&self) -> &[u8] {
+        match self {
+            Thrift::Binary(value) => value,
+            other => panic!("expected binary, got {:?}", other),
+        }
+    }
+
+    fn list(&self) -> &[Thrift] {
+        match self {
+            Thrift::List(value) => value,
+            other => panic!("expected a list, got {:?}", other),
+        }
+    }
+
+    fn fields(&self) -> &std::collections::BTreeMap<i16, Thrift> {
+        match self {
+            Thrift::Struct(value) => value,
+            other => panic!("expected a struct, got {:?}", other),
+        }
+    }
+}
+
+impl ThriftReader<'_> {
+    fn byte(&mut self) -> u8 {
+        let byte = self.bytes[self.position];
+        self.position += 1;
+        byte
+    }
+
+    fn varint(&mut self) -> u64 {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte();
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn zigzag(&mut self) -> i64 {
+        let value = self.varint();
+        (value >> 1) as i64 ^ -((value & 1) as i64)
+    }
+
+    fn read_struct(&mut self) -> std::collections::BTreeMap<i16, Thrift> {
+        let mut fields = std::collections::BTreeMap::new();
+        let mut last: i16 = 0;
+        loop {
+            let header = self.byte();
+            if header == 0 {
+                return fields;
+            }
+            let id = match header >> 4 {
+                0 => self.zigzag() as i16,
+                delta => last + i16::from(delta),
+            };
+            let value = match header & 0x0f {
+                // Booleans are kept in the field header
+                kind @ (1 | 2) => Thrift::Int(i64::from(kind == 1)),
+                kind => self.read_value(kind),
+            };
+            assert!(fields.insert(id, value).is_none(), "field {} twice", id);
+            last = id;
+        }
+    }
+
+    fn read_value(&mut self, kind: u8) -> Thrift {
+        match kind {
+            1..=3 => Thrift::Int(i64::from(self.byte() as i8)),
+            4..=6 => Thrift::Int(self.zigzag()),
+            8 => {
+                let len = self.varint() as usize;
+                let value = self.bytes[self.position..self.position + len].to_vec();
+                self.position += len;
+                Thrift::Binary(value)
+            }
+            9 => {
+                let header = self.byte();
+                let len = match header >> 4 {
+                    15 => self.varint() as usize,
+                    len => usize::from(len),
+                };
+                Thrift::List((0..len).map(|_| self.read_value(header & 0x0f)).collect())
+            }
+            12 => Thrift::Struct(self.read_struct()),
+            kind => panic!("unexpected Thrift type {}", kind),
+        }
+    }
+}
+
+#[test]
+fn test_sqlite_store() {
+    // Runs are saved through the sqlite3 command
+    if std::process::Command::new("sqlite3")
+        .arg("-version")
+        .output()
+        .is_err()
+    {
+        return;
+    }
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("loadster.db");
+    let db = db_path.to_str().unwrap();
+
+    for samples in [false, true] {
+        let mut cmd = Command::cargo_bin("loadster").unwrap();
+        cmd.args(["http://127.0.0.1:1/it's", "-n", "3", "--sqlite", db]);
+        if samples {
+            cmd.arg("--sqlite-samples");
+        }
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("saved to"));
+    }
+
+    let output = std::process::Command::new("sqlite3")
+        .args([
+            db,
+            "SELECT id, url, total_requests, failed FROM runs; \
+             SELECT run_id, count(*), min(error) FROM samples GROUP BY run_id;",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "1|http://127.0.0.1:1/it's|3|3\n2|http://127.0.0.1:1/it's|3|3\n2|3|connection_refused\n"
+    );
+}
+
+#[test]
+fn test_sqlite_unopenable() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--sqlite",
+        "/nonexistent/loadster.db",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to open SQLite database"));
+}
+
+#[test]
+fn test_trend() {
+    // Runs are saved through the sqlite3 command
+    if std::process::Command::new("sqlite3")
+        .arg("-version")
+        .output()
+        .is_err()
+    {
+        return;
+    }
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("loadster.db");
+    let db = db_path.to_str().unwrap();
+
+    for _ in 0..4 {
+        let mut cmd = Command::cargo_bin("loadster").unwrap();
+        cmd.args([
+            "http://127.0.0.1:1",
+            "-n",
+            "3",
+            "--sqlite",
+            db,
+            "--tag",
+            "api",
+        ]);
+        cmd.assert().success();
+    }
+
+    // A steady trend passes
+    std::process::Command::new("sqlite3")
+        .args([db, "UPDATE runs SET p95_ms = 10, requests_per_sec = 100"])
+        .output()
+        .unwrap();
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["trend", "api", "--sqlite", db]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Trend of 4 runs of api"))
+        .stdout(predicate::str::contains("Run 4 is in line"));
+
+    // The latest run slowing down fails it
+    std::process::Command::new("sqlite3")
+        .args([db, "UPDATE runs SET p95_ms = 20 WHERE id = 4"])
+        .output()
+        .unwrap();
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["trend", "api", "--sqlite", db]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("⚠ p95 +100.0%"))
+        .stdout(predicate::str::contains("Run 4 regressed"));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["trend", "other", "--sqlite", db]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No runs of other"));
+}
+
+#[test]
+fn test_trend_invalid_min_change() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["trend", "api", "--min-change=-5%"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid percentage \"-5%\""))
+        .stderr(predicate::str::contains("error rate").not());
+}
+
+#[test]
+fn test_trend_missing_database() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["trend", "api", "--sqlite", "/nonexistent/loadster.db"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read runs"));
+}
+
+#[test]
+fn test_compare() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_path = temp_dir.path().join("old.json");
+    let new_path = temp_dir.path().join("new.json");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "-n", "4", "-o"])
+        .arg(&old_path);
+    cmd.assert().success();
+
+    // The same run with its p95 doubled and half the throughput
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&old_path).unwrap()).unwrap();
+    json["requests_per_sec"] = serde_json::json!(100.0);
+    json["latency"]["p95_ms"] = serde_json::json!(10.0);
+    fs::write(&old_path, json.to_string()).unwrap();
+    json["requests_per_sec"] = serde_json::json!(50.0);
+    json["latency"]["p95_ms"] = serde_json::json!(20.0);
+    fs::write(&new_path, json.to_string()).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare").arg(&old_path).arg(&old_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Connection refused"))
+        .stdout(predicate::str::contains("No regressions"));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare").arg(&old_path).arg(&new_path);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("-50.0%"))
+        .stdout(predicate::str::contains("+100.0%"))
+        .stdout(predicate::str::contains(
+            "Regressed beyond the tolerances: Requests/sec, Latency p95",
+        ));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare").arg(&old_path).arg(&new_path).args([
+        "--rps-tolerance",
+        "60%",
+        "--latency-tolerance",
+        "150%",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare")
+        .arg(&old_path)
+        .arg(&new_path)
+        .args(["--rps-tolerance", "lots"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid percentage \"lots\""))
+        .stderr(predicate::str::contains("error rate").not());
+}
+
+#[test]
+fn test_compare_old_reports() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_path = temp_dir.path().join("old.json");
+    let new_path = temp_dir.path().join("new.json");
+    fs::write(&old_path, OLD_REPORT).unwrap();
+    fs::write(&new_path, OLD_REPORT.replace("98.088458", "196.176916")).unwrap();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare").arg(&old_path).arg(&old_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("No regressions"));
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.arg("compare").arg(&old_path).arg(&new_path);
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "Regressed beyond the tolerances: Latency p95, Latency p99",
+    ));
+}
+
+#[test]
+fn test_compare_missing_report() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["compare", "/nonexistent/old.json", "/nonexistent/new.json"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read report"));
+}
+
+#[test]
+fn test_html_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let html_path = temp_dir.path().join("report.html");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1/?a=<b>",
+        "-n",
+        "5",
+        "--threshold",
+        "error_rate<1%",
+        "--html",
+        html_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("HTML report saved to"));
+
+    let html = fs::read_to_string(&html_path).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("http://127.0.0.1:1/?a=&lt;b&gt;"));
+    assert!(html.contains("<svg"));
+    assert!(html.contains("<td>Connection refused</td><td>5</td>"));
+    assert!(html.contains("<td>error_rate&lt;1%</td><td>threshold</td>"));
+    assert!(!html.contains("<script"));
+}
+
+#[test]
+fn test_html_report_unwritable() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--html",
+        "/nonexistent/report.html",
+    ]);
+
+    cmd.assert()
+        .stderr(predicate::str::contains("Failed to save HTML report"));
+}
+
+#[test]
+fn test_markdown_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let markdown_path = temp_dir.path().join("report.md");
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "4",
+        "--threshold",
+        "error_rate<1%",
+        "--threshold",
+        "p99<10s",
+        "--markdown",
+        markdown_path.to_str().unwrap(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("Markdown report saved to"));
+
+    let markdown = fs::read_to_string(&markdown_path).unwrap();
+    assert!(markdown.starts_with("## Load test of `http://127.0.0.1:1`"));
+    assert!(markdown.contains("| 4 | 0 | 4 | 100.00% |"));
+    assert!(markdown.contains("| Connection refused | 4 | 100.00% |"));
+    assert!(markdown.contains("| ❌ | `error_rate<1%` | 100.00% |"));
+    assert!(markdown.contains("| ✅ | `p99<10s` |"));
+    assert!(markdown.contains("**❌ Some thresholds failed**"));
+}
+
+#[test]
+fn test_markdown_to_stdout() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "2",
+        "--percentiles",
+        "90,99.9",
+        "--quiet",
+        "--markdown",
+        "-",
+    ]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.starts_with("## Load test of"));
+    assert!(stdout.contains("| avg | p90 | p99.9 | max |"));
+    assert!(!stdout.contains("Total requests"));
+}
+
+#[test]
+fn test_metrics_endpoint() {
+    use std::io::{Read, Write};
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{}", port);
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_loadster"))
+        .args([
+            "http://127.0.0.1:1",
+            "-z",
+            "3s",
+            "--rate",
+            "50",
+            "--metrics-addr",
+            addr.as_str(),
+        ])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // Scraped while the run goes on, once a request has finished
+    let mut metrics = String::new();
+    for _ in 0..40 {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let Ok(mut stream) = std::net::TcpStream::connect(&addr) else {
+            continue;
+        };
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        metrics.clear();
+        stream.read_to_string(&mut metrics).unwrap();
+        if !metrics.contains("loadster_requests_total 0\n") {
+            break;
+        }
+    }
+    let status = child.wait().unwrap();
+
+    assert!(status.success());
+    assert!(metrics.starts_with("HTTP/1.1 200 OK"));
+    assert!(metrics.contains("# TYPE loadster_request_duration_seconds histogram"));
+    assert!(metrics.contains("loadster_errors_by_kind_total{error=\"connection_refused\"}"));
+    assert!(metrics.contains("loadster_request_duration_seconds_bucket{le=\"+Inf\"}"));
+    assert!(!metrics.contains("loadster_requests_total 0\n"));
+    // Nothing is served once the run is over
+    assert!(std::net::TcpStream::connect(&addr).is_err());
+}
+
+#[test]
+fn test_metrics_addr_in_use() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--metrics-addr",
+        addr.as_str(),
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to serve metrics"));
+}
+
+/// Lines of the packets sent to `socket` until none come for a moment
+fn statsd_packets(socket: &std::net::UdpSocket) -> String {
+    let mut packets = String::new();
+    let mut buffer = [0; 2048];
+    while let Ok(read) = socket.recv(&mut buffer) {
+        packets.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        packets.push('\n');
+        socket
+            .set_read_timeout(Some(std::time::Duration::from_millis(200)))
+            .unwrap();
+    }
+    packets
+}
+
+#[test]
+fn test_statsd() {
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let addr = socket.local_addr().unwrap().to_string();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--statsd",
+        addr.as_str(),
+    ]);
+    cmd.assert().success();
+
+    let packets = statsd_packets(&socket);
+    let lines: Vec<&str> = packets.lines().collect();
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| **line == "loadster.requests:1|c")
+            .count(),
+        3
+    );
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| **line == "loadster.errors.connection_refused:1|c")
+            .count(),
+        3
+    );
+    assert!(lines.iter().any(|line| {
+        line.strip_prefix("loadster.request.duration:")
+            .is_some_and(|value| value.ends_with("|ms"))
+    }));
+}
+
+#[test]
+fn test_dogstatsd_tags() {
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let addr = socket.local_addr().unwrap().to_string();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--statsd",
+        addr.as_str(),
+        "--dogstatsd",
+        "--statsd-tag",
+        "env:ci",
+        "--tag",
+        "checkout",
+    ]);
+    cmd.assert().success();
+
+    let packets = statsd_packets(&socket);
+    assert!(packets.contains("loadster.requests:1|c|#env:ci,test:checkout\n"));
+    assert!(packets.contains("loadster.errors:1|c|#env:ci,test:checkout,error:connection_refused"));
+}
+
+#[test]
+fn test_invalid_statsd_tag() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "--statsd",
+        "localhost:8125",
+        "--dogstatsd",
+        "--statsd-tag",
+        "env:a,b",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid tag"));
+}
+
+/// Answers like InfluxDB on a port of its own, keeping every request's
+/// line and body
+fn fake_influx() -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let kept = std::sync::Arc::clone(&requests);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    } else if name.eq_ignore_ascii_case("authorization") {
+                        request.push_str(&line);
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            kept.lock().unwrap().push(request);
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        }
+    });
+    (url, requests)
+}
+
+#[test]
+fn test_influx() {
+    let (url, requests) = fake_influx();
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1/",
+        "-n",
+        "3",
+        "-c",
+        "1",
+        "--tag",
+        "check out",
+        "--influx",
+        url.as_str(),
+        "--influx-bucket",
+        "loads",
+        "--influx-org",
+        "acme",
+        "--influx-token",
+        "s3cret",
+        "--influx-samples",
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "points written to InfluxDB bucket: loads",
+    ));
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("GET /ping "));
+    let writes: Vec<&String> = requests[1..].iter().collect();
+    assert!(!writes.is_empty());
+    assert!(writes.iter().all(|write| {
+        write.starts_with("POST /api/v2/write?bucket=loads&org=acme&precision=ns ")
+            && write.contains("Token s3cret")
+    }));
+    let lines: Vec<&str> = writes
+        .iter()
+        .flat_map(|write| write.lines())
+        .filter(|line| line.starts_with("loadster"))
+        .collect();
+    let samples: Vec<&&str> = lines
+        .iter()
+        .filter(|line| line.starts_with("loadster_sample,"))
+        .collect();
+    assert_eq!(samples.len(), 3);
+    assert!(samples.iter().all(|line| line.starts_with(
+        "loadster_sample,target=http://127.0.0.1:1/,test=check\\ out,error=connection_refused latency_ms="
+    )));
+    // The requests of each second they finished in, which may be two
+    let requests: u64 = lines
+        .iter()
+        .filter(|line| line.starts_with("loadster,"))
+        .map(|line| {
+            assert!(line.contains("errors="));
+            assert!(line.contains("p95_ms="));
+            let fields = line.split(' ').nth(2).unwrap();
+            let count = fields.split(',').next().unwrap();
+            count["requests=".len()..count.len() - 1]
+                .parse::<u64>()
+                .unwrap()
+        })
+        .sum();
+    assert_eq!(requests, 3);
+}
+
+#[test]
+fn test_influx_unreachable() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let url = format!("http://127.0.0.1:{}", port);
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--influx",
+        url.as_str(),
+        "--influx-bucket",
+        "loads",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to reach InfluxDB"));
+}
+
+#[test]
+fn test_pushgateway() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway = format!("http://{}/", listener.local_addr().unwrap());
+    // Takes one push and answers it like a Pushgateway
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        (request_line, String::from_utf8(body).unwrap())
+    });
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1/",
+        "-n",
+        "3",
+        "--tag",
+        "api/checkout",
+        "--pushgateway",
+        gateway.as_str(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Results pushed to:"));
+    let (request_line, body) = server.join().unwrap();
+    // "api/checkout" and "http://127.0.0.1:1/", base64url-encoded
+    assert!(request_line.starts_with(
+        "PUT /metrics/job/loadster/test@base64/YXBpL2NoZWNrb3V0/target@base64/aHR0cDovLzEyNy4wLjAuMToxLw "
+    ));
+    assert!(body.contains("# TYPE loadster_run_requests gauge\nloadster_run_requests 3\n"));
+    assert!(body.contains("loadster_run_error_rate 1\n"));
+    assert!(body.contains("loadster_run_errors{error=\"connection_refused\"} 3\n"));
+    assert!(body.contains("loadster_run_latency_seconds{quantile=\"0.95\"}"));
+    assert!(body.contains("loadster_run_passed 1\n"));
+}
+
+#[test]
+fn test_pushgateway_unreachable() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let gateway = format!("http://127.0.0.1:{}", port);
+
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args([
+        "http://127.0.0.1:1",
+        "-n",
+        "1",
+        "--pushgateway",
+        gateway.as_str(),
+    ]);
+
+    // The run itself still passes
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Failed to push results to"));
+}
+
+#[test]
+fn test_invalid_pushgateway() {
+    let mut cmd = Command::cargo_bin("loadster").unwrap();
+    cmd.args(["http://127.0.0.1:1", "--pushgateway", "localhost:9091"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid Pushgateway URL"));
+}